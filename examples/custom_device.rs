@@ -0,0 +1,166 @@
+//! A device with a class of its own: a block of gain controls next to the managers, assembled
+//! with the model builder. Run with `cargo run --example custom_device -- --port 3100`.
+
+use clap::Parser;
+use nmos_control_rusty_device::{
+    DeviceServer, MemberBuilder, ModelBuilder, NcMember,
+    config::{Cli, Config},
+    data_types::{
+        IdArgs, IdArgsValue, NcClassDescriptor, NcDescriptor, NcElementId, NcMethodStatus,
+        NcPropertyChangeType, NcPropertyDescriptor, PropertyChangedEvent, PropertyChangedEventData,
+    },
+    nc_worker::NcWorker,
+};
+use serde_json::{Value, json};
+use std::any::Any;
+
+const GAIN_CONTROL_CLASS_ID: [u32; 4] = [1, 2, 0, 1];
+
+/// Gain of an audio path in dB
+struct GainControl {
+    base: NcWorker,
+    gain: f64,
+}
+
+impl GainControl {
+    fn class_descriptor() -> NcClassDescriptor {
+        NcClassDescriptor {
+            base: NcDescriptor {
+                description: Some("Gain control".to_string()),
+            },
+            class_id: GAIN_CONTROL_CLASS_ID.to_vec(),
+            name: "GainControl".to_string(),
+            fixed_role: None,
+            properties: vec![NcPropertyDescriptor {
+                base: NcDescriptor {
+                    description: Some("Gain in dB".to_string()),
+                },
+                id: NcElementId { level: 3, index: 1 },
+                name: "gain".to_string(),
+                type_name: Some("NcFloat64".to_string()),
+                is_read_only: false,
+                is_nullable: false,
+                is_sequence: false,
+                is_deprecated: false,
+                constraints: None,
+            }],
+            methods: vec![],
+            events: vec![],
+        }
+    }
+}
+
+impl NcMember for GainControl {
+    fn member_type(&self) -> &'static str {
+        "GainControl"
+    }
+
+    fn get_role(&self) -> &str {
+        self.base.get_role()
+    }
+
+    fn get_oid(&self) -> u64 {
+        self.base.get_oid()
+    }
+
+    fn get_constant_oid(&self) -> bool {
+        self.base.get_constant_oid()
+    }
+
+    fn get_class_id(&self) -> &[u32] {
+        self.base.get_class_id()
+    }
+
+    fn get_user_label(&self) -> Option<&str> {
+        self.base.get_user_label()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn get_property(&self, oid: u64, id_args: &IdArgs) -> (Option<String>, Value, NcMethodStatus) {
+        if id_args.id.level == 3 && id_args.id.index == 1 {
+            return (None, json!(self.gain), NcMethodStatus::Ok);
+        }
+        self.base.get_property(oid, id_args)
+    }
+
+    fn set_property(
+        &mut self,
+        oid: u64,
+        id_args_value: IdArgsValue,
+    ) -> (Option<String>, NcMethodStatus) {
+        if id_args_value.id.level == 3 && id_args_value.id.index == 1 {
+            let Some(gain) = id_args_value.value.as_f64() else {
+                return (
+                    Some("Property value was invalid".to_string()),
+                    NcMethodStatus::ParameterError,
+                );
+            };
+            self.gain = gain;
+            let _ = self.base.base.notifier.send(PropertyChangedEvent::new(
+                oid,
+                PropertyChangedEventData {
+                    property_id: id_args_value.id,
+                    change_type: NcPropertyChangeType::ValueChanged,
+                    value: json!(gain),
+                    sequence_item_index: None,
+                },
+            ));
+            return (None, NcMethodStatus::Ok);
+        }
+        self.base.set_property(oid, id_args_value)
+    }
+
+    fn invoke_method(
+        &self,
+        oid: u64,
+        method_id: NcElementId,
+        args: Value,
+    ) -> (Option<String>, Option<Value>, NcMethodStatus) {
+        self.base.invoke_method(oid, method_id, args)
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(&cli)?;
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::new(&config.log_filter))
+        .init();
+
+    let model = ModelBuilder::new()
+        .class(GainControl::class_descriptor(), |ctx| {
+            Box::new(GainControl {
+                base: ctx.worker(),
+                gain: 0.0,
+            })
+        })
+        .member(MemberBuilder::new(
+            "NcBulkPropertiesManager",
+            "BulkPropertiesManager",
+        ))
+        .member(
+            MemberBuilder::block("outputs")
+                .user_label("Outputs")
+                .member(
+                    MemberBuilder::new("GainControl", "gain-1")
+                        .user_label("Output 1 gain")
+                        .property(NcElementId { level: 3, index: 1 }, json!(-3.0)),
+                )
+                .member(MemberBuilder::new("GainControl", "gain-2").user_label("Output 2 gain")),
+        );
+
+    DeviceServer::builder(config)
+        .model_builder(model)
+        .build()
+        .await?
+        .serve()
+        .await
+}
@@ -0,0 +1,317 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use crate::{
+    AppState,
+    channel_mapping::CHANNEL_MAPPING_API_BASE,
+    configuration::CONFIGURATION_API_BASE,
+    data_types::{DeviceControl, NmosEndpoint, NmosInterface},
+    host::{HostInterface, discover_interfaces, endpoint_addresses, nmos_interfaces},
+};
+
+/// How clients reach the APIs of this node, everything the hrefs of the IS-04 resources are
+/// built from
+#[derive(Debug, Clone)]
+pub struct Advertisement {
+    /// Address the server is actually listening on
+    pub local_addr: SocketAddr,
+    pub tls: bool,
+    pub authorization: bool,
+    /// Configured host name, advertised before the discovered addresses
+    pub advertised_host: Option<String>,
+    /// Interfaces given in the configuration, None when they are discovered
+    pub configured_interfaces: Option<Vec<NmosInterface>>,
+}
+
+/// Hosts and interfaces advertised at one point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdvertisedHosts {
+    /// Host names and addresses, never empty
+    pub hosts: Vec<String>,
+    pub interfaces: Vec<NmosInterface>,
+}
+
+impl Advertisement {
+    /// Discovers the interfaces of the host and the addresses the listener is reachable on
+    pub fn discover(&self) -> AdvertisedHosts {
+        let mut host_interfaces = discover_interfaces();
+        let interfaces = match &self.configured_interfaces {
+            Some(interfaces) => {
+                host_interfaces.retain(|h| interfaces.iter().any(|i| i.name == h.name));
+                interfaces.clone()
+            }
+            None => nmos_interfaces(&host_interfaces),
+        };
+        AdvertisedHosts {
+            hosts: self.hosts(&host_interfaces),
+            interfaces,
+        }
+    }
+
+    fn hosts(&self, interfaces: &[HostInterface]) -> Vec<String> {
+        let mut hosts: Vec<String> = self.advertised_host.iter().cloned().collect();
+        for address in endpoint_addresses(self.local_addr.ip(), interfaces) {
+            let address = address.to_string();
+            if !hosts.contains(&address) {
+                hosts.push(address);
+            }
+        }
+        if hosts.is_empty() {
+            hosts.push(match self.local_addr.ip().is_unspecified() {
+                true => "127.0.0.1".to_string(),
+                false => self.local_addr.ip().to_string(),
+            });
+        }
+        hosts
+    }
+
+    fn http_scheme(&self) -> &'static str {
+        if self.tls { "https" } else { "http" }
+    }
+
+    fn ws_scheme(&self) -> &'static str {
+        if self.tls { "wss" } else { "ws" }
+    }
+
+    /// `host:port` for URLs, with IPv6 addresses in brackets
+    fn authority(&self, host: &str) -> String {
+        match host.contains(':') {
+            true => format!("[{host}]:{}", self.local_addr.port()),
+            false => format!("{host}:{}", self.local_addr.port()),
+        }
+    }
+
+    /// Base URL of the node, on the first advertised host
+    pub fn node_href(&self, hosts: &AdvertisedHosts) -> String {
+        format!(
+            "{}://{}",
+            self.http_scheme(),
+            self.authority(&hosts.hosts[0])
+        )
+    }
+
+    /// One Node API endpoint per advertised host
+    pub fn endpoints(&self, hosts: &AdvertisedHosts) -> Vec<NmosEndpoint> {
+        hosts
+            .hosts
+            .iter()
+            .map(|host| NmosEndpoint {
+                host: host.clone(),
+                port: self.local_addr.port().into(),
+                protocol: self.http_scheme().into(),
+                authorization: self.authorization,
+            })
+            .collect()
+    }
+
+    /// The IS-12 WebSocket, the configuration API and the channel mapping API on every
+    /// advertised host
+    pub fn device_controls(&self, hosts: &AdvertisedHosts) -> Vec<DeviceControl> {
+        let ncp = hosts.hosts.iter().map(|host| DeviceControl {
+            type_: "urn:x-nmos:control:ncp/v1.0".into(),
+            href: format!("{}://{}/ws", self.ws_scheme(), self.authority(host)),
+            authorization: self.authorization,
+        });
+        let configuration = hosts.hosts.iter().map(|host| DeviceControl {
+            type_: "urn:x-nmos:control:configuration/v1.0".into(),
+            href: format!(
+                "{}://{}{CONFIGURATION_API_BASE}/",
+                self.http_scheme(),
+                self.authority(host)
+            ),
+            authorization: self.authorization,
+        });
+        let channel_mapping = hosts.hosts.iter().map(|host| DeviceControl {
+            type_: "urn:x-nmos:control:cm-ctrl/v1.0".into(),
+            href: format!(
+                "{}://{}{CHANNEL_MAPPING_API_BASE}/",
+                self.http_scheme(),
+                self.authority(host)
+            ),
+            authorization: self.authorization,
+        });
+        ncp.chain(configuration).chain(channel_mapping).collect()
+    }
+}
+
+/// Checks the host addresses periodically, giving the node and the device new endpoints,
+/// controls and versions when they change. There is no registry to update: the node is not
+/// registered anywhere, clients see the changes through the Node API.
+pub async fn refresh_advertisement(
+    state: Arc<AppState>,
+    advertisement: Advertisement,
+    mut current: AdvertisedHosts,
+    period: Duration,
+) {
+    let mut interval = tokio::time::interval(period);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let discovery = advertisement.clone();
+        let Ok(hosts) = tokio::task::spawn_blocking(move || discovery.discover()).await else {
+            continue;
+        };
+        if hosts == current {
+            continue;
+        }
+        tracing::info!(hosts = ?hosts.hosts, "advertised addresses changed");
+
+        let mut resources = state.resources.write().await;
+        resources.update_node(|node| {
+            node.href = advertisement.node_href(&hosts);
+            node.api.endpoints = advertisement.endpoints(&hosts);
+            node.interfaces = hosts.interfaces.clone();
+        });
+        if hosts.hosts != current.hosts {
+            resources.update_device(|device| {
+                device.controls = advertisement.device_controls(&hosts);
+            });
+        }
+        current = hosts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    fn advertisement(local_addr: &str, tls: bool) -> Advertisement {
+        Advertisement {
+            local_addr: local_addr.parse().unwrap(),
+            tls,
+            authorization: false,
+            advertised_host: None,
+            configured_interfaces: None,
+        }
+    }
+
+    fn interface(addresses: &[IpAddr]) -> HostInterface {
+        HostInterface {
+            name: "eth0".to_string(),
+            mac: "00-15-5d-67-c3-4e".to_string(),
+            addresses: addresses.to_vec(),
+        }
+    }
+
+    fn hosts(hosts: &[&str]) -> AdvertisedHosts {
+        AdvertisedHosts {
+            hosts: hosts.iter().map(|host| host.to_string()).collect(),
+            interfaces: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn ipv6_hosts_are_bracketed() {
+        let advertisement = advertisement("[::]:3000", false);
+        assert_eq!(advertisement.authority("192.168.1.10"), "192.168.1.10:3000");
+        assert_eq!(advertisement.authority("fd00::10"), "[fd00::10]:3000");
+        assert_eq!(
+            advertisement.authority("device.example.com"),
+            "device.example.com:3000"
+        );
+    }
+
+    #[test]
+    fn schemes_follow_tls() {
+        let plain = advertisement("0.0.0.0:3000", false);
+        assert_eq!((plain.http_scheme(), plain.ws_scheme()), ("http", "ws"));
+        assert_eq!(
+            plain.node_href(&hosts(&["10.0.0.1"])),
+            "http://10.0.0.1:3000"
+        );
+
+        let tls = advertisement("0.0.0.0:3443", true);
+        assert_eq!((tls.http_scheme(), tls.ws_scheme()), ("https", "wss"));
+        assert_eq!(
+            tls.node_href(&hosts(&["10.0.0.1"])),
+            "https://10.0.0.1:3443"
+        );
+        let endpoint = &tls.endpoints(&hosts(&["10.0.0.1"]))[0];
+        assert_eq!(
+            (
+                endpoint.host.as_str(),
+                endpoint.port,
+                endpoint.protocol.as_str()
+            ),
+            ("10.0.0.1", 3443, "https")
+        );
+    }
+
+    #[test]
+    fn hosts_of_an_unspecified_bind_address() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10));
+        let v6 = IpAddr::V6("fd00::10".parse().unwrap());
+        let link_local = IpAddr::V6("fe80::1".parse().unwrap());
+
+        // Without any interface address the loopback address is advertised
+        let unspecified = advertisement("0.0.0.0:3000", false);
+        assert_eq!(unspecified.hosts(&[]), vec!["127.0.0.1"]);
+        assert_eq!(
+            unspecified.hosts(&[interface(&[v4, v6])]),
+            vec!["192.168.1.10"]
+        );
+
+        // An IPv6 listener also advertises the routable IPv6 addresses
+        let dual_stack = advertisement("[::]:3000", false);
+        assert_eq!(
+            dual_stack.hosts(&[interface(&[v4, v6, link_local])]),
+            vec!["192.168.1.10", "fd00::10"]
+        );
+
+        // A specific bind address is the only address advertised
+        let bound = advertisement("10.0.0.5:3000", false);
+        assert_eq!(bound.hosts(&[interface(&[v4])]), vec!["10.0.0.5"]);
+        let loopback = advertisement("[::1]:3000", false);
+        assert_eq!(loopback.hosts(&[]), vec![Ipv6Addr::LOCALHOST.to_string()]);
+
+        // The configured host name comes first and addresses are not repeated
+        let mut named = advertisement("10.0.0.5:3000", false);
+        named.advertised_host = Some("device.example.com".to_string());
+        assert_eq!(
+            named.hosts(&[interface(&[v4])]),
+            vec!["device.example.com", "10.0.0.5"]
+        );
+        named.advertised_host = Some("10.0.0.5".to_string());
+        assert_eq!(named.hosts(&[]), vec!["10.0.0.5"]);
+    }
+
+    #[test]
+    fn device_controls_cover_every_api_on_every_host() {
+        let mut advertisement = advertisement("[::]:3443", true);
+        advertisement.authorization = true;
+        let controls = advertisement.device_controls(&hosts(&["10.0.0.1", "fd00::10"]));
+        let controls: Vec<(&str, &str)> = controls
+            .iter()
+            .map(|control| (control.type_.as_str(), control.href.as_str()))
+            .collect();
+        assert_eq!(
+            controls,
+            vec![
+                ("urn:x-nmos:control:ncp/v1.0", "wss://10.0.0.1:3443/ws"),
+                ("urn:x-nmos:control:ncp/v1.0", "wss://[fd00::10]:3443/ws"),
+                (
+                    "urn:x-nmos:control:configuration/v1.0",
+                    "https://10.0.0.1:3443/x-nmos/configuration/v1.0/"
+                ),
+                (
+                    "urn:x-nmos:control:configuration/v1.0",
+                    "https://[fd00::10]:3443/x-nmos/configuration/v1.0/"
+                ),
+                (
+                    "urn:x-nmos:control:cm-ctrl/v1.0",
+                    "https://10.0.0.1:3443/x-nmos/channelmapping/v1.0/"
+                ),
+                (
+                    "urn:x-nmos:control:cm-ctrl/v1.0",
+                    "https://[fd00::10]:3443/x-nmos/channelmapping/v1.0/"
+                ),
+            ]
+        );
+        assert!(
+            advertisement
+                .device_controls(&hosts(&["10.0.0.1"]))
+                .iter()
+                .all(|control| control.authorization)
+        );
+    }
+}
@@ -0,0 +1,927 @@
+use axum::{
+    Json,
+    extract::{Path, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use uuid::Uuid;
+
+use crate::{
+    AppState, auth::bearer_token, data_types::NmosSourceFormat, node_api::is_04_error,
+    node_resources::NodeResources, tai,
+};
+
+/// Versioned base path of the IS-08 Audio Channel Mapping API
+pub const CHANNEL_MAPPING_API_BASE: &str = "/x-nmos/channelmapping/v1.0";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IoProperties {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IoChannel {
+    pub label: String,
+}
+
+/// IS-04 resource an input takes its audio from
+#[derive(Debug, Clone, Serialize)]
+pub struct InputParent {
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InputCaps {
+    pub reordering: bool,
+    pub block_size: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputCaps {
+    /// Inputs the output may be routed from, null entries allowing unrouted channels. Any input
+    /// when None
+    pub routable_inputs: Option<Vec<Option<String>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelMappingInput {
+    #[serde(skip)]
+    pub id: String,
+    pub properties: IoProperties,
+    pub parent: InputParent,
+    pub channels: Vec<IoChannel>,
+    pub caps: InputCaps,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelMappingOutput {
+    #[serde(skip)]
+    pub id: String,
+    pub properties: IoProperties,
+    pub source_id: Option<String>,
+    pub channels: Vec<IoChannel>,
+    pub caps: OutputCaps,
+}
+
+/// The input channel an output channel carries, both null while it is unrouted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelRoute {
+    pub input: Option<String>,
+    pub channel_index: Option<usize>,
+}
+
+/// Routes per output id and output channel index
+pub type ChannelMap = BTreeMap<String, BTreeMap<usize, ChannelRoute>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActivationMode {
+    #[serde(rename = "activate_immediate")]
+    Immediate,
+    #[serde(rename = "activate_scheduled_absolute")]
+    ScheduledAbsolute,
+    #[serde(rename = "activate_scheduled_relative")]
+    ScheduledRelative,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Activation {
+    pub mode: Option<ActivationMode>,
+    /// TAI timestamp for absolute, TAI duration for relative activations
+    pub requested_time: Option<String>,
+    #[serde(default)]
+    pub activation_time: Option<String>,
+}
+
+/// Body of a `map/activations` request
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ActivationRequest {
+    pub activation: Activation,
+    pub action: ChannelMap,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledActivation {
+    pub activation: Activation,
+    pub action: ChannelMap,
+}
+
+/// A rejected request, answered with the IS-08 error body
+#[derive(Debug)]
+pub struct ChannelMappingError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ChannelMappingError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ChannelMappingError {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+impl IntoResponse for ChannelMappingError {
+    fn into_response(self) -> Response {
+        is_04_error(self.status, self.message)
+    }
+}
+
+/// In-memory audio router of the device: its inputs and outputs, the active map and the
+/// activations still waiting for their time
+#[derive(Debug, Default)]
+pub struct ChannelMapping {
+    inputs: Vec<ChannelMappingInput>,
+    outputs: Vec<ChannelMappingOutput>,
+    active: ChannelMap,
+    last_activation: Activation,
+    scheduled: BTreeMap<String, ScheduledActivation>,
+}
+
+impl ChannelMapping {
+    pub fn inputs(&self) -> &[ChannelMappingInput] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[ChannelMappingOutput] {
+        &self.outputs
+    }
+
+    pub fn find_input(&self, id: &str) -> Option<&ChannelMappingInput> {
+        self.inputs.iter().find(|input| input.id == id)
+    }
+
+    pub fn find_output(&self, id: &str) -> Option<&ChannelMappingOutput> {
+        self.outputs.iter().find(|output| output.id == id)
+    }
+
+    pub fn add_input(&mut self, input: ChannelMappingInput) {
+        self.inputs.push(input);
+    }
+
+    /// Adds an output with all of its channels unrouted
+    pub fn add_output(&mut self, output: ChannelMappingOutput) {
+        let channels = (0..output.channels.len())
+            .map(|index| (index, ChannelRoute::default()))
+            .collect();
+        self.active.insert(output.id.clone(), channels);
+        self.outputs.push(output);
+    }
+
+    /// Checks an action against the inputs and outputs and their capabilities
+    fn validate(&self, action: &ChannelMap) -> Result<(), ChannelMappingError> {
+        for (output_id, routes) in action {
+            let output = self.find_output(output_id).ok_or_else(|| {
+                ChannelMappingError::bad_request(format!("No output {output_id}"))
+            })?;
+            if self
+                .scheduled
+                .values()
+                .any(|scheduled| scheduled.action.contains_key(output_id))
+            {
+                return Err(ChannelMappingError::new(
+                    StatusCode::LOCKED,
+                    format!("Output {output_id} is part of a scheduled activation"),
+                ));
+            }
+            for (channel, route) in routes {
+                if *channel >= output.channels.len() {
+                    return Err(ChannelMappingError::bad_request(format!(
+                        "Output {output_id} has no channel {channel}"
+                    )));
+                }
+                if let Some(routable) = &output.caps.routable_inputs
+                    && !routable.contains(&route.input)
+                {
+                    return Err(ChannelMappingError::bad_request(format!(
+                        "Output {output_id} cannot be routed from {:?}",
+                        route.input
+                    )));
+                }
+                match (&route.input, route.channel_index) {
+                    (None, None) => {}
+                    (Some(input_id), Some(index)) => {
+                        let input = self.find_input(input_id).ok_or_else(|| {
+                            ChannelMappingError::bad_request(format!("No input {input_id}"))
+                        })?;
+                        if index >= input.channels.len() {
+                            return Err(ChannelMappingError::bad_request(format!(
+                                "Input {input_id} has no channel {index}"
+                            )));
+                        }
+                    }
+                    _ => {
+                        return Err(ChannelMappingError::bad_request(
+                            "input and channel_index must both be set or both be null",
+                        ));
+                    }
+                }
+            }
+
+            // Capabilities are checked on the map the output ends up with
+            let mut routes_after = self.active.get(output_id).cloned().unwrap_or_default();
+            routes_after.extend(routes.iter().map(|(c, r)| (*c, r.clone())));
+            self.check_input_caps(output_id, &routes_after)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the routes of an output against the caps of its inputs: the channels of an input
+    /// without reordering keep their order (a higher output channel carries a higher input
+    /// channel, channels may be left out), and an input with a block size is routed in whole
+    /// blocks, each block to consecutive output channels in order
+    fn check_input_caps(
+        &self,
+        output_id: &str,
+        routes: &BTreeMap<usize, ChannelRoute>,
+    ) -> Result<(), ChannelMappingError> {
+        for input in &self.inputs {
+            // (output channel, input channel) in output channel order
+            let routed: Vec<(usize, usize)> = routes
+                .iter()
+                .filter(|(_, route)| route.input.as_deref() == Some(input.id.as_str()))
+                .filter_map(|(channel, route)| Some((*channel, route.channel_index?)))
+                .collect();
+
+            if !input.caps.reordering && routed.windows(2).any(|pair| pair[1].1 <= pair[0].1) {
+                return Err(ChannelMappingError::bad_request(format!(
+                    "Input {} does not support reordering, output {output_id} must keep the \
+                     order of its channels",
+                    input.id
+                )));
+            }
+
+            let block_size = input.caps.block_size.max(1);
+            for (channel, index) in &routed {
+                let offset = index % block_size;
+                let whole_block = channel.checked_sub(offset).is_some_and(|first| {
+                    (0..block_size).all(|k| {
+                        routes.get(&(first + k))
+                            == Some(&ChannelRoute {
+                                input: Some(input.id.clone()),
+                                channel_index: Some(index - offset + k),
+                            })
+                    })
+                });
+                if !whole_block {
+                    return Err(ChannelMappingError::bad_request(format!(
+                        "Input {} must be routed to output {output_id} in blocks of \
+                         {block_size} channels",
+                        input.id
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, activation: Activation, action: &ChannelMap) {
+        for (output_id, routes) in action {
+            if let Some(active) = self.active.get_mut(output_id) {
+                active.extend(routes.iter().map(|(c, r)| (*c, r.clone())));
+            }
+        }
+        tracing::info!(outputs = ?action.keys().collect::<Vec<_>>(), "channel map activated");
+        self.last_activation = activation;
+    }
+
+    /// Takes a scheduled activation out of the schedule and applies it, unless the map it was
+    /// validated against has changed in a way that makes it invalid. None when it was cancelled
+    fn apply_scheduled(&mut self, id: &str) -> Option<Result<(), ChannelMappingError>> {
+        let scheduled = self.scheduled.remove(id)?;
+        Some(
+            self.validate(&scheduled.action)
+                .map(|()| self.apply(scheduled.activation, &scheduled.action)),
+        )
+    }
+}
+
+/// Registers example inputs and outputs for the built-in model: the example audio receiver and a
+/// test tone generator feeding the example audio sender
+pub fn build_default_channel_mapping(
+    channel_mapping: &mut ChannelMapping,
+    resources: &NodeResources,
+) {
+    let audio_receiver = resources
+        .receivers()
+        .iter()
+        .find(|receiver| receiver.format == "urn:x-nmos:format:audio");
+    let audio_source = resources
+        .sources()
+        .iter()
+        .find(|source| matches!(source.format, NmosSourceFormat::Audio { .. }))
+        .map(|source| source.base.id.clone());
+    let stereo = || {
+        ["Left", "Right"]
+            .into_iter()
+            .map(|label| IoChannel {
+                label: label.into(),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    channel_mapping.add_input(ChannelMappingInput {
+        id: "receiver-audio".into(),
+        properties: IoProperties {
+            name: "Audio receiver".into(),
+            description: "Channels of the example audio receiver".into(),
+        },
+        parent: InputParent {
+            id: audio_receiver.map(|receiver| receiver.base.id.clone()),
+            type_: audio_receiver.map(|_| "receiver".into()),
+        },
+        channels: stereo(),
+        caps: InputCaps {
+            reordering: true,
+            block_size: 1,
+        },
+    });
+    channel_mapping.add_input(ChannelMappingInput {
+        id: "test-tone".into(),
+        properties: IoProperties {
+            name: "Test tone".into(),
+            description: "1 kHz line-up tone".into(),
+        },
+        parent: InputParent {
+            id: None,
+            type_: None,
+        },
+        channels: stereo(),
+        caps: InputCaps {
+            reordering: false,
+            block_size: 1,
+        },
+    });
+    channel_mapping.add_output(ChannelMappingOutput {
+        id: "sender-audio".into(),
+        properties: IoProperties {
+            name: "Audio sender".into(),
+            description: "Channels of the example audio sender".into(),
+        },
+        source_id: audio_source,
+        channels: stereo(),
+        caps: OutputCaps {
+            routable_inputs: None,
+        },
+    });
+
+    // Passes the receiver through to the sender until a controller changes the map
+    let passthrough = (0..2)
+        .map(|index| {
+            (
+                index,
+                ChannelRoute {
+                    input: Some("receiver-audio".into()),
+                    channel_index: Some(index),
+                },
+            )
+        })
+        .collect();
+    channel_mapping.apply(
+        Activation::default(),
+        &ChannelMap::from([("sender-audio".into(), passthrough)]),
+    );
+}
+
+/// Parses `<seconds>:<nanoseconds>` TAI timestamps and durations
+fn parse_tai(text: &str) -> Option<Duration> {
+    let (seconds, nanos) = text.split_once(':')?;
+    let nanos: u32 = nanos.parse().ok()?;
+    (nanos < 1_000_000_000).then_some(Duration::new(seconds.parse().ok()?, nanos))
+}
+
+fn format_tai(time: Duration) -> String {
+    format!("{}:{}", time.as_secs(), time.subsec_nanos())
+}
+
+/// Requires read access for GET requests and write access for anything else when
+/// authorization is enabled
+pub async fn channel_mapping_auth_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(auth) = &state.auth {
+        let path = request
+            .uri()
+            .path()
+            .trim_start_matches(CHANNEL_MAPPING_API_BASE)
+            .trim_matches('/');
+        let write = request.method() != Method::GET && request.method() != Method::HEAD;
+        let token = bearer_token(request.headers(), request.uri().query());
+        if let Err(err) = auth.authorize_channel_mapping(token, path, write) {
+            return err.into_response();
+        }
+    }
+    next.run(request).await
+}
+
+pub async fn versions_listing_handler() -> impl IntoResponse {
+    Json(json!(["v1.0/"]))
+}
+
+pub async fn base_handler() -> impl IntoResponse {
+    Json(json!(["inputs/", "outputs/", "map/", "io/"]))
+}
+
+pub async fn map_handler() -> impl IntoResponse {
+    Json(json!(["activations/", "active/"]))
+}
+
+pub async fn inputs_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let channel_mapping = state.channel_mapping.read().await;
+    let ids: Vec<String> = channel_mapping
+        .inputs()
+        .iter()
+        .map(|input| format!("{}/", input.id))
+        .collect();
+    Json(json!(ids))
+}
+
+pub async fn input_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, ChannelMappingError> {
+    find_input(&*state.channel_mapping.read().await, &id)?;
+    Ok(Json(json!(["caps/", "channels/", "parent/", "properties/"])).into_response())
+}
+
+/// One attribute of an input (`properties`, `parent`, `channels` or `caps`)
+pub async fn input_attribute_handler(
+    State(state): State<Arc<AppState>>,
+    Path((id, attribute)): Path<(String, String)>,
+) -> Result<Response, ChannelMappingError> {
+    let channel_mapping = state.channel_mapping.read().await;
+    let input = find_input(&channel_mapping, &id)?;
+    let value = match attribute.as_str() {
+        "properties" => json!(input.properties),
+        "parent" => json!(input.parent),
+        "channels" => json!(input.channels),
+        "caps" => json!(input.caps),
+        _ => return Err(not_found("No such input attribute")),
+    };
+    Ok(Json(value).into_response())
+}
+
+pub async fn outputs_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let channel_mapping = state.channel_mapping.read().await;
+    let ids: Vec<String> = channel_mapping
+        .outputs()
+        .iter()
+        .map(|output| format!("{}/", output.id))
+        .collect();
+    Json(json!(ids))
+}
+
+pub async fn output_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, ChannelMappingError> {
+    find_output(&*state.channel_mapping.read().await, &id)?;
+    Ok(Json(json!(["caps/", "channels/", "properties/", "sourceid/"])).into_response())
+}
+
+/// One attribute of an output (`properties`, `sourceid`, `channels` or `caps`)
+pub async fn output_attribute_handler(
+    State(state): State<Arc<AppState>>,
+    Path((id, attribute)): Path<(String, String)>,
+) -> Result<Response, ChannelMappingError> {
+    let channel_mapping = state.channel_mapping.read().await;
+    let output = find_output(&channel_mapping, &id)?;
+    let value = match attribute.as_str() {
+        "properties" => json!(output.properties),
+        "sourceid" => json!(output.source_id),
+        "channels" => json!(output.channels),
+        "caps" => json!(output.caps),
+        _ => return Err(not_found("No such output attribute")),
+    };
+    Ok(Json(value).into_response())
+}
+
+/// Every input and output with all of their attributes
+pub async fn io_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let channel_mapping = state.channel_mapping.read().await;
+    let inputs: BTreeMap<&str, &ChannelMappingInput> = channel_mapping
+        .inputs()
+        .iter()
+        .map(|input| (input.id.as_str(), input))
+        .collect();
+    let outputs: BTreeMap<&str, &ChannelMappingOutput> = channel_mapping
+        .outputs()
+        .iter()
+        .map(|output| (output.id.as_str(), output))
+        .collect();
+    Json(json!({ "inputs": inputs, "outputs": outputs }))
+}
+
+pub async fn active_map_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let channel_mapping = state.channel_mapping.read().await;
+    Json(json!({
+        "activation": channel_mapping.last_activation,
+        "map": channel_mapping.active,
+    }))
+}
+
+pub async fn activations_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(json!(state.channel_mapping.read().await.scheduled))
+}
+
+pub async fn activation_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, ChannelMappingError> {
+    let channel_mapping = state.channel_mapping.read().await;
+    let scheduled = channel_mapping
+        .scheduled
+        .get(&id)
+        .ok_or_else(|| not_found("No scheduled activation with this id"))?;
+    Ok(Json(json!({ id: scheduled })).into_response())
+}
+
+/// Cancels a scheduled activation
+pub async fn delete_activation_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, ChannelMappingError> {
+    let mut channel_mapping = state.channel_mapping.write().await;
+    channel_mapping
+        .scheduled
+        .remove(&id)
+        .ok_or_else(|| not_found("No scheduled activation with this id"))?;
+    tracing::info!(
+        activation = id,
+        "scheduled channel map activation cancelled"
+    );
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Applies an action right away or schedules it, answering 200 or 202 with the activation
+pub async fn activate_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ActivationRequest>,
+) -> Result<Response, ChannelMappingError> {
+    let ActivationRequest {
+        mut activation,
+        action,
+    } = request;
+    let now = tai::now();
+    let activation_time = match (activation.mode, activation.requested_time.as_deref()) {
+        (Some(ActivationMode::Immediate), None) => now,
+        (Some(ActivationMode::Immediate), Some(_)) => {
+            return Err(ChannelMappingError::bad_request(
+                "Immediate activations must not have a requested_time",
+            ));
+        }
+        (Some(mode), Some(requested_time)) => {
+            let requested = parse_tai(requested_time).ok_or_else(|| {
+                ChannelMappingError::bad_request("requested_time must be <seconds>:<nanoseconds>")
+            })?;
+            match mode {
+                ActivationMode::ScheduledRelative => now + requested,
+                _ => requested,
+            }
+        }
+        (Some(_), None) => {
+            return Err(ChannelMappingError::bad_request(
+                "Scheduled activations need a requested_time",
+            ));
+        }
+        (None, _) => return Err(ChannelMappingError::bad_request("Missing activation mode")),
+    };
+    activation.activation_time = Some(format_tai(activation_time));
+
+    let mut channel_mapping = state.channel_mapping.write().await;
+    channel_mapping.validate(&action)?;
+    let id = Uuid::new_v4().to_string();
+    let scheduled = ScheduledActivation {
+        activation: activation.clone(),
+        action,
+    };
+    let body = Json(json!({ &id: scheduled }));
+
+    if activation.mode == Some(ActivationMode::Immediate) {
+        channel_mapping.apply(activation, &scheduled.action);
+        return Ok((StatusCode::OK, body).into_response());
+    }
+
+    tracing::info!(activation = id, activation_time = ?activation.activation_time, "channel map activation scheduled");
+    channel_mapping.scheduled.insert(id.clone(), scheduled);
+    tokio::spawn(run_scheduled_activation(
+        state.clone(),
+        id,
+        activation_time.saturating_sub(now),
+    ));
+    Ok((StatusCode::ACCEPTED, body).into_response())
+}
+
+/// Applies a scheduled activation once its time has come, unless it was cancelled or is no longer
+/// valid
+async fn run_scheduled_activation(state: Arc<AppState>, id: String, delay: Duration) {
+    tokio::time::sleep(delay).await;
+    let mut channel_mapping = state.channel_mapping.write().await;
+    if let Some(Err(e)) = channel_mapping.apply_scheduled(&id) {
+        tracing::warn!(
+            activation = id,
+            error = e.message,
+            "scheduled channel map activation dropped"
+        );
+    }
+}
+
+fn find_input<'a>(
+    channel_mapping: &'a ChannelMapping,
+    id: &str,
+) -> Result<&'a ChannelMappingInput, ChannelMappingError> {
+    channel_mapping
+        .find_input(id)
+        .ok_or_else(|| not_found("No input with this id"))
+}
+
+fn find_output<'a>(
+    channel_mapping: &'a ChannelMapping,
+    id: &str,
+) -> Result<&'a ChannelMappingOutput, ChannelMappingError> {
+    channel_mapping
+        .find_output(id)
+        .ok_or_else(|| not_found("No output with this id"))
+}
+
+fn not_found(message: &str) -> ChannelMappingError {
+    ChannelMappingError::new(StatusCode::NOT_FOUND, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(count: usize) -> Vec<IoChannel> {
+        (0..count)
+            .map(|index| IoChannel {
+                label: format!("Channel {index}"),
+            })
+            .collect()
+    }
+
+    fn input(id: &str, count: usize, reordering: bool, block_size: usize) -> ChannelMappingInput {
+        ChannelMappingInput {
+            id: id.into(),
+            properties: IoProperties {
+                name: id.into(),
+                description: String::new(),
+            },
+            parent: InputParent {
+                id: None,
+                type_: None,
+            },
+            channels: channels(count),
+            caps: InputCaps {
+                reordering,
+                block_size,
+            },
+        }
+    }
+
+    fn output(
+        id: &str,
+        count: usize,
+        routable_inputs: Option<Vec<Option<String>>>,
+    ) -> ChannelMappingOutput {
+        ChannelMappingOutput {
+            id: id.into(),
+            properties: IoProperties {
+                name: id.into(),
+                description: String::new(),
+            },
+            source_id: None,
+            channels: channels(count),
+            caps: OutputCaps { routable_inputs },
+        }
+    }
+
+    /// Inputs `free` (reordering), `ordered` (no reordering) and `pairs` (blocks of 2), outputs
+    /// `main` and `free-only` (routable from `free` or unrouted)
+    fn channel_mapping() -> ChannelMapping {
+        let mut channel_mapping = ChannelMapping::default();
+        channel_mapping.add_input(input("free", 4, true, 1));
+        channel_mapping.add_input(input("ordered", 4, false, 1));
+        channel_mapping.add_input(input("pairs", 4, true, 2));
+        channel_mapping.add_output(output("main", 4, None));
+        channel_mapping.add_output(output(
+            "free-only",
+            2,
+            Some(vec![Some("free".into()), None]),
+        ));
+        channel_mapping
+    }
+
+    fn route(input: &str, channel_index: usize) -> ChannelRoute {
+        ChannelRoute {
+            input: Some(input.into()),
+            channel_index: Some(channel_index),
+        }
+    }
+
+    fn action(output: &str, routes: &[(usize, ChannelRoute)]) -> ChannelMap {
+        ChannelMap::from([(output.into(), routes.iter().cloned().collect())])
+    }
+
+    fn status(result: Result<(), ChannelMappingError>) -> Option<StatusCode> {
+        result.err().map(|err| err.status)
+    }
+
+    #[test]
+    fn routes_must_name_existing_outputs_inputs_and_channels() {
+        let cm = channel_mapping();
+        let bad_request = Some(StatusCode::BAD_REQUEST);
+
+        assert_eq!(
+            status(cm.validate(&action("main", &[(3, route("free", 0))]))),
+            None
+        );
+        assert_eq!(status(cm.validate(&action("other", &[]))), bad_request);
+        assert_eq!(
+            status(cm.validate(&action("main", &[(4, route("free", 0))]))),
+            bad_request
+        );
+        assert_eq!(
+            status(cm.validate(&action("main", &[(0, route("other", 0))]))),
+            bad_request
+        );
+        assert_eq!(
+            status(cm.validate(&action("main", &[(0, route("free", 4))]))),
+            bad_request
+        );
+    }
+
+    #[test]
+    fn input_and_channel_index_are_both_set_or_both_null() {
+        let cm = channel_mapping();
+        let unrouted = ChannelRoute::default();
+        assert_eq!(status(cm.validate(&action("main", &[(0, unrouted)]))), None);
+
+        let half_routes = [
+            ChannelRoute {
+                input: Some("free".into()),
+                channel_index: None,
+            },
+            ChannelRoute {
+                input: None,
+                channel_index: Some(0),
+            },
+        ];
+        for half_route in half_routes {
+            assert_eq!(
+                status(cm.validate(&action("main", &[(0, half_route)]))),
+                Some(StatusCode::BAD_REQUEST)
+            );
+        }
+    }
+
+    #[test]
+    fn routable_inputs_restrict_an_output() {
+        let cm = channel_mapping();
+        let free = action("free-only", &[(0, route("free", 1))]);
+        assert_eq!(status(cm.validate(&free)), None);
+        // The null entry allows unrouted channels
+        let unrouted = action("free-only", &[(1, ChannelRoute::default())]);
+        assert_eq!(status(cm.validate(&unrouted)), None);
+
+        let pairs = action(
+            "free-only",
+            &[(0, route("pairs", 0)), (1, route("pairs", 1))],
+        );
+        assert_eq!(status(cm.validate(&pairs)), Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn inputs_without_reordering_keep_their_channel_order() {
+        let cm = channel_mapping();
+        let in_order = action(
+            "main",
+            &[(0, route("ordered", 1)), (2, route("ordered", 3))],
+        );
+        assert_eq!(status(cm.validate(&in_order)), None);
+
+        let swapped = action(
+            "main",
+            &[(0, route("ordered", 1)), (1, route("ordered", 0))],
+        );
+        assert_eq!(status(cm.validate(&swapped)), Some(StatusCode::BAD_REQUEST));
+
+        // Routes left active by an earlier activation count as well
+        let mut cm = cm;
+        cm.apply(
+            Activation::default(),
+            &action("main", &[(3, route("ordered", 0))]),
+        );
+        let before_it = action("main", &[(0, route("ordered", 2))]);
+        assert_eq!(
+            status(cm.validate(&before_it)),
+            Some(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[test]
+    fn inputs_with_a_block_size_are_routed_in_whole_blocks() {
+        let cm = channel_mapping();
+        let block = action("main", &[(1, route("pairs", 2)), (2, route("pairs", 3))]);
+        assert_eq!(status(cm.validate(&block)), None);
+
+        let partial = action("main", &[(0, route("pairs", 0))]);
+        let split = action("main", &[(0, route("pairs", 0)), (2, route("pairs", 1))]);
+        let reversed = action("main", &[(0, route("pairs", 1)), (1, route("pairs", 0))]);
+        let misaligned = action("main", &[(0, route("pairs", 1)), (1, route("pairs", 2))]);
+        for action in [partial, split, reversed, misaligned] {
+            assert_eq!(
+                status(cm.validate(&action)),
+                Some(StatusCode::BAD_REQUEST),
+                "{action:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn outputs_of_a_scheduled_activation_are_locked() {
+        let mut cm = channel_mapping();
+        let scheduled = action("main", &[(0, route("free", 0))]);
+        cm.scheduled.insert(
+            "activation".into(),
+            ScheduledActivation {
+                activation: Activation::default(),
+                action: scheduled.clone(),
+            },
+        );
+
+        assert_eq!(status(cm.validate(&scheduled)), Some(StatusCode::LOCKED));
+        let other_output = action("free-only", &[(0, route("free", 0))]);
+        assert_eq!(status(cm.validate(&other_output)), None);
+    }
+
+    #[test]
+    fn scheduled_activations_are_validated_again_when_due() {
+        let mut cm = channel_mapping();
+        let schedule = |cm: &mut ChannelMapping, id: &str, action: ChannelMap| {
+            cm.validate(&action).unwrap();
+            cm.scheduled.insert(
+                id.into(),
+                ScheduledActivation {
+                    activation: Activation::default(),
+                    action,
+                },
+            );
+        };
+
+        schedule(
+            &mut cm,
+            "valid",
+            action("main", &[(1, route("ordered", 2))]),
+        );
+        assert!(matches!(cm.apply_scheduled("valid"), Some(Ok(()))));
+        assert_eq!(cm.active["main"][&1], route("ordered", 2));
+        assert!(cm.apply_scheduled("valid").is_none());
+
+        // The active map changes behind the pending activation
+        schedule(
+            &mut cm,
+            "stale",
+            action("main", &[(3, route("ordered", 3))]),
+        );
+        cm.active
+            .get_mut("main")
+            .unwrap()
+            .insert(2, route("ordered", 3));
+        assert_eq!(
+            status(cm.apply_scheduled("stale").unwrap()),
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(cm.active["main"][&3], ChannelRoute::default());
+        assert!(cm.scheduled.is_empty());
+    }
+
+    #[test]
+    fn tai_times_are_seconds_and_nanoseconds() {
+        assert_eq!(parse_tai("1:500"), Some(Duration::new(1, 500)));
+        assert_eq!(
+            parse_tai("0:999999999"),
+            Some(Duration::new(0, 999_999_999))
+        );
+        for invalid in ["1", "1:1000000000", "-1:0", "1:-5", "a:b", ""] {
+            assert_eq!(parse_tai(invalid), None, "{invalid}");
+        }
+        let time = Duration::new(1_700_000_037, 120);
+        assert_eq!(parse_tai(&format_tai(time)), Some(time));
+    }
+}
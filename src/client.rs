@@ -0,0 +1,573 @@
+use futures_util::{SinkExt, StreamExt, stream::SplitSink};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fmt,
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+use tokio::{
+    net::TcpStream,
+    sync::{Mutex, mpsc, oneshot},
+    task::JoinHandle,
+};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async,
+    tungstenite::{Message, client::IntoClientRequest, http::header},
+};
+
+use crate::data_types::{
+    MESSAGE_TYPE_COMMAND, MESSAGE_TYPE_COMMAND_RESPONSE, MESSAGE_TYPE_ERROR,
+    MESSAGE_TYPE_NOTIFICATION, MESSAGE_TYPE_SUBSCRIPTION, MESSAGE_TYPE_SUBSCRIPTION_RESPONSE,
+    NcBlockMemberDescriptor, NcClassDescriptor, NcElementId, NcMethodStatus, PropertyChangedEvent,
+};
+
+/// Lowest and highest command handle allowed by IS-12
+const MIN_HANDLE: u64 = 1;
+const MAX_HANDLE: u64 = 65535;
+
+type WsSink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
+type PendingCommands = Arc<StdMutex<HashMap<u64, oneshot::Sender<MethodResult>>>>;
+
+/// Problems talking to a device, including commands the device answered with an error status
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientError {
+    Connect(String),
+    /// The connection failed or was closed before the answer arrived
+    Closed,
+    Timeout,
+    /// A message from the device which does not follow IS-12
+    Protocol(String),
+    /// An IS-12 Error message sent by the device
+    Device {
+        status: u64,
+        error_message: String,
+    },
+    /// A command which completed with an error status
+    Method {
+        status: u16,
+        error_message: Option<String>,
+    },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Connect(message) => write!(f, "Failed to connect: {message}"),
+            ClientError::Closed => write!(f, "Connection closed"),
+            ClientError::Timeout => write!(f, "Timed out waiting for the device"),
+            ClientError::Protocol(message) => write!(f, "Invalid message: {message}"),
+            ClientError::Device {
+                status,
+                error_message,
+            } => write!(f, "Device error {status}: {error_message}"),
+            ClientError::Method {
+                status,
+                error_message,
+            } => match error_message {
+                Some(error_message) => write!(f, "Command failed with {status}: {error_message}"),
+                None => write!(f, "Command failed with {status}"),
+            },
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Outcome of a command: the status, the value for Get and the methods returning one, and the
+/// error message of failed commands
+#[derive(Debug, Clone)]
+pub struct MethodResult {
+    pub status: NcMethodStatus,
+    pub value: Value,
+    pub error_message: Option<String>,
+}
+
+impl MethodResult {
+    /// Ok and the deprecation statuses, which IS-12 counts as success
+    pub fn is_success(&self) -> bool {
+        u16::from(self.status.clone()) < 300
+    }
+
+    /// The value of a successful command, the status and error message otherwise
+    pub fn into_value(self) -> Result<Value, ClientError> {
+        if self.is_success() {
+            Ok(self.value)
+        } else {
+            Err(ClientError::Method {
+                status: self.status.into(),
+                error_message: self.error_message,
+            })
+        }
+    }
+
+    fn from_result(result: &Value) -> Result<Self, ClientError> {
+        let status = result
+            .get("status")
+            .and_then(Value::as_u64)
+            .and_then(|status| u16::try_from(status).ok())
+            .map(NcMethodStatus::from)
+            .ok_or_else(|| ClientError::Protocol(format!("Invalid method result: {result}")))?;
+        Ok(MethodResult {
+            status,
+            value: result.get("value").cloned().unwrap_or(Value::Null),
+            error_message: result
+                .get("errorMessage")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        })
+    }
+}
+
+/// Close code and reason of a connection closed by the device
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloseReason {
+    pub code: u16,
+    pub reason: String,
+}
+
+/// IS-12 controller end of the WebSocket, for tests and tooling. Commands get their own handle
+/// and can be sent concurrently; notifications, subscription responses and error messages are
+/// queued until asked for.
+pub struct ControlClient {
+    sink: Mutex<WsSink>,
+    pending: PendingCommands,
+    next_handle: StdMutex<u64>,
+    notifications: mpsc::UnboundedReceiver<PropertyChangedEvent>,
+    subscriptions: mpsc::UnboundedReceiver<Vec<u64>>,
+    errors: mpsc::UnboundedReceiver<ClientError>,
+    reader: JoinHandle<Option<CloseReason>>,
+    timeout: Duration,
+}
+
+impl ControlClient {
+    /// Connects to the WebSocket of a device, e.g. `ws://127.0.0.1:3000/ws`
+    pub async fn connect(url: &str) -> Result<Self, ClientError> {
+        Self::connect_request(url, None).await
+    }
+
+    /// Connects with a bearer token, for devices with authorization enabled
+    pub async fn connect_with_token(url: &str, token: &str) -> Result<Self, ClientError> {
+        Self::connect_request(url, Some(token)).await
+    }
+
+    async fn connect_request(url: &str, token: Option<&str>) -> Result<Self, ClientError> {
+        let mut request = url
+            .into_client_request()
+            .map_err(|e| ClientError::Connect(e.to_string()))?;
+        if let Some(token) = token {
+            let value = format!("Bearer {token}")
+                .parse()
+                .map_err(|_| ClientError::Connect("Invalid token".to_string()))?;
+            request.headers_mut().insert(header::AUTHORIZATION, value);
+        }
+        let (stream, _) = connect_async(request)
+            .await
+            .map_err(|e| ClientError::Connect(e.to_string()))?;
+        let (sink, stream) = stream.split();
+
+        let pending = PendingCommands::default();
+        let (notification_tx, notifications) = mpsc::unbounded_channel();
+        let (subscription_tx, subscriptions) = mpsc::unbounded_channel();
+        let (error_tx, errors) = mpsc::unbounded_channel();
+        let reader = tokio::spawn(read_messages(
+            stream,
+            pending.clone(),
+            notification_tx,
+            subscription_tx,
+            error_tx,
+        ));
+
+        Ok(ControlClient {
+            sink: Mutex::new(sink),
+            pending,
+            next_handle: StdMutex::new(MIN_HANDLE),
+            notifications,
+            subscriptions,
+            errors,
+            reader,
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// How long to wait for responses and notifications, 5 seconds by default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends one command and waits for its response, whatever its status
+    pub async fn command(
+        &self,
+        oid: u64,
+        method_id: NcElementId,
+        arguments: Value,
+    ) -> Result<MethodResult, ClientError> {
+        let (handle, response) = self.register_handle()?;
+        let message = json!({
+            "messageType": MESSAGE_TYPE_COMMAND,
+            "commands": [{
+                "handle": handle,
+                "oid": oid,
+                "methodId": method_id,
+                "arguments": arguments,
+            }],
+        });
+        if let Err(err) = self.send_text(message.to_string()).await {
+            self.pending.lock().unwrap().remove(&handle);
+            return Err(err);
+        }
+        match tokio::time::timeout(self.timeout, response).await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(_)) => Err(ClientError::Closed),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&handle);
+                Err(ClientError::Timeout)
+            }
+        }
+    }
+
+    /// Next free handle, wrapping around within the range IS-12 allows
+    fn register_handle(&self) -> Result<(u64, oneshot::Receiver<MethodResult>), ClientError> {
+        let mut pending = self.pending.lock().unwrap();
+        let mut next_handle = self.next_handle.lock().unwrap();
+        for _ in MIN_HANDLE..=MAX_HANDLE {
+            let handle = *next_handle;
+            *next_handle = if handle == MAX_HANDLE {
+                MIN_HANDLE
+            } else {
+                handle + 1
+            };
+            if let Entry::Vacant(entry) = pending.entry(handle) {
+                let (tx, rx) = oneshot::channel();
+                entry.insert(tx);
+                return Ok((handle, rx));
+            }
+        }
+        Err(ClientError::Protocol(
+            "All command handles are in use".to_string(),
+        ))
+    }
+
+    /// Sends a text message as is, e.g. to check how the device handles malformed messages
+    pub async fn send_text(&self, text: String) -> Result<(), ClientError> {
+        self.sink
+            .lock()
+            .await
+            .send(Message::Text(text.into()))
+            .await
+            .map_err(|_| ClientError::Closed)
+    }
+
+    /// Replaces the subscriptions of the connection, returns the oids the device subscribed to
+    pub async fn subscribe(&mut self, oids: &[u64]) -> Result<Vec<u64>, ClientError> {
+        let message = json!({
+            "messageType": MESSAGE_TYPE_SUBSCRIPTION,
+            "subscriptions": oids,
+        });
+        self.send_text(message.to_string()).await?;
+        match tokio::time::timeout(self.timeout, self.subscriptions.recv()).await {
+            Ok(Some(subscriptions)) => Ok(subscriptions),
+            Ok(None) => Err(ClientError::Closed),
+            Err(_) => Err(ClientError::Timeout),
+        }
+    }
+
+    /// Next property changed notification received
+    pub async fn next_notification(&mut self) -> Result<PropertyChangedEvent, ClientError> {
+        match tokio::time::timeout(self.timeout, self.notifications.recv()).await {
+            Ok(Some(event)) => Ok(event),
+            Ok(None) => Err(ClientError::Closed),
+            Err(_) => Err(ClientError::Timeout),
+        }
+    }
+
+    /// Waits for a change of one property, skipping notifications of other properties
+    pub async fn wait_for_change(
+        &mut self,
+        oid: u64,
+        property_id: NcElementId,
+    ) -> Result<PropertyChangedEvent, ClientError> {
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        loop {
+            let event = match tokio::time::timeout_at(deadline, self.notifications.recv()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => return Err(ClientError::Closed),
+                Err(_) => return Err(ClientError::Timeout),
+            };
+            if event.oid == oid && event.event_data.property_id == property_id {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Next IS-12 Error message or invalid message received
+    pub async fn next_error(&mut self) -> Result<ClientError, ClientError> {
+        match tokio::time::timeout(self.timeout, self.errors.recv()).await {
+            Ok(Some(error)) => Ok(error),
+            Ok(None) => Err(ClientError::Closed),
+            Err(_) => Err(ClientError::Timeout),
+        }
+    }
+
+    /// Waits until the device closes the connection, with the close code and reason it gave
+    pub async fn closed(self) -> Result<Option<CloseReason>, ClientError> {
+        match tokio::time::timeout(self.timeout, self.reader).await {
+            Ok(Ok(reason)) => Ok(reason),
+            Ok(Err(_)) => Err(ClientError::Closed),
+            Err(_) => Err(ClientError::Timeout),
+        }
+    }
+
+    /// Closes the connection
+    pub async fn close(self) -> Result<(), ClientError> {
+        let result = self
+            .sink
+            .lock()
+            .await
+            .close()
+            .await
+            .map_err(|_| ClientError::Closed);
+        self.reader.abort();
+        result
+    }
+
+    /// Value of a property (1m1)
+    pub async fn get(&self, oid: u64, id: NcElementId) -> Result<Value, ClientError> {
+        self.command(oid, NcElementId { level: 1, index: 1 }, json!({ "id": id }))
+            .await?
+            .into_value()
+    }
+
+    /// Sets a property (1m2)
+    pub async fn set(&self, oid: u64, id: NcElementId, value: Value) -> Result<(), ClientError> {
+        self.command(
+            oid,
+            NcElementId { level: 1, index: 2 },
+            json!({ "id": id, "value": value }),
+        )
+        .await?
+        .into_value()
+        .map(|_| ())
+    }
+
+    /// Members of a block (2m1), with the members of nested blocks when `recurse` is set
+    pub async fn get_member_descriptors(
+        &self,
+        oid: u64,
+        recurse: bool,
+    ) -> Result<Vec<NcBlockMemberDescriptor>, ClientError> {
+        let value = self
+            .command(
+                oid,
+                NcElementId { level: 2, index: 1 },
+                json!({ "recurse": recurse }),
+            )
+            .await?
+            .into_value()?;
+        decode(value)
+    }
+
+    /// Descriptor of a class (3m1) from the class manager at `class_manager_oid`
+    pub async fn get_control_class(
+        &self,
+        class_manager_oid: u64,
+        class_id: &[u32],
+        include_inherited: bool,
+    ) -> Result<NcClassDescriptor, ClientError> {
+        let value = self
+            .command(
+                class_manager_oid,
+                NcElementId { level: 3, index: 1 },
+                json!({ "classId": class_id, "includeInherited": include_inherited }),
+            )
+            .await?
+            .into_value()?;
+        decode(value)
+    }
+}
+
+fn decode<T: DeserializeOwned>(value: Value) -> Result<T, ClientError> {
+    serde_json::from_value(value).map_err(|e| ClientError::Protocol(e.to_string()))
+}
+
+/// Dispatches the messages of the device until the connection closes. Dropping the pending
+/// commands on return fails them with [`ClientError::Closed`].
+async fn read_messages(
+    mut stream: futures_util::stream::SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    pending: PendingCommands,
+    notification_tx: mpsc::UnboundedSender<PropertyChangedEvent>,
+    subscription_tx: mpsc::UnboundedSender<Vec<u64>>,
+    error_tx: mpsc::UnboundedSender<ClientError>,
+) -> Option<CloseReason> {
+    let close_reason = loop {
+        let text = match stream.next().await {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(frame))) => {
+                break frame.map(|frame| CloseReason {
+                    code: frame.code.into(),
+                    reason: frame.reason.to_string(),
+                });
+            }
+            // Pings are answered by the WebSocket layer
+            Some(Ok(_)) => continue,
+            Some(Err(_)) | None => break None,
+        };
+        let message: Value = match serde_json::from_str(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = error_tx.send(ClientError::Protocol(format!("Malformed JSON: {e}")));
+                continue;
+            }
+        };
+        let message_type = message.get("messageType").and_then(Value::as_u64);
+        match message_type.and_then(|t| u16::try_from(t).ok()) {
+            Some(MESSAGE_TYPE_COMMAND_RESPONSE) => {
+                let responses = message.get("responses").and_then(Value::as_array);
+                for response in responses.into_iter().flatten() {
+                    let handle = response.get("handle").and_then(Value::as_u64);
+                    let result = response
+                        .get("result")
+                        .ok_or_else(|| ClientError::Protocol(format!("No result: {response}")))
+                        .and_then(MethodResult::from_result);
+                    let sender = handle.and_then(|handle| pending.lock().unwrap().remove(&handle));
+                    match (sender, result) {
+                        (Some(sender), Ok(result)) => {
+                            let _ = sender.send(result);
+                        }
+                        (None, _) => {
+                            let _ = error_tx.send(ClientError::Protocol(format!(
+                                "Response for unknown handle: {response}"
+                            )));
+                        }
+                        (Some(_), Err(err)) => {
+                            let _ = error_tx.send(err);
+                        }
+                    }
+                }
+            }
+            Some(MESSAGE_TYPE_NOTIFICATION) => {
+                let notifications = message.get("notifications").cloned();
+                match decode::<Vec<PropertyChangedEvent>>(notifications.unwrap_or(Value::Null)) {
+                    Ok(events) => {
+                        for event in events {
+                            let _ = notification_tx.send(event);
+                        }
+                    }
+                    Err(err) => {
+                        let _ = error_tx.send(err);
+                    }
+                }
+            }
+            Some(MESSAGE_TYPE_SUBSCRIPTION_RESPONSE) => {
+                let subscriptions = message.get("subscriptions").cloned();
+                match decode::<Vec<u64>>(subscriptions.unwrap_or(Value::Null)) {
+                    Ok(subscriptions) => {
+                        let _ = subscription_tx.send(subscriptions);
+                    }
+                    Err(err) => {
+                        let _ = error_tx.send(err);
+                    }
+                }
+            }
+            Some(MESSAGE_TYPE_ERROR) => {
+                let _ = error_tx.send(ClientError::Device {
+                    status: message.get("status").and_then(Value::as_u64).unwrap_or(0),
+                    error_message: message
+                        .get("errorMessage")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+            }
+            _ => {
+                let _ = error_tx.send(ClientError::Protocol(format!(
+                    "Unexpected message: {message}"
+                )));
+            }
+        }
+    };
+    pending.lock().unwrap().clear();
+    close_reason
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, server::DeviceServer};
+    use std::net::{IpAddr, Ipv4Addr};
+
+    const WORKER_OID: u64 = 5;
+
+    fn id(level: u32, index: u32) -> NcElementId {
+        NcElementId { level, index }
+    }
+
+    #[test]
+    fn method_results_map_error_statuses() {
+        let result = MethodResult::from_result(&json!({ "status": 200, "value": 1 })).unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.into_value(), Ok(json!(1)));
+
+        // Deprecated elements still succeed
+        let result = MethodResult::from_result(&json!({ "status": 298 })).unwrap();
+        assert_eq!(result.into_value(), Ok(Value::Null));
+
+        let result =
+            MethodResult::from_result(&json!({ "status": 405, "errorMessage": "Readonly" }))
+                .unwrap();
+        assert!(!result.is_success());
+        assert_eq!(
+            result.into_value(),
+            Err(ClientError::Method {
+                status: 405,
+                error_message: Some("Readonly".to_string()),
+            })
+        );
+
+        assert!(matches!(
+            MethodResult::from_result(&json!({ "value": 1 })),
+            Err(ClientError::Protocol(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn round_trip_against_a_device() {
+        let mut config = Config::default();
+        config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        config.server.port = 0;
+        let server = DeviceServer::builder(config).build().await.unwrap();
+        let url = format!("ws://{}/ws", server.local_addr());
+        tokio::spawn(server.serve());
+        let mut client = ControlClient::connect(&url).await.unwrap();
+
+        assert_eq!(
+            client.get(WORKER_OID, id(1, 6)).await,
+            Ok(json!("My worker 01"))
+        );
+        assert_eq!(client.subscribe(&[WORKER_OID]).await, Ok(vec![WORKER_OID]));
+        client
+            .set(WORKER_OID, id(1, 6), json!("Round trip"))
+            .await
+            .unwrap();
+        let event = client.wait_for_change(WORKER_OID, id(1, 6)).await.unwrap();
+        assert_eq!(event.event_data.value, json!("Round trip"));
+        assert_eq!(
+            client.get(WORKER_OID, id(1, 6)).await,
+            Ok(json!("Round trip"))
+        );
+
+        // The class id is read-only, unknown oids are rejected
+        assert!(matches!(
+            client.set(WORKER_OID, id(1, 1), json!([1, 2])).await,
+            Err(ClientError::Method { status: 405, .. })
+        ));
+        assert!(matches!(
+            client.get(999, id(1, 6)).await,
+            Err(ClientError::Method { status: 404, .. })
+        ));
+
+        client.close().await.unwrap();
+    }
+}
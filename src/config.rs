@@ -0,0 +1,542 @@
+use clap::Parser;
+use serde::Deserialize;
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthConfig,
+    data_types::{NcManufacturer, NcProduct, NmosClock, NmosClockReference, NmosInterface},
+    persistence::{PersistenceConfig, parse_exclude},
+    ptp::PtpConfig,
+    tai::TaiConfig,
+    tls::TlsConfig,
+    websocket::WebSocketConfig,
+};
+
+/// Command line flags, taking precedence over the configuration file
+#[derive(Debug, Parser)]
+#[command(version, about = "An example NMOS Device implementing IS-12")]
+pub struct Cli {
+    /// Configuration file (.toml or .json)
+    #[arg(short, long, env = "NMOS_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Address to listen on
+    #[arg(long)]
+    pub bind: Option<IpAddr>,
+    /// Port to listen on
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Host name or address advertised in the IS-04 resources
+    #[arg(long)]
+    pub advertised_host: Option<String>,
+    /// Node id (UUID)
+    #[arg(long)]
+    pub node_id: Option<String>,
+    /// Node label
+    #[arg(long)]
+    pub node_label: Option<String>,
+    /// Device id (UUID)
+    #[arg(long)]
+    pub device_id: Option<String>,
+    /// Device label
+    #[arg(long)]
+    pub device_label: Option<String>,
+    /// Log filter, e.g. `nmos_control_rusty_device=debug`
+    #[arg(long)]
+    pub log_filter: Option<String>,
+    /// Device model description (.json, .yaml or .yml)
+    #[arg(long)]
+    pub model: Option<PathBuf>,
+    /// Discard persisted property values before starting
+    #[arg(long)]
+    pub factory_reset: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    /// Used when neither `--log-filter` nor `RUST_LOG` is given
+    pub log_filter: String,
+    pub node: NodeConfig,
+    pub device: DeviceConfig,
+    /// Device model description, the built-in example model is used when not set
+    pub model_file: Option<PathBuf>,
+    /// Keeps writable property values across restarts, disabled when not set
+    pub persistence: Option<PersistenceConfig>,
+    pub auth: Option<AuthConfig>,
+    pub tls: Option<TlsConfig>,
+    /// Source of the TAI timestamps used as IS-04 versions
+    pub time: TaiConfig,
+    pub websocket: WebSocketConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: IpAddr,
+    pub port: u16,
+    /// Host name clients use to reach the device, advertised before the discovered addresses
+    pub advertised_host: Option<String>,
+    /// How often the host addresses are checked for changes
+    #[serde(
+        rename = "address_refresh_interval_secs",
+        deserialize_with = "duration_from_secs"
+    )]
+    pub address_refresh_interval: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+    /// Generated at startup when not configured
+    pub id: Option<String>,
+    pub label: String,
+    pub description: String,
+    pub clocks: Vec<NmosClock>,
+    /// Discovered from the host when not configured
+    pub interfaces: Option<Vec<NmosInterface>>,
+    /// Adds a PTP clock whose state is read from ptp4l or a file, disabled when not set
+    pub ptp: Option<PtpConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeviceConfig {
+    pub id: String,
+    pub label: String,
+    pub description: String,
+    pub manufacturer: NcManufacturer,
+    pub product: NcProduct,
+    pub serial_number: String,
+    /// How long the identification beacon stays active once switched on
+    #[serde(
+        rename = "identification_timeout_secs",
+        deserialize_with = "duration_from_secs"
+    )]
+    pub identification_timeout: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            server: ServerConfig::default(),
+            log_filter: "nmos_control_rusty_device=info,tower_http=debug".into(),
+            node: NodeConfig::default(),
+            device: DeviceConfig::default(),
+            model_file: None,
+            persistence: None,
+            auth: None,
+            tls: None,
+            time: TaiConfig::default(),
+            websocket: WebSocketConfig::default(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 3000,
+            advertised_host: None,
+            address_refresh_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        NodeConfig {
+            id: None,
+            label: "Example Node".into(),
+            description: "An example NMOS node".into(),
+            clocks: vec![NmosClock {
+                name: "clk0".into(),
+                reference: NmosClockReference::Internal,
+            }],
+            interfaces: None,
+            ptp: None,
+        }
+    }
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        DeviceConfig {
+            id: "67c25159-ce25-4000-a66c-f31fff890265".into(),
+            label: "Example Device".into(),
+            description: "An example NMOS device".into(),
+            manufacturer: NcManufacturer {
+                name: "Your Company".to_string(),
+                organization_id: None,
+                website: Some("https://example.com".to_string()),
+            },
+            product: NcProduct {
+                name: "Your Product".to_string(),
+                key: "MODEL-XYZ-2000".to_string(),
+                revision_level: "1.0".to_string(),
+                brand_name: Some("Your Brand".to_string()),
+                uuid: Some("550e8400-e29b-41d4-a716-446655440000".to_string()),
+                description: Some("Professional device".to_string()),
+            },
+            serial_number: "SN-123456789".to_string(),
+            identification_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration from the optional file and the command line flags, then validates it
+    pub fn load(cli: &Cli) -> anyhow::Result<Self> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None => Config::default(),
+        };
+        config.apply_cli(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+        let config = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&text).map_err(|e| anyhow::anyhow!("{e}")),
+            Some("toml") => toml::from_str(&text).map_err(|e| anyhow::anyhow!("{e}")),
+            _ => anyhow::bail!(
+                "Unsupported configuration file {}, expected .toml or .json",
+                path.display()
+            ),
+        };
+        config.map_err(|e| anyhow::anyhow!("Invalid configuration file {}: {e}", path.display()))
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(bind) = cli.bind {
+            self.server.bind = bind;
+        }
+        if let Some(port) = cli.port {
+            self.server.port = port;
+        }
+        if let Some(host) = &cli.advertised_host {
+            self.server.advertised_host = Some(host.clone());
+        }
+        if let Some(id) = &cli.node_id {
+            self.node.id = Some(id.clone());
+        }
+        if let Some(label) = &cli.node_label {
+            self.node.label = label.clone();
+        }
+        if let Some(id) = &cli.device_id {
+            self.device.id = id.clone();
+        }
+        if let Some(label) = &cli.device_label {
+            self.device.label = label.clone();
+        }
+        if let Some(filter) = &cli.log_filter {
+            self.log_filter = filter.clone();
+        }
+        if let Some(model) = &cli.model {
+            self.model_file = Some(model.clone());
+        }
+    }
+
+    /// Reports every problem at once so a deployment can be fixed in one go
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+
+        if self.server.port == 0 {
+            errors.push("server.port must not be 0".to_string());
+        }
+        if self
+            .server
+            .advertised_host
+            .as_ref()
+            .is_some_and(|host| host.trim().is_empty())
+        {
+            errors.push("server.advertised_host must not be empty".to_string());
+        }
+        if self.server.address_refresh_interval.is_zero() {
+            errors.push("server.address_refresh_interval_secs must not be 0".to_string());
+        }
+        if self.websocket.queue_capacity == 0 {
+            errors.push("websocket.queue_capacity must not be 0".to_string());
+        }
+        if self.websocket.max_message_size == 0 {
+            errors.push("websocket.max_message_size must not be 0".to_string());
+        }
+        if self.websocket.max_notification_batch == 0 {
+            errors.push("websocket.max_notification_batch must not be 0".to_string());
+        }
+        if self.websocket.pong_timeout.is_zero() {
+            errors.push("websocket.pong_timeout_secs must not be 0".to_string());
+        }
+        if let Some(ping_interval) = self.websocket.ping_interval
+            && self.websocket.pong_timeout >= ping_interval
+        {
+            errors.push(
+                "websocket.pong_timeout_secs must be shorter than websocket.ping_interval_secs"
+                    .to_string(),
+            );
+        }
+        if self.device.identification_timeout.is_zero() {
+            errors.push("device.identification_timeout_secs must not be 0".to_string());
+        }
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.log_filter) {
+            errors.push(format!("log_filter is invalid: {e}"));
+        }
+
+        if let Some(id) = &self.node.id
+            && Uuid::parse_str(id).is_err()
+        {
+            errors.push(format!("node.id {id:?} is not a UUID"));
+        }
+        if Uuid::parse_str(&self.device.id).is_err() {
+            errors.push(format!("device.id {:?} is not a UUID", self.device.id));
+        }
+        if self.node.id.as_deref() == Some(self.device.id.as_str()) {
+            errors.push("node.id and device.id must be different".to_string());
+        }
+        if self.node.label.is_empty() {
+            errors.push("node.label must not be empty".to_string());
+        }
+        if self.device.label.is_empty() {
+            errors.push("device.label must not be empty".to_string());
+        }
+
+        for (i, interface) in self.node.interfaces.iter().flatten().enumerate() {
+            if interface.name.is_empty() {
+                errors.push(format!("node.interfaces[{i}].name must not be empty"));
+            }
+            for (field, value) in [
+                ("chassis_id", &interface.chassis_id),
+                ("port_id", &interface.port_id),
+            ] {
+                if !is_mac_address(value) {
+                    errors.push(format!(
+                        "node.interfaces[{i}].{field} {value:?} is not a MAC address like 00-15-5d-67-c3-4e"
+                    ));
+                }
+            }
+        }
+        for (i, clock) in self.node.clocks.iter().enumerate() {
+            if !clock.name.starts_with("clk") {
+                errors.push(format!(
+                    "node.clocks[{i}].name {:?} must start with clk",
+                    clock.name
+                ));
+            }
+        }
+
+        if let Some(ptp) = &self.node.ptp {
+            if ptp.pmc_socket.is_some() == ptp.file.is_some() {
+                errors.push("node.ptp needs exactly one of pmc_socket and file".to_string());
+            }
+            if !ptp.clock.starts_with("clk") {
+                errors.push(format!(
+                    "node.ptp.clock {:?} must start with clk",
+                    ptp.clock
+                ));
+            }
+            if ptp.poll_interval.is_zero() {
+                errors.push("node.ptp.poll_interval_secs must not be 0".to_string());
+            }
+            if self.node.clocks.iter().any(|clock| clock.name == ptp.clock) {
+                errors.push(format!(
+                    "node.ptp.clock {:?} is already used by node.clocks",
+                    ptp.clock
+                ));
+            }
+        }
+
+        if self.device.manufacturer.name.is_empty() {
+            errors.push("device.manufacturer.name must not be empty".to_string());
+        }
+        if self.device.product.name.is_empty() || self.device.product.key.is_empty() {
+            errors.push("device.product.name and device.product.key must not be empty".to_string());
+        }
+
+        if let Some(model_file) = &self.model_file
+            && !model_file.is_file()
+        {
+            errors.push(format!(
+                "model_file {} does not exist",
+                model_file.display()
+            ));
+        }
+
+        if let Some(persistence) = &self.persistence {
+            if persistence
+                .file
+                .parent()
+                .is_some_and(|dir| !dir.as_os_str().is_empty() && !dir.is_dir())
+            {
+                errors.push(format!(
+                    "persistence.file {} is not in an existing directory",
+                    persistence.file.display()
+                ));
+            }
+            for entry in &persistence.exclude {
+                if parse_exclude(entry).is_none() {
+                    errors.push(format!(
+                        "persistence.exclude entry {entry:?} must look like root.my-worker-01/2p1 or */1p6"
+                    ));
+                }
+            }
+        }
+
+        if let Some(auth) = &self.auth {
+            if auth.issuer.is_empty() {
+                errors.push("auth.issuer must not be empty".to_string());
+            }
+            if !auth.jwks_file.is_file() {
+                errors.push(format!(
+                    "auth.jwks_file {} does not exist",
+                    auth.jwks_file.display()
+                ));
+            }
+        }
+        if let Some(tls) = &self.tls {
+            for (field, path) in [("cert_file", &tls.cert_file), ("key_file", &tls.key_file)] {
+                if !path.is_file() {
+                    errors.push(format!("tls.{field} {} does not exist", path.display()));
+                }
+            }
+            if tls.reload_interval.is_zero() {
+                errors.push("tls.reload_interval_secs must not be 0".to_string());
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Invalid configuration:\n  - {}", errors.join("\n  - "))
+        }
+    }
+}
+
+/// Deserializes a duration given in whole seconds
+pub fn duration_from_secs<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_secs)
+}
+
+/// Deserializes a duration given in milliseconds
+pub fn duration_from_millis<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_millis)
+}
+
+/// Deserializes a duration given in whole seconds, 0 meaning disabled
+pub fn optional_duration_from_secs<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(|secs| (secs > 0).then(|| Duration::from_secs(secs)))
+}
+
+/// Deserializes a limit, 0 meaning unlimited
+pub fn optional_limit<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    usize::deserialize(deserializer).map(|limit| (limit > 0).then_some(limit))
+}
+
+/// IS-04 MAC address format: six lower case hex pairs separated by '-'
+fn is_mac_address(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    parts.len() == 6
+        && parts.iter().all(|p| {
+            p.len() == 2
+                && p.chars()
+                    .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outbound_queue::OverflowPolicy;
+
+    #[test]
+    fn websocket_section_overrides_the_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+            [websocket]
+            ping_interval_secs = 0
+            pong_timeout_secs = 5
+            idle_timeout_secs = 300
+            max_connections = 8
+            queue_capacity = 16
+            overflow_policy = "drop_oldest"
+            max_message_size = 4096
+            notification_batch_window_ms = 20
+            max_notification_batch = 32
+            "#,
+        )
+        .unwrap();
+        let defaults = WebSocketConfig::default();
+        assert_eq!(config.websocket.ping_interval, None);
+        assert_eq!(config.websocket.pong_timeout, Duration::from_secs(5));
+        assert_eq!(
+            config.websocket.idle_timeout,
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(config.websocket.max_connections, Some(8));
+        assert_eq!(config.websocket.queue_capacity, 16);
+        assert_eq!(config.websocket.overflow_policy, OverflowPolicy::DropOldest);
+        assert_eq!(config.websocket.max_message_size, 4096);
+        assert_eq!(
+            config.websocket.notification_batch_window,
+            Duration::from_millis(20)
+        );
+        assert_eq!(config.websocket.max_notification_batch, 32);
+
+        let config: Config = toml::from_str("[websocket]\nmax_connections = 0").unwrap();
+        assert_eq!(config.websocket.max_connections, None);
+        assert_eq!(config.websocket.ping_interval, defaults.ping_interval);
+        assert_eq!(config.websocket.max_message_size, defaults.max_message_size);
+    }
+
+    #[test]
+    fn websocket_timeouts_are_validated() {
+        let mut config = Config::default();
+        config.validate().unwrap();
+
+        config.websocket.queue_capacity = 0;
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("websocket.queue_capacity must not be 0"));
+        config.websocket.queue_capacity = 1;
+
+        config.websocket.max_message_size = 0;
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("websocket.max_message_size must not be 0"));
+        config.websocket.max_message_size = 1024;
+
+        config.websocket.max_notification_batch = 0;
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("websocket.max_notification_batch must not be 0"));
+        config.websocket.max_notification_batch = 1;
+
+        config.websocket.pong_timeout = Duration::ZERO;
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("websocket.pong_timeout_secs must not be 0"));
+
+        config.websocket.pong_timeout = Duration::from_secs(30);
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("shorter than websocket.ping_interval_secs"));
+
+        config.websocket.ping_interval = None;
+        config.validate().unwrap();
+    }
+}
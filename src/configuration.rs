@@ -0,0 +1,461 @@
+use axum::{
+    Json,
+    extract::{Path, Query, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::{
+    AppState,
+    auth::bearer_token,
+    data_types::{
+        NcClassDescriptor, NcElementId, NcMethodResult, NcMethodResultError,
+        NcMethodResultPropertyValue, NcMethodStatus,
+    },
+    device_reset::restart_device,
+    nc_block::NcBlock,
+    nc_bulk_properties_manager::NcBulkPropertiesManager,
+    nc_object::NcMember,
+    nc_role_path::NcRolePath,
+    websocket::execute_command,
+};
+
+/// Versioned base path of the IS-14 Configuration API
+pub const CONFIGURATION_API_BASE: &str = "/x-nmos/configuration/v1.0";
+
+/// A failed request, answered with the HTTP status matching the method status
+#[derive(Debug)]
+pub struct ApiError {
+    status: NcMethodStatus,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: NcMethodStatus, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        method_result_response(self.status, Some(self.message), None)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkPropertiesQuery {
+    recurse: Option<bool>,
+}
+
+/// Requires read access for GET requests and write access for anything else when
+/// authorization is enabled
+pub async fn configuration_auth_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(auth) = &state.auth {
+        let path = request
+            .uri()
+            .path()
+            .trim_start_matches(CONFIGURATION_API_BASE)
+            .trim_matches('/');
+        let write = request.method() != Method::GET && request.method() != Method::HEAD;
+        let token = bearer_token(request.headers(), request.uri().query());
+        if let Err(err) = auth.authorize_configuration(token, path, write) {
+            return err.into_response();
+        }
+    }
+    next.run(request).await
+}
+
+pub async fn versions_listing_handler() -> impl IntoResponse {
+    Json(json!(["v1.0/"]))
+}
+
+pub async fn base_handler() -> impl IntoResponse {
+    Json(json!(["rolePaths/"]))
+}
+
+/// Lists the role path of every object in the model, e.g. `root.my-block-01/`
+pub async fn role_paths_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let root = state.root_block.lock().await;
+    let role_paths: Vec<String> = root
+        .collect_oids()
+        .into_iter()
+        .filter_map(|oid| root.role_path_of(oid))
+        .map(|path| format!("{path}/"))
+        .collect();
+    Json(json!(role_paths))
+}
+
+pub async fn role_path_handler(
+    State(state): State<Arc<AppState>>,
+    Path(role_path): Path<String>,
+) -> Result<Response, ApiError> {
+    let root = state.root_block.lock().await;
+    resolve_role_path(&root, &role_path)?;
+    Ok(Json(json!([
+        "bulkProperties/",
+        "descriptor/",
+        "methods/",
+        "properties/"
+    ]))
+    .into_response())
+}
+
+/// Class descriptor of the object, including inherited elements, from the class manager
+pub async fn descriptor_handler(
+    State(state): State<Arc<AppState>>,
+    Path(role_path): Path<String>,
+) -> Result<Response, ApiError> {
+    let mut root = state.root_block.lock().await;
+    let class_id = resolve_object(&root, &role_path)?.get_class_id().to_vec();
+    let class_manager_oid = root.class_manager().map(|m| m.get_oid()).ok_or_else(|| {
+        ApiError::new(
+            NcMethodStatus::DeviceError,
+            "The device model has no class manager",
+        )
+    })?;
+    let (status, error, value) = execute_command(
+        &mut root,
+        class_manager_oid,
+        NcElementId { level: 3, index: 1 },
+        json!({ "classId": class_id, "includeInherited": true }),
+    );
+    Ok(method_result_response(status, error, Some(value)))
+}
+
+pub async fn properties_handler(
+    State(state): State<Arc<AppState>>,
+    Path(role_path): Path<String>,
+) -> Result<Response, ApiError> {
+    let root = state.root_block.lock().await;
+    let (_, descriptor) = resolve_class_descriptor(&root, &role_path)?;
+    let ids: Vec<String> = descriptor
+        .properties
+        .iter()
+        .map(|p| format!("{}p{}/", p.id.level, p.id.index))
+        .collect();
+    Ok(Json(json!(ids)).into_response())
+}
+
+pub async fn property_handler(
+    State(state): State<Arc<AppState>>,
+    Path((role_path, property_id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let root = state.root_block.lock().await;
+    resolve_property(&root, &role_path, &property_id)?;
+    Ok(Json(json!(["descriptor/", "value/"])).into_response())
+}
+
+pub async fn property_descriptor_handler(
+    State(state): State<Arc<AppState>>,
+    Path((role_path, property_id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let root = state.root_block.lock().await;
+    let (_, descriptor) = resolve_class_descriptor(&root, &role_path)?;
+    let id = parse_element_id(&property_id, 'p')?;
+    let property = descriptor
+        .properties
+        .into_iter()
+        .find(|p| p.id.level == id.level && p.id.index == id.index)
+        .ok_or_else(property_not_found)?;
+    Ok(method_result_response(
+        NcMethodStatus::Ok,
+        None,
+        Some(json!(property)),
+    ))
+}
+
+pub async fn get_property_value_handler(
+    State(state): State<Arc<AppState>>,
+    Path((role_path, property_id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let mut root = state.root_block.lock().await;
+    let (oid, id) = resolve_property(&root, &role_path, &property_id)?;
+    let (status, error, value) = execute_command(
+        &mut root,
+        oid,
+        NcElementId { level: 1, index: 1 },
+        json!({ "id": id }),
+    );
+    Ok(method_result_response(status, error, Some(value)))
+}
+
+/// Sets a property from a `{"value": ...}` body
+pub async fn set_property_value_handler(
+    State(state): State<Arc<AppState>>,
+    Path((role_path, property_id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Result<Response, ApiError> {
+    let value = body
+        .get("value")
+        .ok_or_else(|| ApiError::new(NcMethodStatus::BadCommandFormat, "Missing value"))?;
+    let mut root = state.root_block.lock().await;
+    let (oid, id) = resolve_property(&root, &role_path, &property_id)?;
+    let (status, error, _) = execute_command(
+        &mut root,
+        oid,
+        NcElementId { level: 1, index: 2 },
+        json!({ "id": id, "value": value }),
+    );
+    Ok(method_result_response(status, error, None))
+}
+
+pub async fn methods_handler(
+    State(state): State<Arc<AppState>>,
+    Path(role_path): Path<String>,
+) -> Result<Response, ApiError> {
+    let root = state.root_block.lock().await;
+    let (_, descriptor) = resolve_class_descriptor(&root, &role_path)?;
+    let ids: Vec<String> = descriptor
+        .methods
+        .iter()
+        .map(|m| format!("{}m{}/", m.id.level, m.id.index))
+        .collect();
+    Ok(Json(json!(ids)).into_response())
+}
+
+pub async fn method_handler(
+    State(state): State<Arc<AppState>>,
+    Path((role_path, method_id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let root = state.root_block.lock().await;
+    resolve_method(&root, &role_path, &method_id)?;
+    Ok(Json(json!(["descriptor/"])).into_response())
+}
+
+pub async fn method_descriptor_handler(
+    State(state): State<Arc<AppState>>,
+    Path((role_path, method_id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let root = state.root_block.lock().await;
+    let (_, descriptor) = resolve_class_descriptor(&root, &role_path)?;
+    let id = parse_element_id(&method_id, 'm')?;
+    let method = descriptor
+        .methods
+        .into_iter()
+        .find(|m| m.id.level == id.level && m.id.index == id.index)
+        .ok_or_else(method_not_found)?;
+    Ok(method_result_response(
+        NcMethodStatus::Ok,
+        None,
+        Some(json!(method)),
+    ))
+}
+
+/// Invokes a method with the `arguments` of the body
+pub async fn invoke_method_handler(
+    State(state): State<Arc<AppState>>,
+    Path((role_path, method_id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Result<Response, ApiError> {
+    let arguments = body.get("arguments").cloned().unwrap_or(json!({}));
+    let mut root = state.root_block.lock().await;
+    let (oid, id) = resolve_method(&root, &role_path, &method_id)?;
+    let (status, error, value) = execute_command(&mut root, oid, id.clone(), arguments);
+    if root
+        .device_manager()
+        .is_some_and(|manager| manager.is_reset(oid, &id, &status))
+    {
+        // Waits for this handler to release the model
+        tokio::spawn(restart_device(state.clone()));
+    }
+    Ok(method_result_response(status, error, Some(value)))
+}
+
+/// Backup of the object and, unless `recurse=false`, of everything nested under it
+pub async fn get_bulk_properties_handler(
+    State(state): State<Arc<AppState>>,
+    Path(role_path): Path<String>,
+    Query(query): Query<BulkPropertiesQuery>,
+) -> Result<Response, ApiError> {
+    let arguments = json!({
+        "path": parse_role_path(&role_path)?,
+        "recurse": query.recurse.unwrap_or(true),
+    });
+    invoke_bulk_method(&state, &role_path, 1, arguments).await
+}
+
+/// Validates a restore from the `arguments` of the body without changing anything
+pub async fn validate_bulk_properties_handler(
+    State(state): State<Arc<AppState>>,
+    Path(role_path): Path<String>,
+    Json(body): Json<Value>,
+) -> Result<Response, ApiError> {
+    let arguments = bulk_set_arguments(&role_path, body)?;
+    invoke_bulk_method(&state, &role_path, 2, arguments).await
+}
+
+/// Restores the values given in the `arguments` of the body
+pub async fn set_bulk_properties_handler(
+    State(state): State<Arc<AppState>>,
+    Path(role_path): Path<String>,
+    Json(body): Json<Value>,
+) -> Result<Response, ApiError> {
+    let arguments = bulk_set_arguments(&role_path, body)?;
+    invoke_bulk_method(&state, &role_path, 3, arguments).await
+}
+
+/// The body `arguments` (dataSet, recurse, restoreMode) plus the role path of the URL
+fn bulk_set_arguments(role_path: &str, body: Value) -> Result<Value, ApiError> {
+    let Some(Value::Object(mut arguments)) = body.get("arguments").cloned() else {
+        return Err(ApiError::new(
+            NcMethodStatus::BadCommandFormat,
+            "Missing arguments",
+        ));
+    };
+    arguments.insert("path".to_string(), json!(parse_role_path(role_path)?));
+    Ok(Value::Object(arguments))
+}
+
+/// Runs one of the bulk properties manager methods through the same dispatch as the WebSocket
+async fn invoke_bulk_method(
+    state: &AppState,
+    role_path: &str,
+    index: u32,
+    arguments: Value,
+) -> Result<Response, ApiError> {
+    let mut root = state.root_block.lock().await;
+    resolve_role_path(&root, role_path)?;
+    let manager_oid = root
+        .members
+        .iter()
+        .find(|m| m.as_any().is::<NcBulkPropertiesManager>())
+        .map(|m| m.get_oid())
+        .ok_or_else(|| {
+            ApiError::new(
+                NcMethodStatus::MethodNotImplemented,
+                "The device model has no bulk properties manager",
+            )
+        })?;
+    let (status, error, value) = execute_command(
+        &mut root,
+        manager_oid,
+        NcElementId { level: 3, index },
+        arguments,
+    );
+    Ok(method_result_response(status, error, Some(value)))
+}
+
+/// Role paths are written with '.' separators in URLs, e.g. `root.my-block-01`
+fn parse_role_path(role_path: &str) -> Result<NcRolePath, ApiError> {
+    NcRolePath::parse(role_path).ok_or_else(role_path_not_found)
+}
+
+fn resolve_object<'a>(root: &'a NcBlock, role_path: &str) -> Result<&'a dyn NcMember, ApiError> {
+    root.object_at_role_path(&parse_role_path(role_path)?)
+        .ok_or_else(role_path_not_found)
+}
+
+fn resolve_role_path(root: &NcBlock, role_path: &str) -> Result<u64, ApiError> {
+    resolve_object(root, role_path).map(|object| object.get_oid())
+}
+
+fn role_path_not_found() -> ApiError {
+    ApiError::new(NcMethodStatus::BadOid, "Role path not found")
+}
+
+fn resolve_class_descriptor(
+    root: &NcBlock,
+    role_path: &str,
+) -> Result<(u64, NcClassDescriptor), ApiError> {
+    let object = resolve_object(root, role_path)?;
+    root.class_manager()
+        .and_then(|m| m.find_class_descriptor(object.get_class_id()))
+        .map(|descriptor| (object.get_oid(), descriptor))
+        .ok_or_else(|| ApiError::new(NcMethodStatus::DeviceError, "Object class is not known"))
+}
+
+fn resolve_property(
+    root: &NcBlock,
+    role_path: &str,
+    property_id: &str,
+) -> Result<(u64, NcElementId), ApiError> {
+    let (oid, descriptor) = resolve_class_descriptor(root, role_path)?;
+    let id = parse_element_id(property_id, 'p')?;
+    if !descriptor
+        .properties
+        .iter()
+        .any(|p| p.id.level == id.level && p.id.index == id.index)
+    {
+        return Err(property_not_found());
+    }
+    Ok((oid, id))
+}
+
+fn resolve_method(
+    root: &NcBlock,
+    role_path: &str,
+    method_id: &str,
+) -> Result<(u64, NcElementId), ApiError> {
+    let (oid, descriptor) = resolve_class_descriptor(root, role_path)?;
+    let id = parse_element_id(method_id, 'm')?;
+    if !descriptor
+        .methods
+        .iter()
+        .any(|m| m.id.level == id.level && m.id.index == id.index)
+    {
+        return Err(method_not_found());
+    }
+    Ok((oid, id))
+}
+
+/// Parses `{level}p{index}` property ids and `{level}m{index}` method ids
+fn parse_element_id(text: &str, separator: char) -> Result<NcElementId, ApiError> {
+    text.trim_end_matches('/')
+        .split_once(separator)
+        .and_then(|(level, index)| {
+            Some(NcElementId {
+                level: level.parse().ok()?,
+                index: index.parse().ok()?,
+            })
+        })
+        .ok_or_else(|| {
+            ApiError::new(
+                NcMethodStatus::BadCommandFormat,
+                format!("Invalid element id {text}, expected 1{separator}1 style"),
+            )
+        })
+}
+
+fn property_not_found() -> ApiError {
+    ApiError::new(NcMethodStatus::PropertyNotImplemented, "Property not found")
+}
+
+fn method_not_found() -> ApiError {
+    ApiError::new(NcMethodStatus::MethodNotImplemented, "Method not found")
+}
+
+/// Maps a method result onto the HTTP status with the same code and an NcMethodResult body
+fn method_result_response(
+    status: NcMethodStatus,
+    error: Option<String>,
+    value: Option<Value>,
+) -> Response {
+    let code = u16::from(status.clone());
+    let http_status = match code {
+        200..=299 => StatusCode::OK,
+        _ => StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let body = match (http_status, value) {
+        (StatusCode::OK, Some(value)) => json!(NcMethodResultPropertyValue {
+            base: NcMethodResult { status },
+            value,
+        }),
+        (StatusCode::OK, None) => json!(NcMethodResult { status }),
+        _ => json!(NcMethodResultError {
+            base: NcMethodResult { status },
+            error_message: error,
+        }),
+    };
+    (http_status, Json(body)).into_response()
+}
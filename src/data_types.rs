@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
+use crate::nc_role_path::NcRolePath;
+
 pub const MESSAGE_TYPE_COMMAND: u16 = 0;
 pub const MESSAGE_TYPE_COMMAND_RESPONSE: u16 = 1;
 pub const MESSAGE_TYPE_NOTIFICATION: u16 = 2;
@@ -70,13 +72,28 @@ impl NmosDevice {
     }
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct NmosClock {
     pub name: String,
-    pub ref_type: String,
+    #[serde(flatten)]
+    pub reference: NmosClockReference,
 }
 
-#[derive(Serialize, Clone)]
+/// What the clock is referenced to, `ref_type` in the node resource
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "ref_type", rename_all = "lowercase")]
+pub enum NmosClockReference {
+    Internal,
+    Ptp {
+        traceable: bool,
+        version: String,
+        /// Grandmaster clock identity, e.g. `08-00-11-ff-fe-21-e1-b0`
+        gmid: String,
+        locked: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct NmosInterface {
     pub chassis_id: String,
     pub name: String,
@@ -88,6 +105,7 @@ pub struct NmosEndpoint {
     pub host: String,
     pub port: u32,
     pub protocol: String,
+    pub authorization: bool,
 }
 
 #[derive(Serialize, Clone)]
@@ -142,6 +160,147 @@ impl NmosNode {
     }
 }
 
+#[derive(Serialize, Clone, Copy)]
+pub struct NmosRational {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosChannel {
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+}
+
+/// Format specific part of a source
+#[derive(Serialize, Clone)]
+#[serde(tag = "format")]
+pub enum NmosSourceFormat {
+    #[serde(rename = "urn:x-nmos:format:video")]
+    Video,
+    #[serde(rename = "urn:x-nmos:format:audio")]
+    Audio { channels: Vec<NmosChannel> },
+    #[serde(rename = "urn:x-nmos:format:data")]
+    Data,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosSource {
+    #[serde(flatten)]
+    pub base: NmosResource,
+    pub caps: Map<String, Value>,
+    pub device_id: String,
+    pub parents: Vec<String>,
+    pub clock_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grain_rate: Option<NmosRational>,
+    #[serde(flatten)]
+    pub format: NmosSourceFormat,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosComponent {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosFlowVideoRaw {
+    pub media_type: String,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub interlace_mode: String,
+    pub colorspace: String,
+    pub transfer_characteristic: String,
+    pub components: Vec<NmosComponent>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosFlowAudioRaw {
+    pub media_type: String,
+    pub sample_rate: NmosRational,
+    pub bit_depth: u32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosDidSdid {
+    #[serde(rename = "DID")]
+    pub did: String,
+    #[serde(rename = "SDID")]
+    pub sdid: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosFlowData {
+    pub media_type: String,
+    #[serde(rename = "DID_SDID", skip_serializing_if = "Vec::is_empty")]
+    pub did_sdid: Vec<NmosDidSdid>,
+}
+
+/// Format specific part of a flow
+#[derive(Serialize, Clone)]
+#[serde(tag = "format")]
+pub enum NmosFlowFormat {
+    #[serde(rename = "urn:x-nmos:format:video")]
+    VideoRaw(NmosFlowVideoRaw),
+    #[serde(rename = "urn:x-nmos:format:audio")]
+    AudioRaw(NmosFlowAudioRaw),
+    #[serde(rename = "urn:x-nmos:format:data")]
+    Data(NmosFlowData),
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosFlow {
+    #[serde(flatten)]
+    pub base: NmosResource,
+    pub source_id: String,
+    pub device_id: String,
+    pub parents: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grain_rate: Option<NmosRational>,
+    #[serde(flatten)]
+    pub format: NmosFlowFormat,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct NmosSenderSubscription {
+    pub receiver_id: Option<String>,
+    pub active: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosSender {
+    #[serde(flatten)]
+    pub base: NmosResource,
+    pub flow_id: Option<String>,
+    pub transport: String,
+    pub device_id: String,
+    pub manifest_href: Option<String>,
+    pub interface_bindings: Vec<String>,
+    pub subscription: NmosSenderSubscription,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct NmosReceiverSubscription {
+    pub sender_id: Option<String>,
+    pub active: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct NmosReceiver {
+    #[serde(flatten)]
+    pub base: NmosResource,
+    pub device_id: String,
+    pub transport: String,
+    pub interface_bindings: Vec<String>,
+    pub subscription: NmosReceiverSubscription,
+    pub format: String,
+    pub caps: Map<String, Value>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(into = "u16", try_from = "u16")]
 #[repr(u16)]
@@ -272,8 +431,44 @@ impl NcTouchpointResourceNmosChannelMapping {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum NcTouchpoint {
-    Nmos(NcTouchpointNmos),
+    // Tried first, a channel mapping touchpoint would otherwise parse as Nmos and lose its ioId
     NmosChannelMapping(NcTouchpointNmosChannelMapping),
+    Nmos(NcTouchpointNmos),
+}
+
+impl NcTouchpoint {
+    /// Touchpoint to an IS-04 resource, e.g. `device` or `receiver`
+    pub fn nmos(resource_type: &str, id: &str) -> Self {
+        NcTouchpoint::Nmos(NcTouchpointNmos {
+            base: NcTouchpointBase {
+                context_namespace: "x-nmos".into(),
+            },
+            resource: NcTouchpointResourceNmos {
+                base: NcTouchpointResourceBase {
+                    resource_type: resource_type.into(),
+                },
+                id: id.into(),
+            },
+        })
+    }
+
+    /// Touchpoint to an IS-08 `input` or `output` of the device with the given id
+    pub fn nmos_channel_mapping(resource_type: &str, id: &str, io_id: &str) -> Self {
+        NcTouchpoint::NmosChannelMapping(NcTouchpointNmosChannelMapping {
+            base: NcTouchpointBase {
+                context_namespace: "x-nmos/channelmapping".into(),
+            },
+            resource: NcTouchpointResourceNmosChannelMapping {
+                base: NcTouchpointResourceNmos {
+                    base: NcTouchpointResourceBase {
+                        resource_type: resource_type.into(),
+                    },
+                    id: id.into(),
+                },
+                io_id: io_id.into(),
+            },
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -409,7 +604,7 @@ impl NcDescriptor {
     }
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NcBlockMemberDescriptor {
     #[serde(flatten)]
     pub base: NcDescriptor,
@@ -1457,7 +1652,7 @@ impl NcParameterConstraintsString {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct NcElementId {
     pub level: u32,
     pub index: u32,
@@ -1709,7 +1904,7 @@ impl NcProduct {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NcDeviceOperationalState {
     pub generic: NcDeviceGenericState,
     #[serde(rename = "deviceSpecificDetails")]
@@ -1750,7 +1945,7 @@ impl NcDeviceOperationalState {
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PropertyChangedEvent {
     pub oid: u64,
     #[serde(rename = "eventId")]
@@ -1769,7 +1964,7 @@ impl PropertyChangedEvent {
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PropertyChangedEventData {
     #[serde(rename = "propertyId")]
     pub property_id: NcElementId,
@@ -1885,8 +2080,6 @@ pub struct IdArgsValue {
 #[derive(Deserialize, Debug)]
 pub struct WsCommandMessage {
     pub commands: Vec<Command>,
-    #[serde(rename = "messageType")]
-    pub message_type: u16,
 }
 
 #[derive(Serialize, Debug)]
@@ -2206,9 +2399,18 @@ pub struct WsErrorMessage {
 
 #[derive(Deserialize, Debug)]
 pub struct WsSubscriptionMessage {
-    pub subscriptions: Vec<u64>,
-    #[serde(rename = "messageType")]
-    pub message_type: u16,
+    /// Complete set of subscriptions, replaces any existing ones (IS-12 semantics)
+    #[serde(default)]
+    pub subscriptions: Option<Vec<u64>>,
+    /// Oids to add to the existing subscriptions
+    #[serde(default)]
+    pub subscribe: Vec<u64>,
+    /// Oids to remove from the existing subscriptions
+    #[serde(default)]
+    pub unsubscribe: Vec<u64>,
+    /// When set, subscribing to a block also subscribes to everything nested under it
+    #[serde(default)]
+    pub subtree: Option<bool>,
 }
 
 #[derive(Serialize, Debug)]
@@ -2406,7 +2608,7 @@ pub struct WsNotificationMessage {
     pub message_type: u16,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(into = "u32", try_from = "u32")]
 #[repr(u32)]
 pub enum NcDeviceGenericState {
@@ -2438,7 +2640,7 @@ impl From<u32> for NcDeviceGenericState {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(into = "u32", try_from = "u32")]
 #[repr(u32)]
 pub enum NcResetCause {
@@ -2469,3 +2671,480 @@ impl From<u32> for NcResetCause {
         }
     }
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NcPropertyHolder {
+    pub id: NcElementId,
+    pub descriptor: NcPropertyDescriptor,
+    pub value: Value,
+}
+
+impl NcPropertyHolder {
+    pub fn get_type_descriptor(_include_inherited: bool) -> NcDatatypeDescriptorStruct {
+        NcDatatypeDescriptorStruct {
+            base: NcDatatypeDescriptor {
+                base: NcDescriptor {
+                    description: Some("Property holder descriptor".to_string()),
+                },
+                name: "NcPropertyHolder".to_string(),
+                type_: NcDatatypeType::Struct,
+                constraints: None,
+            },
+            fields: vec![
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Property id".to_string()),
+                    },
+                    name: "id".to_string(),
+                    type_name: Some("NcPropertyId".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Property descriptor".to_string()),
+                    },
+                    name: "descriptor".to_string(),
+                    type_name: Some("NcPropertyDescriptor".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Property value".to_string()),
+                    },
+                    name: "value".to_string(),
+                    type_name: None,
+                    is_nullable: true,
+                    is_sequence: false,
+                    constraints: None,
+                },
+            ],
+            parent_type: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NcObjectPropertiesHolder {
+    pub path: NcRolePath,
+    #[serde(rename = "dependencyPaths")]
+    pub dependency_paths: Vec<Vec<String>>,
+    #[serde(rename = "allowedMembersClasses")]
+    pub allowed_members_classes: Vec<Vec<u32>>,
+    pub values: Vec<NcPropertyHolder>,
+    #[serde(rename = "isRebuildable")]
+    pub is_rebuildable: bool,
+}
+
+impl NcObjectPropertiesHolder {
+    pub fn get_type_descriptor(_include_inherited: bool) -> NcDatatypeDescriptorStruct {
+        NcDatatypeDescriptorStruct {
+            base: NcDatatypeDescriptor {
+                base: NcDescriptor {
+                    description: Some("Object properties holder descriptor".to_string()),
+                },
+                name: "NcObjectPropertiesHolder".to_string(),
+                type_: NcDatatypeType::Struct,
+                constraints: None,
+            },
+            fields: vec![
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Object role path".to_string()),
+                    },
+                    name: "path".to_string(),
+                    type_name: Some("NcRolePath".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some(
+                            "Sequence of role paths of objects the restore of this object depends on"
+                                .to_string(),
+                        ),
+                    },
+                    name: "dependencyPaths".to_string(),
+                    type_name: Some("NcRolePath".to_string()),
+                    is_nullable: false,
+                    is_sequence: true,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some(
+                            "Classes which can be added to the members of a rebuildable block"
+                                .to_string(),
+                        ),
+                    },
+                    name: "allowedMembersClasses".to_string(),
+                    type_name: Some("NcClassId".to_string()),
+                    is_nullable: false,
+                    is_sequence: true,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Object properties values".to_string()),
+                    },
+                    name: "values".to_string(),
+                    type_name: Some("NcPropertyHolder".to_string()),
+                    is_nullable: false,
+                    is_sequence: true,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some(
+                            "Describes if the object is rebuildable".to_string(),
+                        ),
+                    },
+                    name: "isRebuildable".to_string(),
+                    type_name: Some("NcBoolean".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+            ],
+            parent_type: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NcBulkValuesHolder {
+    #[serde(rename = "validationFingerprint")]
+    pub validation_fingerprint: Option<String>,
+    pub values: Vec<NcObjectPropertiesHolder>,
+}
+
+impl NcBulkValuesHolder {
+    pub fn get_type_descriptor(_include_inherited: bool) -> NcDatatypeDescriptorStruct {
+        NcDatatypeDescriptorStruct {
+            base: NcDatatypeDescriptor {
+                base: NcDescriptor {
+                    description: Some("Bulk values holder descriptor".to_string()),
+                },
+                name: "NcBulkValuesHolder".to_string(),
+                type_: NcDatatypeType::Struct,
+                constraints: None,
+            },
+            fields: vec![
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some(
+                            "Optional vendor specific fingerprint used to validate the data set"
+                                .to_string(),
+                        ),
+                    },
+                    name: "validationFingerprint".to_string(),
+                    type_name: Some("NcString".to_string()),
+                    is_nullable: true,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Values of the objects in the data set".to_string()),
+                    },
+                    name: "values".to_string(),
+                    type_name: Some("NcObjectPropertiesHolder".to_string()),
+                    is_nullable: false,
+                    is_sequence: true,
+                    constraints: None,
+                },
+            ],
+            parent_type: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(into = "u32", try_from = "u32")]
+#[repr(u32)]
+pub enum NcRestoreMode {
+    Modify = 1,
+    Rebuild = 2,
+}
+
+impl From<NcRestoreMode> for u32 {
+    fn from(mode: NcRestoreMode) -> Self {
+        mode as u32
+    }
+}
+
+impl TryFrom<u32> for NcRestoreMode {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(NcRestoreMode::Modify),
+            2 => Ok(NcRestoreMode::Rebuild),
+            _ => Err(format!("Invalid restore mode {value}")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(into = "u32", try_from = "u32")]
+#[repr(u32)]
+pub enum NcRestoreValidationStatus {
+    Ok = 200,
+    Failed = 400,
+    NotFound = 404,
+    DeviceError = 500,
+}
+
+impl From<NcRestoreValidationStatus> for u32 {
+    fn from(status: NcRestoreValidationStatus) -> Self {
+        status as u32
+    }
+}
+
+impl From<u32> for NcRestoreValidationStatus {
+    fn from(value: u32) -> Self {
+        match value {
+            200 => NcRestoreValidationStatus::Ok,
+            400 => NcRestoreValidationStatus::Failed,
+            404 => NcRestoreValidationStatus::NotFound,
+            _ => NcRestoreValidationStatus::DeviceError,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(into = "u32", try_from = "u32")]
+#[repr(u32)]
+pub enum NcPropertyRestoreNoticeType {
+    Warning = 300,
+    Error = 400,
+}
+
+impl From<NcPropertyRestoreNoticeType> for u32 {
+    fn from(notice_type: NcPropertyRestoreNoticeType) -> Self {
+        notice_type as u32
+    }
+}
+
+impl From<u32> for NcPropertyRestoreNoticeType {
+    fn from(value: u32) -> Self {
+        match value {
+            300 => NcPropertyRestoreNoticeType::Warning,
+            _ => NcPropertyRestoreNoticeType::Error,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NcPropertyRestoreNotice {
+    pub id: NcElementId,
+    pub name: String,
+    #[serde(rename = "noticeType")]
+    pub notice_type: NcPropertyRestoreNoticeType,
+    #[serde(rename = "noticeMessage")]
+    pub notice_message: String,
+}
+
+impl NcPropertyRestoreNotice {
+    pub fn get_type_descriptor(_include_inherited: bool) -> NcDatatypeDescriptorStruct {
+        NcDatatypeDescriptorStruct {
+            base: NcDatatypeDescriptor {
+                base: NcDescriptor {
+                    description: Some("Property restore notice descriptor".to_string()),
+                },
+                name: "NcPropertyRestoreNotice".to_string(),
+                type_: NcDatatypeType::Struct,
+                constraints: None,
+            },
+            fields: vec![
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Property id".to_string()),
+                    },
+                    name: "id".to_string(),
+                    type_name: Some("NcPropertyId".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Property name".to_string()),
+                    },
+                    name: "name".to_string(),
+                    type_name: Some("NcName".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Property restore notice type".to_string()),
+                    },
+                    name: "noticeType".to_string(),
+                    type_name: Some("NcPropertyRestoreNoticeType".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Property restore notice message".to_string()),
+                    },
+                    name: "noticeMessage".to_string(),
+                    type_name: Some("NcString".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+            ],
+            parent_type: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NcObjectPropertiesSetValidation {
+    pub path: NcRolePath,
+    pub status: NcRestoreValidationStatus,
+    pub notices: Vec<NcPropertyRestoreNotice>,
+    #[serde(rename = "statusMessage")]
+    pub status_message: Option<String>,
+}
+
+impl NcObjectPropertiesSetValidation {
+    pub fn get_type_descriptor(_include_inherited: bool) -> NcDatatypeDescriptorStruct {
+        NcDatatypeDescriptorStruct {
+            base: NcDatatypeDescriptor {
+                base: NcDescriptor {
+                    description: Some("Object properties set validation descriptor".to_string()),
+                },
+                name: "NcObjectPropertiesSetValidation".to_string(),
+                type_: NcDatatypeType::Struct,
+                constraints: None,
+            },
+            fields: vec![
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Object role path".to_string()),
+                    },
+                    name: "path".to_string(),
+                    type_name: Some("NcRolePath".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Validation status".to_string()),
+                    },
+                    name: "status".to_string(),
+                    type_name: Some("NcRestoreValidationStatus".to_string()),
+                    is_nullable: false,
+                    is_sequence: false,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Validation property notices".to_string()),
+                    },
+                    name: "notices".to_string(),
+                    type_name: Some("NcPropertyRestoreNotice".to_string()),
+                    is_nullable: false,
+                    is_sequence: true,
+                    constraints: None,
+                },
+                NcFieldDescriptor {
+                    base: NcDescriptor {
+                        description: Some("Validation status message".to_string()),
+                    },
+                    name: "statusMessage".to_string(),
+                    type_name: Some("NcString".to_string()),
+                    is_nullable: true,
+                    is_sequence: false,
+                    constraints: None,
+                },
+            ],
+            parent_type: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct NcMethodResultBulkValuesHolder {
+    #[serde(flatten)]
+    pub base: NcMethodResult,
+    pub value: NcBulkValuesHolder,
+}
+
+impl NcMethodResultBulkValuesHolder {
+    pub fn get_type_descriptor(include_inherited: bool) -> NcDatatypeDescriptorStruct {
+        let mut current = NcDatatypeDescriptorStruct {
+            base: NcDatatypeDescriptor {
+                base: NcDescriptor {
+                    description: Some("Method result containing bulk values holder".to_string()),
+                },
+                name: "NcMethodResultBulkValuesHolder".to_string(),
+                type_: NcDatatypeType::Struct,
+                constraints: None,
+            },
+            fields: vec![NcFieldDescriptor {
+                base: NcDescriptor { description: None },
+                name: "value".to_string(),
+                type_name: Some("NcBulkValuesHolder".to_string()),
+                is_nullable: false,
+                is_sequence: false,
+                constraints: None,
+            }],
+            parent_type: Some("NcMethodResult".to_string()),
+        };
+        if include_inherited {
+            let base = NcMethodResult::get_type_descriptor(true);
+            current.fields.extend(base.fields);
+        }
+        current
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct NcMethodResultObjectPropertiesSetValidation {
+    #[serde(flatten)]
+    pub base: NcMethodResult,
+    pub value: Vec<NcObjectPropertiesSetValidation>,
+}
+
+impl NcMethodResultObjectPropertiesSetValidation {
+    pub fn get_type_descriptor(include_inherited: bool) -> NcDatatypeDescriptorStruct {
+        let mut current = NcDatatypeDescriptorStruct {
+            base: NcDatatypeDescriptor {
+                base: NcDescriptor {
+                    description: Some(
+                        "Method result containing object properties set validation results"
+                            .to_string(),
+                    ),
+                },
+                name: "NcMethodResultObjectPropertiesSetValidation".to_string(),
+                type_: NcDatatypeType::Struct,
+                constraints: None,
+            },
+            fields: vec![NcFieldDescriptor {
+                base: NcDescriptor { description: None },
+                name: "value".to_string(),
+                type_name: Some("NcObjectPropertiesSetValidation".to_string()),
+                is_nullable: false,
+                is_sequence: true,
+                constraints: None,
+            }],
+            parent_type: Some("NcMethodResult".to_string()),
+        };
+        if include_inherited {
+            let base = NcMethodResult::get_type_descriptor(true);
+            current.fields.extend(base.fields);
+        }
+        current
+    }
+}
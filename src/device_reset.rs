@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::{
+    AppState,
+    data_types::{NcDeviceGenericState, NcResetCause},
+    model::prepare_model,
+    outbound_queue::CLOSE_CODE_SERVICE_RESTART,
+    persistence::Persistence,
+};
+
+/// Restarts the device in process after a controller invoked Reset on the device manager:
+/// control sessions are closed as a service restart, then the model is rebuilt and the
+/// persisted values restored. Called once the Reset response has been queued.
+pub async fn restart_device(state: Arc<AppState>) {
+    tracing::info!("restarting on controller request");
+    let mut root = state.root_block.lock().await;
+
+    if let Some(persistence) = &state.persistence
+        && let Err(e) = persistence.record_reset_cause(NcResetCause::ControllerRequest)
+    {
+        tracing::warn!(error = %e, "cannot record the reset cause");
+    }
+    // Subscribers see Initializing before their connections close
+    if let Some(event) = root
+        .device_manager_mut()
+        .and_then(|device_manager| device_manager.begin_reset())
+    {
+        state.notify_subscribers(vec![event]).await;
+    }
+
+    // Clients are expected to reconnect and subscribe again once the device is back
+    for connection in state.connections.read().await.values() {
+        connection
+            .queue
+            .close_after_pending(CLOSE_CODE_SERVICE_RESTART, "Device reset");
+    }
+
+    // The new model reports to a channel of its own: the events of its initial and restored
+    // values are discarded, later ones are forwarded to the event loop
+    let (model_tx, mut model_rx) = mpsc::unbounded_channel();
+    let channel_mapping = state.channel_mapping.read().await;
+    match (state.model_factory)(&state.config, &channel_mapping, model_tx) {
+        Ok(model) => {
+            *root = model;
+            let reset_cause = state.persistence.as_ref().map_or(
+                NcResetCause::ControllerRequest,
+                Persistence::take_reset_cause,
+            );
+            prepare_model(
+                &mut root,
+                &mut *state.resources.write().await,
+                &channel_mapping,
+                state.persistence.as_ref(),
+                reset_cause,
+            );
+            while model_rx.try_recv().is_ok() {}
+            let event_tx = state.event_tx.clone();
+            tokio::spawn(async move {
+                while let Some(event) = model_rx.recv().await {
+                    let _ = event_tx.send(event);
+                }
+            });
+        }
+        Err(e) => {
+            // The previous model keeps running, the marker still reports the request later on
+            tracing::error!(error = %e, "cannot rebuild the device model");
+            if let Some(device_manager) = root.device_manager_mut() {
+                device_manager.set_message(Some(format!("Reset failed: {e}")));
+            }
+        }
+    }
+
+    if let Some(device_manager) = root.device_manager_mut()
+        && let Err(e) =
+            device_manager.set_operational_state(NcDeviceGenericState::NormalOperation, None)
+    {
+        tracing::debug!(error = %e, "operational state left unchanged");
+    }
+}
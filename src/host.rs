@@ -0,0 +1,98 @@
+use std::{net::IpAddr, path::Path};
+
+use crate::data_types::NmosInterface;
+
+/// Hardware type of Ethernet interfaces in `/sys/class/net/<name>/type`
+const ARPHRD_ETHER: &str = "1";
+
+/// A network interface of the host with the addresses bound to it
+#[derive(Debug, Clone)]
+pub struct HostInterface {
+    pub name: String,
+    /// MAC address in IS-04 form, e.g. `00-15-5d-67-c3-4e`
+    pub mac: String,
+    pub addresses: Vec<IpAddr>,
+}
+
+/// Ethernet interfaces listed in `/sys/class/net`, sorted by name, with their addresses. Idle
+/// virtual interfaces are left out.
+pub fn discover_interfaces() -> Vec<HostInterface> {
+    let entries = match std::fs::read_dir("/sys/class/net") {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!(error = %e, "cannot list network interfaces");
+            return Vec::new();
+        }
+    };
+    let addresses = if_addrs::get_if_addrs().unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "cannot read interface addresses");
+        Vec::new()
+    });
+
+    let mut interfaces: Vec<HostInterface> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let mac = read_ethernet_mac(&entry.path())?;
+            let addresses: Vec<IpAddr> = addresses
+                .iter()
+                .filter(|a| a.name == name && !a.is_loopback())
+                .map(|a| a.ip())
+                .collect();
+            // Virtual interfaces only count when they carry addresses, e.g. bridges and bonds
+            let physical = entry.path().join("device").exists();
+            if !physical && addresses.is_empty() {
+                return None;
+            }
+            Some(HostInterface {
+                name,
+                mac,
+                addresses,
+            })
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    interfaces
+}
+
+/// None for loopback, tunnels and anything else without an Ethernet hardware address
+fn read_ethernet_mac(path: &Path) -> Option<String> {
+    let read = |file: &str| std::fs::read_to_string(path.join(file)).ok();
+    if read("type")?.trim() != ARPHRD_ETHER {
+        return None;
+    }
+    let mac = read("address")?.trim().replace(':', "-").to_lowercase();
+    (mac.len() == 17 && mac != "00-00-00-00-00-00").then_some(mac)
+}
+
+/// IS-04 descriptions of the interfaces, the chassis is identified by the MAC of the first one
+pub fn nmos_interfaces(interfaces: &[HostInterface]) -> Vec<NmosInterface> {
+    let Some(chassis_id) = interfaces.first().map(|i| i.mac.clone()) else {
+        return Vec::new();
+    };
+    interfaces
+        .iter()
+        .map(|interface| NmosInterface {
+            chassis_id: chassis_id.clone(),
+            name: interface.name.clone(),
+            port_id: interface.mac.clone(),
+        })
+        .collect()
+}
+
+/// Addresses the APIs can be reached on: the bind address itself, or every address of the
+/// interfaces when bound to all of them (IPv6 ones only when listening on `::`). Link-local IPv6
+/// addresses are left out as they are unusable without a zone.
+pub fn endpoint_addresses(bind: IpAddr, interfaces: &[HostInterface]) -> Vec<IpAddr> {
+    if !bind.is_unspecified() {
+        return vec![bind];
+    }
+    interfaces
+        .iter()
+        .flat_map(|interface| interface.addresses.iter().copied())
+        .filter(|address| match address {
+            IpAddr::V4(_) => true,
+            IpAddr::V6(v6) => bind.is_ipv6() && !v6.is_unicast_link_local(),
+        })
+        .collect()
+}
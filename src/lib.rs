@@ -0,0 +1,199 @@
+//! Framework for NMOS devices controlled through IS-12: the MS-05-02 classes and the
+//! [`NcMember`] trait to add your own, the IS-04, IS-08 and IS-14 APIs and the IS-12 WebSocket,
+//! assembled into a ready-to-serve device by [`DeviceServerBuilder`], plus an IS-12 `client` to
+//! drive devices from tests and tools (`client` feature).
+
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tokio::sync::{Mutex, RwLock, Semaphore, mpsc};
+use uuid::Uuid;
+
+// Declare modules
+mod advertisement;
+pub mod auth;
+pub mod channel_mapping;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod config;
+mod configuration;
+pub mod data_types;
+mod device_reset;
+mod host;
+pub mod model;
+pub mod model_builder;
+pub mod model_loader;
+pub mod nc_block;
+pub mod nc_bulk_properties_manager;
+pub mod nc_class_manager;
+pub mod nc_device_manager;
+pub mod nc_ident_beacon;
+pub mod nc_manager;
+pub mod nc_object;
+pub mod nc_role_path;
+pub mod nc_worker;
+mod node_api;
+mod node_api_versions;
+pub mod node_resources;
+pub mod outbound_queue;
+pub mod persistence;
+mod protocol;
+pub mod ptp;
+pub mod router;
+pub mod server;
+pub mod tai;
+pub mod tls;
+pub mod websocket;
+
+pub use model_builder::{MemberBuilder, ModelBuilder};
+pub use nc_object::NcMember;
+pub use server::{DeviceServer, DeviceServerBuilder, ModelFactory};
+
+// Imports
+use crate::{
+    auth::Authorizer,
+    channel_mapping::ChannelMapping,
+    config::Config,
+    data_types::{PropertyChangedEvent, WsSubscriptionMessage},
+    nc_block::NcBlock,
+    node_resources::NodeResources,
+    outbound_queue::{OutboundQueue, PushOutcome},
+    persistence::Persistence,
+    websocket::WebSocketConfig,
+};
+
+// === AppState ===
+
+pub struct AppState {
+    pub connections: RwLock<HashMap<Uuid, ConnectionState>>,
+    /// One permit per WebSocket connection allowed, taken before the upgrade and held until the
+    /// connection ends. None when the number of connections is not limited.
+    pub connection_slots: Option<Arc<Semaphore>>,
+    /// Node, device, sources, flows, senders and receivers served by the Node API
+    pub resources: RwLock<NodeResources>,
+    pub root_block: Mutex<NcBlock>,
+    /// IS-08 inputs, outputs and channel map
+    pub channel_mapping: RwLock<ChannelMapping>,
+    /// Handed to the objects of models rebuilt on a reset
+    pub event_tx: mpsc::UnboundedSender<PropertyChangedEvent>,
+    /// Builds the model at startup and again on every reset
+    pub model_factory: ModelFactory,
+    pub event_rx: Mutex<mpsc::UnboundedReceiver<PropertyChangedEvent>>,
+    pub ws_config: WebSocketConfig,
+    /// Access token validation, None when authorization is disabled
+    pub auth: Option<Authorizer>,
+    /// Writable property values kept across restarts, None when disabled
+    pub persistence: Option<Persistence>,
+    pub config: Config,
+}
+
+/// Each WebSocket connection’s state
+#[derive(Debug)]
+pub struct ConnectionState {
+    /// Oids explicitly subscribed to by the client
+    pub subscribed_oids: HashSet<u64>,
+    /// When true, subscriptions to blocks include every object nested under them
+    pub subscribe_subtree: bool,
+    /// Oids nested under subscribed blocks (only populated in subtree mode)
+    pub subtree_oids: HashSet<u64>,
+    pub queue: Arc<OutboundQueue>,
+}
+
+impl ConnectionState {
+    pub fn new(queue: Arc<OutboundQueue>) -> Self {
+        ConnectionState {
+            subscribed_oids: HashSet::new(),
+            subscribe_subtree: false,
+            subtree_oids: HashSet::new(),
+            queue,
+        }
+    }
+
+    pub fn is_subscribed(&self, oid: u64) -> bool {
+        self.subscribed_oids.contains(&oid) || self.subtree_oids.contains(&oid)
+    }
+
+    /// Applies a subscription message, only keeping oids which exist in the model.
+    pub fn apply_subscription(&mut self, sub: &WsSubscriptionMessage, root: &NcBlock) {
+        if let Some(subtree) = sub.subtree {
+            self.subscribe_subtree = subtree;
+        }
+        if let Some(subscriptions) = &sub.subscriptions {
+            self.subscribed_oids = subscriptions.iter().cloned().collect();
+        }
+        self.subscribed_oids.extend(sub.subscribe.iter().cloned());
+        for oid in &sub.unsubscribe {
+            self.subscribed_oids.remove(oid);
+        }
+
+        self.refresh(root);
+    }
+
+    /// Drops subscriptions to objects no longer in the model and re-expands subtrees.
+    pub fn refresh(&mut self, root: &NcBlock) {
+        self.subscribed_oids.retain(|oid| root.contains_oid(*oid));
+
+        self.subtree_oids.clear();
+        if self.subscribe_subtree {
+            for oid in &self.subscribed_oids {
+                if let Some(oids) = root.subtree_oids(*oid) {
+                    self.subtree_oids.extend(oids);
+                }
+            }
+        }
+    }
+
+    /// Currently subscribed oids in ascending order
+    pub fn subscriptions(&self) -> Vec<u64> {
+        self.subscribed_oids.iter().cloned().sorted().collect()
+    }
+}
+
+impl AppState {
+    /// Broadcasts a batch of property changes to subscribed clients, each client receives
+    /// a single notification message with the changes it is subscribed to.
+    pub async fn notify_subscribers(&self, events: Vec<PropertyChangedEvent>) {
+        let conns = self.connections.read().await;
+
+        for (conn_id, conn) in conns.iter() {
+            let notifications: Vec<PropertyChangedEvent> = events
+                .iter()
+                .filter(|e| conn.is_subscribed(e.oid))
+                .cloned()
+                .collect();
+            if notifications.is_empty() {
+                continue;
+            }
+
+            let outcome = conn.queue.push_notification(notifications);
+            if outcome == PushOutcome::Disconnected {
+                tracing::warn!("Connection {} closed: outbound queue overflow", conn_id);
+            }
+        }
+    }
+
+    /// Removes an object (and everything nested under it) from the block holding it. Subscribers
+    /// of that block are notified of its new members and subscriptions to removed objects are
+    /// dropped before returning.
+    pub async fn remove_member(&self, oid: u64) -> Option<Box<dyn NcMember>> {
+        let mut root = self.root_block.lock().await;
+        let removed = root.remove_member(oid)?;
+        let mut conns = self.connections.write().await;
+        for conn in conns.values_mut() {
+            conn.refresh(&root);
+        }
+        Some(removed)
+    }
+
+    /// Re-validates every connection's subscriptions against the current model, picking up
+    /// objects added under blocks subscribed to in subtree mode
+    pub async fn refresh_subscriptions(&self) {
+        let root = self.root_block.lock().await;
+        let mut conns = self.connections.write().await;
+        for conn in conns.values_mut() {
+            conn.refresh(&root);
+        }
+    }
+}
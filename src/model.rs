@@ -0,0 +1,463 @@
+use serde_json::{Map, json};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    channel_mapping::ChannelMapping,
+    config::{Config, DeviceConfig},
+    data_types::{
+        NcResetCause, NcTouchpoint, NmosChannel, NmosComponent, NmosDidSdid, NmosFlow,
+        NmosFlowAudioRaw, NmosFlowData, NmosFlowFormat, NmosFlowVideoRaw, NmosRational,
+        NmosReceiver, NmosReceiverSubscription, NmosResource, NmosSender, NmosSenderSubscription,
+        NmosSource, NmosSourceFormat, PropertyChangedEvent,
+    },
+    model_loader::{ClassFactoryRegistry, ModelDescription, load_model},
+    nc_block::NcBlock,
+    nc_bulk_properties_manager::NcBulkPropertiesManager,
+    nc_class_manager::NcClassManager,
+    nc_device_manager::NcDeviceManager,
+    nc_ident_beacon::NcIdentBeacon,
+    nc_object::NcObject,
+    nc_worker::NcWorker,
+    node_resources::{NodeResources, find_by_id},
+    persistence::Persistence,
+    tai,
+};
+
+/// Builds the model from the configured description, or the built-in example model
+pub fn build_model(
+    config: &Config,
+    channel_mapping: &ChannelMapping,
+    tx: mpsc::UnboundedSender<PropertyChangedEvent>,
+) -> anyhow::Result<NcBlock> {
+    match &config.model_file {
+        Some(path) => {
+            let description = ModelDescription::from_file(path)?;
+            Ok(load_model(
+                &description,
+                &ClassFactoryRegistry::default(),
+                &config.device,
+                tx,
+            )?)
+        }
+        None => Ok(build_default_model(&config.device, channel_mapping, tx)),
+    }
+}
+
+/// Readies a freshly built model for serving: persisted values are restored, the IS-04 device
+/// label follows deviceName and the device manager reports why the device (re)started
+pub fn prepare_model(
+    root: &mut NcBlock,
+    resources: &mut NodeResources,
+    channel_mapping: &ChannelMapping,
+    persistence: Option<&Persistence>,
+    reset_cause: NcResetCause,
+) {
+    check_touchpoints(root, resources, channel_mapping);
+    if let Some(persistence) = persistence {
+        persistence.restore(root);
+    }
+    if let Some(device_manager) = root.device_manager_mut() {
+        resources.set_device_name(device_manager.device_name.as_deref());
+        tracing::info!(?reset_cause, "starting");
+        device_manager.set_reset_cause(reset_cause);
+    }
+}
+
+/// Builds the example device model: the managers plus a few objects, workers, a nested block and
+/// a block with one worker per IS-08 input and output
+pub fn build_default_model(
+    device: &DeviceConfig,
+    channel_mapping: &ChannelMapping,
+    tx: mpsc::UnboundedSender<PropertyChangedEvent>,
+) -> NcBlock {
+    // Create the root block
+    let mut root = NcBlock::new(
+        true,
+        vec![1, 1],
+        1,
+        true,
+        None,
+        "root",
+        None,
+        true,
+        None,
+        None,
+        tx.clone(),
+    );
+
+    let device_manager = NcDeviceManager::new(
+        2,
+        true,
+        Some(1),
+        "DeviceManager",
+        Some("Device Manager"),
+        Some(vec![NcTouchpoint::nmos("device", &device.id)]),
+        None,
+        "v1.0.0".to_string(),
+        device.manufacturer.clone(),
+        device.product.clone(),
+        device.serial_number.clone(),
+        tx.clone(),
+    );
+    root.add_member(Box::new(device_manager));
+
+    let class_manager = NcClassManager::new(
+        3,
+        true,
+        Some(1),
+        "ClassManager",
+        Some("Class Manager"),
+        None,
+        None,
+        tx.clone(),
+    );
+    root.add_member(Box::new(class_manager));
+
+    let bulk_properties_manager = NcBulkPropertiesManager::new(
+        9,
+        true,
+        Some(1),
+        "BulkPropertiesManager",
+        Some("Bulk Properties Manager"),
+        None,
+        None,
+        tx.clone(),
+    );
+    root.add_member(Box::new(bulk_properties_manager));
+
+    let ident_beacon = NcIdentBeacon::new(
+        10,
+        true,
+        Some(1),
+        "IdentBeacon",
+        Some("Identification beacon"),
+        None,
+        None,
+        device.identification_timeout,
+        tx.clone(),
+    );
+    root.add_member(Box::new(ident_beacon));
+
+    // Add NcObject member
+    let obj_1 = NcObject::new(
+        vec![1],
+        4,
+        true,
+        Some(1),
+        "my-obj-01",
+        Some("My object 01"),
+        None,
+        None,
+        tx.clone(),
+    );
+    root.add_member(Box::new(obj_1));
+
+    // Add NcWorker member
+    let worker_1 = NcWorker::new(
+        vec![1, 2],
+        5,
+        true,
+        Some(1),
+        "my-worker-01",
+        Some("My worker 01"),
+        None,
+        None,
+        tx.clone(),
+    );
+    root.add_member(Box::new(worker_1));
+
+    // Add NcBlock member
+    let mut block_1 = NcBlock::new(
+        false,
+        vec![1, 1],
+        6,
+        true,
+        Some(1),
+        "my-block-01",
+        None,
+        true,
+        None,
+        None,
+        tx.clone(),
+    );
+
+    let obj_2 = NcObject::new(
+        vec![1],
+        7,
+        true,
+        Some(6),
+        "my-nested-block-obj",
+        None,
+        None,
+        None,
+        tx.clone(),
+    );
+
+    let worker_2 = NcWorker::new(
+        vec![1, 2],
+        8,
+        true,
+        Some(6),
+        "my-worker-02",
+        Some("My worker 02"),
+        None,
+        None,
+        tx.clone(),
+    );
+    block_1.add_member(Box::new(obj_2));
+    block_1.add_member(Box::new(worker_2));
+    root.add_member(Box::new(block_1));
+
+    root.add_member(Box::new(build_channel_mapping_block(
+        11,
+        1,
+        &device.id,
+        channel_mapping,
+        tx,
+    )));
+
+    root
+}
+
+/// A block with a worker per IS-08 input and output, each with a touchpoint to its io so
+/// controllers can find the objects processing the channels they map
+fn build_channel_mapping_block(
+    oid: u64,
+    owner: u64,
+    device_id: &str,
+    channel_mapping: &ChannelMapping,
+    tx: mpsc::UnboundedSender<PropertyChangedEvent>,
+) -> NcBlock {
+    let mut block = NcBlock::new(
+        false,
+        vec![1, 1],
+        oid,
+        true,
+        Some(owner),
+        "ChannelMapping",
+        Some("Audio channel mapping"),
+        true,
+        None,
+        None,
+        tx.clone(),
+    );
+
+    let inputs = channel_mapping
+        .inputs()
+        .iter()
+        .map(|input| ("input", &input.id, &input.properties.name));
+    let outputs = channel_mapping
+        .outputs()
+        .iter()
+        .map(|output| ("output", &output.id, &output.properties.name));
+    for ((resource_type, io_id, name), member_oid) in inputs.chain(outputs).zip(oid + 1..) {
+        let touchpoint = NcTouchpoint::nmos_channel_mapping(resource_type, device_id, io_id);
+        block.add_member(Box::new(NcWorker::new(
+            vec![1, 2],
+            member_oid,
+            true,
+            Some(oid),
+            &format!("{resource_type}-{io_id}"),
+            Some(name),
+            Some(vec![touchpoint]),
+            None,
+            tx.clone(),
+        )));
+    }
+
+    block
+}
+
+/// Registers the example IS-04 resources: video, audio and data senders with their sources and
+/// flows plus a video and an audio receiver. Ids are derived from the device id so they stay the
+/// same across restarts.
+pub fn build_default_resources(resources: &mut NodeResources) {
+    let device_id = resources.device().base.id.clone();
+    let node = resources.node();
+    let clock_name = node.clocks.first().map(|clock| clock.name.clone());
+    let interface_bindings: Vec<String> = node.interfaces.iter().map(|i| i.name.clone()).collect();
+    let grain_rate = Some(NmosRational {
+        numerator: 50,
+        denominator: 1,
+    });
+
+    let essences = [
+        (
+            "video",
+            NmosSourceFormat::Video,
+            grain_rate,
+            NmosFlowFormat::VideoRaw(NmosFlowVideoRaw {
+                media_type: "video/raw".into(),
+                frame_width: 1920,
+                frame_height: 1080,
+                interlace_mode: "progressive".into(),
+                colorspace: "BT709".into(),
+                transfer_characteristic: "SDR".into(),
+                components: [("Y", 1920), ("Cb", 960), ("Cr", 960)]
+                    .into_iter()
+                    .map(|(name, width)| NmosComponent {
+                        name: name.into(),
+                        width,
+                        height: 1080,
+                        bit_depth: 10,
+                    })
+                    .collect(),
+            }),
+        ),
+        (
+            "audio",
+            NmosSourceFormat::Audio {
+                channels: ["Left", "Right"]
+                    .into_iter()
+                    .zip(["L", "R"])
+                    .map(|(label, symbol)| NmosChannel {
+                        label: label.into(),
+                        symbol: Some(symbol.into()),
+                    })
+                    .collect(),
+            },
+            None,
+            NmosFlowFormat::AudioRaw(NmosFlowAudioRaw {
+                media_type: "audio/L24".into(),
+                sample_rate: NmosRational {
+                    numerator: 48000,
+                    denominator: 1,
+                },
+                bit_depth: 24,
+            }),
+        ),
+        (
+            "data",
+            NmosSourceFormat::Data,
+            grain_rate,
+            NmosFlowFormat::Data(NmosFlowData {
+                media_type: "video/smpte291".into(),
+                did_sdid: vec![NmosDidSdid {
+                    did: "0x41".into(),
+                    sdid: "0x07".into(),
+                }],
+            }),
+        ),
+    ];
+
+    for (essence, source_format, grain_rate, flow_format) in essences {
+        let source_id = example_resource_id(&device_id, &format!("source/{essence}"));
+        let flow_id = example_resource_id(&device_id, &format!("flow/{essence}"));
+        resources.insert_source(NmosSource {
+            base: example_resource(&source_id, &format!("Example {essence} source")),
+            caps: Map::new(),
+            device_id: device_id.clone(),
+            parents: Vec::new(),
+            clock_name: clock_name.clone(),
+            grain_rate,
+            format: source_format,
+        });
+        resources.insert_flow(NmosFlow {
+            base: example_resource(&flow_id, &format!("Example {essence} flow")),
+            source_id,
+            device_id: device_id.clone(),
+            parents: Vec::new(),
+            grain_rate,
+            format: flow_format,
+        });
+        resources.insert_sender(NmosSender {
+            base: example_resource(
+                &example_resource_id(&device_id, &format!("sender/{essence}")),
+                &format!("Example {essence} sender"),
+            ),
+            flow_id: Some(flow_id),
+            transport: "urn:x-nmos:transport:rtp.mcast".into(),
+            device_id: device_id.clone(),
+            manifest_href: None,
+            interface_bindings: interface_bindings.clone(),
+            subscription: NmosSenderSubscription::default(),
+        });
+    }
+
+    for (essence, format, media_type) in [
+        ("video", "urn:x-nmos:format:video", "video/raw"),
+        ("audio", "urn:x-nmos:format:audio", "audio/L24"),
+    ] {
+        let mut caps = Map::new();
+        caps.insert("media_types".into(), json!([media_type]));
+        resources.insert_receiver(NmosReceiver {
+            base: example_resource(
+                &example_resource_id(&device_id, &format!("receiver/{essence}")),
+                &format!("Example {essence} receiver"),
+            ),
+            device_id: device_id.clone(),
+            transport: "urn:x-nmos:transport:rtp".into(),
+            interface_bindings: interface_bindings.clone(),
+            subscription: NmosReceiverSubscription::default(),
+            format: format.into(),
+            caps,
+        });
+    }
+}
+
+fn example_resource(id: &str, label: &str) -> NmosResource {
+    NmosResource {
+        id: id.to_string(),
+        label: label.to_string(),
+        description: label.to_string(),
+        version: tai::next_version(),
+        tags: HashMap::new(),
+    }
+}
+
+fn example_resource_id(device_id: &str, name: &str) -> String {
+    let namespace = Uuid::parse_str(device_id).unwrap_or_default();
+    Uuid::new_v5(&namespace, name.as_bytes()).to_string()
+}
+
+/// Logs where the touchpoints of the model point to, warning about those naming an IS-04
+/// resource or IS-08 input or output this node does not have
+pub fn check_touchpoints(
+    root: &NcBlock,
+    resources: &NodeResources,
+    channel_mapping: &ChannelMapping,
+) {
+    for (role_path, touchpoint) in root.touchpoints_by_role_path() {
+        let nmos = match &touchpoint {
+            NcTouchpoint::Nmos(nmos) => nmos,
+            NcTouchpoint::NmosChannelMapping(mapping) => {
+                let io_id = mapping.resource.io_id.as_str();
+                let known = match mapping.resource.base.base.resource_type.as_str() {
+                    "input" => channel_mapping.find_input(io_id).is_some(),
+                    "output" => channel_mapping.find_output(io_id).is_some(),
+                    _ => false,
+                };
+                match known {
+                    true => tracing::debug!(%role_path, ?touchpoint, "touchpoint"),
+                    false => tracing::warn!(
+                        %role_path,
+                        resource_type = mapping.resource.base.base.resource_type,
+                        io_id,
+                        "touchpoint refers to an unknown IS-08 input or output"
+                    ),
+                }
+                continue;
+            }
+        };
+        let id = nmos.resource.id.as_str();
+        let known = match nmos.resource.base.resource_type.as_str() {
+            "device" => resources.device().base.id == id,
+            "source" => find_by_id(resources.sources(), id).is_some(),
+            "flow" => find_by_id(resources.flows(), id).is_some(),
+            "sender" => find_by_id(resources.senders(), id).is_some(),
+            "receiver" => find_by_id(resources.receivers(), id).is_some(),
+            _ => true,
+        };
+        match known {
+            true => tracing::debug!(%role_path, ?touchpoint, "touchpoint"),
+            false => tracing::warn!(
+                %role_path,
+                resource_type = nmos.resource.base.resource_type,
+                resource_id = id,
+                "touchpoint refers to an unknown IS-04 resource"
+            ),
+        }
+    }
+}
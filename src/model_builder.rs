@@ -0,0 +1,310 @@
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::{
+    config::DeviceConfig,
+    data_types::{
+        NcClassDescriptor, NcElementId, NcPropertyConstraints, NcTouchpoint, PropertyChangedEvent,
+    },
+    model_loader::{
+        ClassFactoryRegistry, FactoryContext, MemberDescription, ModelDescription, ModelError,
+        PropertyValue, load_model,
+    },
+    nc_block::NcBlock,
+    nc_object::NcMember,
+};
+
+/// Assembles a device model in code. The tree is built through the same loader as model files,
+/// so oids, owners and the notifier are assigned automatically and roles are checked the same way.
+#[derive(Clone)]
+pub struct ModelBuilder {
+    description: ModelDescription,
+    registry: ClassFactoryRegistry,
+}
+
+impl Default for ModelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModelBuilder {
+    /// A root block holding the device manager and the class manager every model needs
+    pub fn new() -> Self {
+        ModelBuilder {
+            description: ModelDescription {
+                user_label: None,
+                touchpoints: None,
+                members: vec![
+                    MemberBuilder::new("NcDeviceManager", "DeviceManager")
+                        .user_label("Device Manager")
+                        .description,
+                    MemberBuilder::new("NcClassManager", "ClassManager")
+                        .user_label("Class Manager")
+                        .description,
+                ],
+            },
+            registry: ClassFactoryRegistry::default(),
+        }
+    }
+
+    pub fn user_label(mut self, user_label: &str) -> Self {
+        self.description.user_label = Some(user_label.to_string());
+        self
+    }
+
+    pub fn touchpoint(mut self, touchpoint: NcTouchpoint) -> Self {
+        self.description
+            .touchpoints
+            .get_or_insert_default()
+            .push(touchpoint);
+        self
+    }
+
+    /// Adds a member to the root block
+    pub fn member(mut self, member: MemberBuilder) -> Self {
+        self.description.members.push(member.description);
+        self
+    }
+
+    /// Makes a class of your own available to [`MemberBuilder::new`] under the name of its
+    /// descriptor and known to the class manager
+    pub fn class(
+        mut self,
+        descriptor: NcClassDescriptor,
+        factory: impl Fn(&FactoryContext) -> Box<dyn NcMember> + Send + Sync + 'static,
+    ) -> Self {
+        self.registry.register_class(descriptor, factory);
+        self
+    }
+
+    /// Creates the objects, can be called again to rebuild the model on a reset
+    pub fn build(
+        &self,
+        device: &DeviceConfig,
+        notifier: mpsc::UnboundedSender<PropertyChangedEvent>,
+    ) -> Result<NcBlock, ModelError> {
+        load_model(&self.description, &self.registry, device, notifier)
+    }
+}
+
+/// One object of a model built with [`ModelBuilder`], and the members nested under it for blocks
+#[derive(Debug, Clone)]
+pub struct MemberBuilder {
+    description: MemberDescription,
+}
+
+impl MemberBuilder {
+    /// An object of a registered class, e.g. `NcIdentBeacon` or `NcBulkPropertiesManager`
+    pub fn new(class: &str, role: &str) -> Self {
+        MemberBuilder {
+            description: MemberDescription {
+                class: class.to_string(),
+                class_id: None,
+                role: role.to_string(),
+                oid: None,
+                user_label: None,
+                touchpoints: None,
+                runtime_property_constraints: None,
+                properties: Vec::new(),
+                members: None,
+            },
+        }
+    }
+
+    pub fn object(role: &str) -> Self {
+        Self::new("NcObject", role)
+    }
+
+    pub fn worker(role: &str) -> Self {
+        Self::new("NcWorker", role)
+    }
+
+    pub fn block(role: &str) -> Self {
+        Self::new("NcBlock", role)
+    }
+
+    /// Class id of a derived class, extending the class id of the registered class
+    pub fn class_id(mut self, class_id: Vec<u32>) -> Self {
+        self.description.class_id = Some(class_id);
+        self
+    }
+
+    /// Fixed oid instead of the next free one
+    pub fn oid(mut self, oid: u64) -> Self {
+        self.description.oid = Some(oid);
+        self
+    }
+
+    pub fn user_label(mut self, user_label: &str) -> Self {
+        self.description.user_label = Some(user_label.to_string());
+        self
+    }
+
+    pub fn touchpoint(mut self, touchpoint: NcTouchpoint) -> Self {
+        self.description
+            .touchpoints
+            .get_or_insert_default()
+            .push(touchpoint);
+        self
+    }
+
+    pub fn runtime_property_constraint(mut self, constraint: NcPropertyConstraints) -> Self {
+        self.description
+            .runtime_property_constraints
+            .get_or_insert_default()
+            .push(constraint);
+        self
+    }
+
+    /// Initial value of a property, applied with Set once the object is created
+    pub fn property(mut self, id: NcElementId, value: Value) -> Self {
+        self.description
+            .properties
+            .push(PropertyValue { id, value });
+        self
+    }
+
+    /// Adds a member to this block, building the model fails for other classes
+    pub fn member(mut self, member: MemberBuilder) -> Self {
+        self.description
+            .members
+            .get_or_insert_default()
+            .push(member.description);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_types::{IdArgs, NcDescriptor},
+        nc_role_path::NcRolePath,
+    };
+    use serde_json::json;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    fn build(builder: &ModelBuilder) -> Result<NcBlock, ModelError> {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        builder.build(&DeviceConfig::default(), tx)
+    }
+
+    /// Oid and owner of the object at a role path like `root.outputs.gain`
+    fn oid_and_owner(root: &NcBlock, role_path: &str) -> (u64, Value) {
+        let oid = root
+            .resolve_role_path(&NcRolePath::parse(role_path).unwrap())
+            .unwrap();
+        let owner_id = IdArgs {
+            id: NcElementId { level: 1, index: 4 },
+        };
+        let (_, owner, _) = root.find_object(oid).unwrap().get_property(oid, &owner_id);
+        (oid, owner)
+    }
+
+    /// Adds a gain property to NcWorker
+    fn gain_descriptor() -> NcClassDescriptor {
+        NcClassDescriptor {
+            base: NcDescriptor {
+                description: Some("Gain control".to_string()),
+            },
+            class_id: vec![1, 2, 0, 1],
+            name: "GainControl".to_string(),
+            fixed_role: None,
+            properties: vec![],
+            methods: vec![],
+            events: vec![],
+        }
+    }
+
+    #[test]
+    fn oids_and_owners_follow_the_tree() {
+        let builder = ModelBuilder::new()
+            .member(MemberBuilder::object("first"))
+            .member(
+                MemberBuilder::block("outputs")
+                    .member(MemberBuilder::worker("gain"))
+                    .member(MemberBuilder::worker("mute").oid(100)),
+            )
+            .member(MemberBuilder::worker("last"));
+        let root = build(&builder).unwrap();
+
+        // The managers come first, free oids are handed out depth first
+        assert_eq!(oid_and_owner(&root, "root.DeviceManager"), (2, json!(1)));
+        assert_eq!(oid_and_owner(&root, "root.ClassManager"), (3, json!(1)));
+        assert_eq!(oid_and_owner(&root, "root.first"), (4, json!(1)));
+        assert_eq!(oid_and_owner(&root, "root.outputs"), (5, json!(1)));
+        assert_eq!(oid_and_owner(&root, "root.outputs.gain"), (6, json!(5)));
+        assert_eq!(oid_and_owner(&root, "root.outputs.mute"), (100, json!(5)));
+        assert_eq!(oid_and_owner(&root, "root.last"), (7, json!(1)));
+    }
+
+    #[test]
+    fn registered_classes_reach_the_class_manager() {
+        let built = Arc::new(AtomicUsize::new(0));
+        let counter = built.clone();
+        let builder = ModelBuilder::new()
+            .class(gain_descriptor(), move |ctx| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Box::new(ctx.worker())
+            })
+            .member(MemberBuilder::new("GainControl", "gain"));
+
+        let root = build(&builder).unwrap();
+        build(&builder).unwrap();
+        assert_eq!(built.load(Ordering::SeqCst), 2);
+        let (oid, _) = oid_and_owner(&root, "root.gain");
+        assert_eq!(root.find_object(oid).unwrap().get_class_id(), [1, 2, 0, 1]);
+
+        let class_manager = root.class_manager().unwrap();
+        let own = class_manager
+            .get_control_class_descriptor(&[1, 2, 0, 1], false)
+            .unwrap();
+        assert_eq!(own.name, "GainControl");
+        assert!(own.properties.is_empty());
+
+        // Inherited elements come from the closest known ancestor, NcWorker
+        let inherited = class_manager
+            .get_control_class_descriptor(&[1, 2, 0, 1], true)
+            .unwrap();
+        let worker = crate::nc_worker::NcWorker::get_class_descriptor(true);
+        assert_eq!(inherited.name, "GainControl");
+        assert_eq!(inherited.properties.len(), worker.properties.len());
+        assert_eq!(inherited.methods.len(), worker.methods.len());
+    }
+
+    #[test]
+    fn only_blocks_have_members() {
+        let builder = ModelBuilder::new()
+            .member(MemberBuilder::worker("gain").member(MemberBuilder::object("nested")));
+        assert!(matches!(
+            build(&builder),
+            Err(ModelError::MembersNotAllowed { role_path, .. }) if role_path == "root.gain"
+        ));
+    }
+
+    #[test]
+    fn initial_property_values_are_applied() {
+        let user_label = NcElementId { level: 1, index: 6 };
+        let builder = ModelBuilder::new()
+            .member(MemberBuilder::worker("gain").property(user_label.clone(), json!("Initial")));
+        let root = build(&builder).unwrap();
+        let (oid, _) = oid_and_owner(&root, "root.gain");
+        let (_, value, _) = root
+            .find_object(oid)
+            .unwrap()
+            .get_property(oid, &IdArgs { id: user_label });
+        assert_eq!(value, json!("Initial"));
+
+        let invalid = ModelBuilder::new().member(
+            MemberBuilder::worker("gain").property(NcElementId { level: 2, index: 1 }, json!(1)),
+        );
+        assert!(matches!(
+            build(&invalid),
+            Err(ModelError::InvalidPropertyValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,786 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::Path,
+    sync::Arc,
+};
+use tokio::sync::mpsc;
+
+use crate::{
+    config::DeviceConfig,
+    data_types::{
+        IdArgsValue, NcClassDescriptor, NcElementId, NcMethodStatus, NcPropertyConstraints,
+        NcTouchpoint, PropertyChangedEvent,
+    },
+    nc_block::NcBlock,
+    nc_bulk_properties_manager::NcBulkPropertiesManager,
+    nc_class_manager::NcClassManager,
+    nc_device_manager::{NcDeviceManager, RESETTABLE_DEVICE_MANAGER_CLASS_ID},
+    nc_ident_beacon::NcIdentBeacon,
+    nc_object::{NcMember, NcObject},
+    nc_worker::NcWorker,
+};
+
+/// Oid of the root block, member oids are assigned from the next one
+pub const ROOT_OID: u64 = 1;
+
+/// A device model description, the root block and everything nested under it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ModelDescription {
+    pub user_label: Option<String>,
+    pub touchpoints: Option<Vec<NcTouchpoint>>,
+    #[serde(default)]
+    pub members: Vec<MemberDescription>,
+}
+
+/// One object in the model; `members` is only allowed for blocks
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct MemberDescription {
+    /// Name of a class in the factory registry, e.g. `NcWorker`
+    pub class: String,
+    /// Class id of a derived class, must extend the registered class id
+    pub class_id: Option<Vec<u32>>,
+    pub role: String,
+    /// Assigned automatically when missing
+    pub oid: Option<u64>,
+    pub user_label: Option<String>,
+    pub touchpoints: Option<Vec<NcTouchpoint>>,
+    pub runtime_property_constraints: Option<Vec<NcPropertyConstraints>>,
+    /// Values applied with Set once the object is created
+    #[serde(default)]
+    pub properties: Vec<PropertyValue>,
+    pub members: Option<Vec<MemberDescription>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PropertyValue {
+    pub id: NcElementId,
+    pub value: Value,
+}
+
+/// Problems found while loading a model, role paths are dot separated (e.g. `root.my-block-01`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelError {
+    Read {
+        path: String,
+        message: String,
+    },
+    Parse {
+        path: String,
+        message: String,
+    },
+    UnknownClass {
+        role_path: String,
+        class: String,
+    },
+    InvalidClassId {
+        role_path: String,
+        class_id: Vec<u32>,
+        base_class_id: Vec<u32>,
+    },
+    EmptyRole {
+        role_path: String,
+    },
+    DuplicateRole {
+        role_path: String,
+    },
+    DuplicateOid {
+        oid: u64,
+        first: String,
+        second: String,
+    },
+    MembersNotAllowed {
+        role_path: String,
+        class: String,
+    },
+    InvalidPropertyValue {
+        role_path: String,
+        level: u32,
+        index: u32,
+        message: String,
+    },
+    MissingManager {
+        class: String,
+    },
+    ManagerNotInRoot {
+        role_path: String,
+    },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Read { path, message } => write!(f, "Failed to read {path}: {message}"),
+            ModelError::Parse { path, message } => write!(f, "Invalid model {path}: {message}"),
+            ModelError::UnknownClass { role_path, class } => {
+                write!(f, "{role_path}: unknown class {class}")
+            }
+            ModelError::InvalidClassId {
+                role_path,
+                class_id,
+                base_class_id,
+            } => write!(
+                f,
+                "{role_path}: class id {class_id:?} does not derive from {base_class_id:?}"
+            ),
+            ModelError::EmptyRole { role_path } => write!(f, "{role_path}: role must not be empty"),
+            ModelError::DuplicateRole { role_path } => {
+                write!(f, "{role_path}: role is already used in the same block")
+            }
+            ModelError::DuplicateOid { oid, first, second } => {
+                write!(f, "{second}: oid {oid} is already used by {first}")
+            }
+            ModelError::MembersNotAllowed { role_path, class } => {
+                write!(
+                    f,
+                    "{role_path}: {class} is not a block and cannot have members"
+                )
+            }
+            ModelError::InvalidPropertyValue {
+                role_path,
+                level,
+                index,
+                message,
+            } => write!(
+                f,
+                "{role_path}: cannot set property {level}p{index}: {message}"
+            ),
+            ModelError::MissingManager { class } => {
+                write!(f, "the root block must contain exactly one {class}")
+            }
+            ModelError::ManagerNotInRoot { role_path } => {
+                write!(f, "{role_path}: managers must be members of the root block")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+/// What a class factory gets to build an object
+pub struct FactoryContext<'a> {
+    pub description: &'a MemberDescription,
+    pub class_id: Vec<u32>,
+    pub oid: u64,
+    pub owner: u64,
+    pub device: &'a DeviceConfig,
+    pub notifier: mpsc::UnboundedSender<PropertyChangedEvent>,
+}
+
+impl FactoryContext<'_> {
+    /// The NcObject part of the object, for classes deriving from NcObject
+    pub fn object(&self) -> NcObject {
+        NcObject::new(
+            self.class_id.clone(),
+            self.oid,
+            true,
+            Some(self.owner),
+            &self.description.role,
+            self.description.user_label.as_deref(),
+            self.description.touchpoints.clone(),
+            self.description.runtime_property_constraints.clone(),
+            self.notifier.clone(),
+        )
+    }
+
+    /// The NcWorker part of the object, for classes deriving from NcWorker
+    pub fn worker(&self) -> NcWorker {
+        NcWorker::new(
+            self.class_id.clone(),
+            self.oid,
+            true,
+            Some(self.owner),
+            &self.description.role,
+            self.description.user_label.as_deref(),
+            self.description.touchpoints.clone(),
+            self.description.runtime_property_constraints.clone(),
+            self.notifier.clone(),
+        )
+    }
+}
+
+/// Constructor of the objects of a class, which may capture state such as shared handles
+pub type ClassFactory = Arc<dyn Fn(&FactoryContext) -> Box<dyn NcMember> + Send + Sync>;
+
+/// Maps class names used in model descriptions to class ids and constructors
+#[derive(Clone)]
+pub struct ClassFactoryRegistry {
+    classes: HashMap<String, (Vec<u32>, ClassFactory)>,
+    /// Descriptors of classes implemented outside this crate, served by the class manager
+    descriptors: Vec<NcClassDescriptor>,
+}
+
+impl Default for ClassFactoryRegistry {
+    fn default() -> Self {
+        let mut registry = ClassFactoryRegistry {
+            classes: HashMap::new(),
+            descriptors: Vec::new(),
+        };
+        registry.register("NcObject", vec![1], |ctx| Box::new(ctx.object()));
+        registry.register("NcBlock", vec![1, 1], |ctx| {
+            Box::new(NcBlock::new(
+                false,
+                ctx.class_id.clone(),
+                ctx.oid,
+                true,
+                Some(ctx.owner),
+                &ctx.description.role,
+                ctx.description.user_label.as_deref(),
+                true,
+                ctx.description.touchpoints.clone(),
+                ctx.description.runtime_property_constraints.clone(),
+                ctx.notifier.clone(),
+            ))
+        });
+        registry.register("NcWorker", vec![1, 2], |ctx| Box::new(ctx.worker()));
+        registry.register("NcIdentBeacon", vec![1, 2, 2], |ctx| {
+            Box::new(NcIdentBeacon::new(
+                ctx.oid,
+                true,
+                Some(ctx.owner),
+                &ctx.description.role,
+                ctx.description.user_label.as_deref(),
+                ctx.description.touchpoints.clone(),
+                ctx.description.runtime_property_constraints.clone(),
+                ctx.device.identification_timeout,
+                ctx.notifier.clone(),
+            ))
+        });
+        registry.register(
+            "NcDeviceManager",
+            RESETTABLE_DEVICE_MANAGER_CLASS_ID.to_vec(),
+            |ctx| {
+                // Points at the IS-04 device unless the description says otherwise
+                let touchpoints = ctx
+                    .description
+                    .touchpoints
+                    .clone()
+                    .or_else(|| Some(vec![NcTouchpoint::nmos("device", &ctx.device.id)]));
+                Box::new(NcDeviceManager::new(
+                    ctx.oid,
+                    true,
+                    Some(ctx.owner),
+                    &ctx.description.role,
+                    ctx.description.user_label.as_deref(),
+                    touchpoints,
+                    ctx.description.runtime_property_constraints.clone(),
+                    "v1.0.0".to_string(),
+                    ctx.device.manufacturer.clone(),
+                    ctx.device.product.clone(),
+                    ctx.device.serial_number.clone(),
+                    ctx.notifier.clone(),
+                ))
+            },
+        );
+        registry.register("NcClassManager", vec![1, 3, 2], |ctx| {
+            Box::new(NcClassManager::new(
+                ctx.oid,
+                true,
+                Some(ctx.owner),
+                &ctx.description.role,
+                ctx.description.user_label.as_deref(),
+                ctx.description.touchpoints.clone(),
+                ctx.description.runtime_property_constraints.clone(),
+                ctx.notifier.clone(),
+            ))
+        });
+        registry.register("NcBulkPropertiesManager", vec![1, 3, 3], |ctx| {
+            Box::new(NcBulkPropertiesManager::new(
+                ctx.oid,
+                true,
+                Some(ctx.owner),
+                &ctx.description.role,
+                ctx.description.user_label.as_deref(),
+                ctx.description.touchpoints.clone(),
+                ctx.description.runtime_property_constraints.clone(),
+                ctx.notifier.clone(),
+            ))
+        });
+        registry
+    }
+}
+
+impl ClassFactoryRegistry {
+    /// Registers a class, replacing any previous registration with the same name
+    pub fn register(
+        &mut self,
+        name: &str,
+        class_id: Vec<u32>,
+        factory: impl Fn(&FactoryContext) -> Box<dyn NcMember> + Send + Sync + 'static,
+    ) {
+        self.classes
+            .insert(name.to_string(), (class_id, Arc::new(factory)));
+    }
+
+    /// Registers a class implemented outside this crate under the name of its descriptor, the
+    /// descriptor holding only the elements the class adds to its parent
+    pub fn register_class(
+        &mut self,
+        descriptor: NcClassDescriptor,
+        factory: impl Fn(&FactoryContext) -> Box<dyn NcMember> + Send + Sync + 'static,
+    ) {
+        self.register(&descriptor.name, descriptor.class_id.clone(), factory);
+        self.descriptors.push(descriptor);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&(Vec<u32>, ClassFactory)> {
+        self.classes.get(name)
+    }
+}
+
+impl ModelDescription {
+    /// Reads a description from a .json, .yaml or .yml file
+    pub fn from_file(path: &Path) -> Result<Self, ModelError> {
+        let display = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|e| ModelError::Read {
+            path: display.clone(),
+            message: e.to_string(),
+        })?;
+        let parsed = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&text).map_err(|e| e.to_string()),
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&text).map_err(|e| e.to_string()),
+            _ => Err("expected a .json, .yaml or .yml file".to_string()),
+        };
+        parsed.map_err(|message| ModelError::Parse {
+            path: display,
+            message,
+        })
+    }
+}
+
+/// Builds the root block from a model description
+pub fn load_model(
+    description: &ModelDescription,
+    registry: &ClassFactoryRegistry,
+    device: &DeviceConfig,
+    notifier: mpsc::UnboundedSender<PropertyChangedEvent>,
+) -> Result<NcBlock, ModelError> {
+    let oids = assign_oids(description)?;
+
+    let mut root = NcBlock::new(
+        true,
+        vec![1, 1],
+        ROOT_OID,
+        true,
+        None,
+        "root",
+        description.user_label.as_deref(),
+        true,
+        description.touchpoints.clone(),
+        None,
+        notifier.clone(),
+    );
+
+    let mut loader = Loader {
+        registry,
+        device,
+        notifier,
+        oids,
+        next: 0,
+    };
+    loader.add_members(&mut root, &description.members, "root")?;
+
+    for (class, class_id) in [
+        ("NcDeviceManager", [1, 3, 1]),
+        ("NcClassManager", [1, 3, 2]),
+    ] {
+        let count = root
+            .members
+            .iter()
+            .filter(|m| m.get_class_id().starts_with(&class_id))
+            .count();
+        if count != 1 {
+            return Err(ModelError::MissingManager {
+                class: class.to_string(),
+            });
+        }
+    }
+
+    if let Some(class_manager) = root.class_manager_mut() {
+        for descriptor in &registry.descriptors {
+            class_manager.register_class(descriptor.clone());
+        }
+    }
+
+    Ok(root)
+}
+
+/// Checks explicit oids for duplicates and assigns the free ones in depth first order
+fn assign_oids(description: &ModelDescription) -> Result<Vec<u64>, ModelError> {
+    fn collect(members: &[MemberDescription], path: &str, out: &mut Vec<(String, Option<u64>)>) {
+        for member in members {
+            let role_path = format!("{path}.{}", member.role);
+            out.push((role_path.clone(), member.oid));
+            if let Some(children) = &member.members {
+                collect(children, &role_path, out);
+            }
+        }
+    }
+
+    let mut members = Vec::new();
+    collect(&description.members, "root", &mut members);
+
+    let mut used: HashMap<u64, String> = HashMap::from([(ROOT_OID, "root".to_string())]);
+    for (role_path, oid) in &members {
+        if let Some(oid) = oid
+            && let Some(first) = used.insert(*oid, role_path.clone())
+        {
+            return Err(ModelError::DuplicateOid {
+                oid: *oid,
+                first,
+                second: role_path.clone(),
+            });
+        }
+    }
+
+    let mut next = ROOT_OID + 1;
+    Ok(members
+        .iter()
+        .map(|(_, oid)| match oid {
+            Some(oid) => *oid,
+            None => {
+                while used.contains_key(&next) {
+                    next += 1;
+                }
+                used.insert(next, String::new());
+                next
+            }
+        })
+        .collect())
+}
+
+struct Loader<'a> {
+    registry: &'a ClassFactoryRegistry,
+    device: &'a DeviceConfig,
+    notifier: mpsc::UnboundedSender<PropertyChangedEvent>,
+    /// Oids in the same depth first order as the descriptions are visited
+    oids: Vec<u64>,
+    next: usize,
+}
+
+impl Loader<'_> {
+    fn add_members(
+        &mut self,
+        block: &mut NcBlock,
+        members: &[MemberDescription],
+        path: &str,
+    ) -> Result<(), ModelError> {
+        let mut roles = HashSet::new();
+        for description in members {
+            let role_path = format!("{path}.{}", description.role);
+            if description.role.is_empty() {
+                return Err(ModelError::EmptyRole { role_path });
+            }
+            if !roles.insert(description.role.as_str()) {
+                return Err(ModelError::DuplicateRole { role_path });
+            }
+
+            let oid = self.oids[self.next];
+            self.next += 1;
+
+            let member = self.build_member(description, oid, block.base.oid, &role_path)?;
+            block.add_member(member);
+        }
+        Ok(())
+    }
+
+    fn build_member(
+        &mut self,
+        description: &MemberDescription,
+        oid: u64,
+        owner: u64,
+        role_path: &str,
+    ) -> Result<Box<dyn NcMember>, ModelError> {
+        let (base_class_id, factory) =
+            self.registry
+                .get(&description.class)
+                .ok_or_else(|| ModelError::UnknownClass {
+                    role_path: role_path.to_string(),
+                    class: description.class.clone(),
+                })?;
+
+        let class_id = match &description.class_id {
+            Some(class_id) if !class_id.starts_with(base_class_id) => {
+                return Err(ModelError::InvalidClassId {
+                    role_path: role_path.to_string(),
+                    class_id: class_id.clone(),
+                    base_class_id: base_class_id.clone(),
+                });
+            }
+            Some(class_id) => class_id.clone(),
+            None => base_class_id.clone(),
+        };
+
+        if class_id.starts_with(&[1, 3]) && owner != ROOT_OID {
+            return Err(ModelError::ManagerNotInRoot {
+                role_path: role_path.to_string(),
+            });
+        }
+
+        let mut member = factory(&FactoryContext {
+            description,
+            class_id,
+            oid,
+            owner,
+            device: self.device,
+            notifier: self.notifier.clone(),
+        });
+
+        for property in &description.properties {
+            let (message, status) = member.set_property(
+                oid,
+                IdArgsValue {
+                    id: property.id.clone(),
+                    value: property.value.clone(),
+                },
+            );
+            if !matches!(status, NcMethodStatus::Ok) {
+                return Err(ModelError::InvalidPropertyValue {
+                    role_path: role_path.to_string(),
+                    level: property.id.level,
+                    index: property.id.index,
+                    message: message.unwrap_or_else(|| format!("{status:?}")),
+                });
+            }
+        }
+
+        if let Some(children) = &description.members {
+            match member.as_any_mut().downcast_mut::<NcBlock>() {
+                Some(block) => self.add_members(block, children, role_path)?,
+                None => {
+                    return Err(ModelError::MembersNotAllowed {
+                        role_path: role_path.to_string(),
+                        class: description.class.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(member)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Root members every model needs
+    const MANAGERS: &str = "
+        - { class: NcDeviceManager, role: DeviceManager }
+        - { class: NcClassManager, role: ClassManager }";
+
+    fn description(members: &str) -> ModelDescription {
+        serde_yaml::from_str(&format!("members:{members}")).unwrap()
+    }
+
+    fn load(members: &str) -> Result<NcBlock, ModelError> {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        load_model(
+            &description(members),
+            &ClassFactoryRegistry::default(),
+            &DeviceConfig::default(),
+            tx,
+        )
+    }
+
+    fn rejected(members: &str) -> ModelError {
+        match load(members) {
+            Ok(_) => panic!("model should be rejected"),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn unknown_classes_are_reported_with_their_role_path() {
+        let error = rejected(&format!(
+            "{MANAGERS}
+        - class: NcBlock
+          role: block
+          members:
+            - {{ class: NcGain, role: gain }}"
+        ));
+        assert_eq!(
+            error,
+            ModelError::UnknownClass {
+                role_path: "root.block.gain".to_string(),
+                class: "NcGain".to_string(),
+            }
+        );
+        assert_eq!(error.to_string(), "root.block.gain: unknown class NcGain");
+    }
+
+    #[test]
+    fn roles_must_be_unique_and_not_empty() {
+        let error = rejected(&format!(
+            "{MANAGERS}
+        - {{ class: NcWorker, role: worker }}
+        - {{ class: NcObject, role: worker }}"
+        ));
+        assert_eq!(
+            error,
+            ModelError::DuplicateRole {
+                role_path: "root.worker".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "root.worker: role is already used in the same block"
+        );
+
+        // The same role in different blocks is fine
+        assert!(
+            load(&format!(
+                "{MANAGERS}
+        - {{ class: NcWorker, role: worker }}
+        - class: NcBlock
+          role: block
+          members:
+            - {{ class: NcWorker, role: worker }}"
+            ))
+            .is_ok()
+        );
+
+        let error = rejected(&format!(
+            "{MANAGERS}
+        - class: NcBlock
+          role: block
+          members:
+            - {{ class: NcWorker, role: '' }}"
+        ));
+        assert_eq!(
+            error,
+            ModelError::EmptyRole {
+                role_path: "root.block.".to_string(),
+            }
+        );
+        assert_eq!(error.to_string(), "root.block.: role must not be empty");
+    }
+
+    #[test]
+    fn explicit_oids_must_be_unique() {
+        let error = rejected(&format!(
+            "{MANAGERS}
+        - {{ class: NcWorker, role: first, oid: 20 }}
+        - class: NcBlock
+          role: block
+          members:
+            - {{ class: NcWorker, role: second, oid: 20 }}"
+        ));
+        assert_eq!(
+            error,
+            ModelError::DuplicateOid {
+                oid: 20,
+                first: "root.first".to_string(),
+                second: "root.block.second".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "root.block.second: oid 20 is already used by root.first"
+        );
+
+        let error = rejected(&format!(
+            "{MANAGERS}
+        - {{ class: NcWorker, role: worker, oid: 1 }}"
+        ));
+        assert_eq!(
+            error.to_string(),
+            "root.worker: oid 1 is already used by root"
+        );
+    }
+
+    #[test]
+    fn assigned_oids_skip_the_explicit_ones() {
+        let oids = assign_oids(&description(
+            "
+        - { class: NcDeviceManager, role: DeviceManager }
+        - { class: NcClassManager, role: ClassManager, oid: 3 }
+        - class: NcBlock
+          role: block
+          oid: 2
+          members:
+            - { class: NcWorker, role: worker }
+            - { class: NcWorker, role: explicit, oid: 5 }
+        - { class: NcObject, role: object }",
+        ))
+        .unwrap();
+        assert_eq!(oids, vec![4, 3, 2, 6, 5, 7]);
+    }
+
+    #[test]
+    fn managers_are_required_once_in_the_root_block() {
+        let error = rejected("\n        - { class: NcClassManager, role: ClassManager }");
+        assert_eq!(
+            error,
+            ModelError::MissingManager {
+                class: "NcDeviceManager".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "the root block must contain exactly one NcDeviceManager"
+        );
+
+        let error = rejected(&format!(
+            "{MANAGERS}
+        - {{ class: NcClassManager, role: SecondClassManager }}"
+        ));
+        assert_eq!(
+            error,
+            ModelError::MissingManager {
+                class: "NcClassManager".to_string(),
+            }
+        );
+
+        let error = rejected(&format!(
+            "{MANAGERS}
+        - class: NcBlock
+          role: block
+          members:
+            - {{ class: NcBulkPropertiesManager, role: BulkPropertiesManager }}"
+        ));
+        assert_eq!(
+            error,
+            ModelError::ManagerNotInRoot {
+                role_path: "root.block.BulkPropertiesManager".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "root.block.BulkPropertiesManager: managers must be members of the root block"
+        );
+    }
+
+    #[test]
+    fn class_ids_must_derive_from_the_registered_class() {
+        let error = rejected(&format!(
+            "{MANAGERS}
+        - {{ class: NcWorker, role: worker, classId: [1, 3, 5] }}"
+        ));
+        assert_eq!(
+            error,
+            ModelError::InvalidClassId {
+                role_path: "root.worker".to_string(),
+                class_id: vec![1, 3, 5],
+                base_class_id: vec![1, 2],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "root.worker: class id [1, 3, 5] does not derive from [1, 2]"
+        );
+
+        let root = load(&format!(
+            "{MANAGERS}
+        - {{ class: NcWorker, role: worker, classId: [1, 2, 0, 1] }}"
+        ))
+        .unwrap();
+        let worker = root.find_member(4).unwrap();
+        assert_eq!(worker.get_class_id(), &[1, 2, 0, 1]);
+    }
+}
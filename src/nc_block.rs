@@ -1,9 +1,13 @@
 use crate::data_types::{
     IdArgs, IdArgsValue, NcBlockMemberDescriptor, NcClassDescriptor, NcElementId,
     NcMethodDescriptor, NcMethodStatus, NcParameterDescriptor, NcPropertyChangeType,
-    NcPropertyDescriptor, PropertyChangedEvent, PropertyChangedEventData,
+    NcPropertyDescriptor, NcTouchpoint, PropertyChangedEvent, PropertyChangedEventData,
 };
+use crate::nc_bulk_properties_manager::NcBulkPropertiesManager;
+use crate::nc_class_manager::NcClassManager;
+use crate::nc_device_manager::NcDeviceManager;
 use crate::nc_object::{NcMember, NcObject};
+use crate::nc_role_path::NcRolePath;
 use itertools::Itertools;
 use serde_json::{Value, json};
 use std::any::Any;
@@ -77,8 +81,12 @@ impl NcMember for NcBlock {
         id_args_value: IdArgsValue,
     ) -> (Option<String>, NcMethodStatus) {
         if oid == self.base.oid {
-            match id_args_value.id.level {
-                2 => (
+            match (id_args_value.id.level, id_args_value.id.index) {
+                (2, 1) | (2, 2) => (
+                    Some("Property is readonly".to_string()),
+                    NcMethodStatus::Readonly,
+                ),
+                (2, _) => (
                     Some("Could not find the property".to_string()),
                     NcMethodStatus::PropertyNotImplemented,
                 ),
@@ -484,6 +492,193 @@ impl NcBlock {
         None
     }
 
+    /// Removes the member with the given oid from whichever nested block holds it and notifies the
+    /// new members of that block. Served models go through `AppState::remove_member`, which also
+    /// drops the subscriptions to the removed objects.
+    pub(crate) fn remove_member(&mut self, oid: u64) -> Option<Box<dyn NcMember>> {
+        if let Some(position) = self.members.iter().position(|m| m.get_oid() == oid) {
+            let removed = self.members.remove(position);
+
+            let members_descriptors = self.generate_members_descriptors();
+
+            let _ = self.base.notifier.send(PropertyChangedEvent::new(
+                self.base.oid,
+                PropertyChangedEventData {
+                    property_id: NcElementId { level: 2, index: 2 },
+                    change_type: NcPropertyChangeType::ValueChanged,
+                    value: json!(members_descriptors),
+                    sequence_item_index: None,
+                },
+            ));
+
+            return Some(removed);
+        }
+
+        for member in &mut self.members {
+            if let Some(block) = member.as_any_mut().downcast_mut::<NcBlock>()
+                && let Some(removed) = block.remove_member(oid)
+            {
+                return Some(removed);
+            }
+        }
+        None
+    }
+
+    /// Invokes a method which may need to read or change the whole tree (the bulk properties
+    /// manager methods), every other method goes through `invoke_method`
+    pub fn invoke_method_mut(
+        &mut self,
+        oid: u64,
+        method_id: NcElementId,
+        args: Value,
+    ) -> (Option<String>, Option<Value>, NcMethodStatus) {
+        let is_bulk_manager = self
+            .find_member(oid)
+            .is_some_and(|m| m.as_any().is::<NcBulkPropertiesManager>());
+        if is_bulk_manager && method_id.level == 3 {
+            return NcBulkPropertiesManager::invoke_on_tree(self, method_id, args);
+        }
+        self.invoke_method(oid, method_id, args)
+    }
+
+    /// Returns true if the oid is this block or any object nested under it
+    pub fn contains_oid(&self, oid: u64) -> bool {
+        self.base.oid == oid || self.find_member(oid).is_some()
+    }
+
+    /// Collects the oids of this block and of every object nested under it
+    pub fn collect_oids(&self) -> Vec<u64> {
+        let mut oids = vec![self.base.oid];
+        for member in &self.members {
+            match member.as_any().downcast_ref::<NcBlock>() {
+                Some(block) => oids.extend(block.collect_oids()),
+                None => oids.push(member.get_oid()),
+            }
+        }
+        oids
+    }
+
+    /// Returns the oid plus, when it refers to a block, every oid nested under it
+    pub fn subtree_oids(&self, oid: u64) -> Option<Vec<u64>> {
+        if oid == self.base.oid {
+            return Some(self.collect_oids());
+        }
+
+        let member = self.find_member(oid)?;
+        match member.as_any().downcast_ref::<NcBlock>() {
+            Some(block) => Some(block.collect_oids()),
+            None => Some(vec![oid]),
+        }
+    }
+
+    /// The class manager among this block's members (only present in the root block)
+    pub fn class_manager(&self) -> Option<&NcClassManager> {
+        self.members
+            .iter()
+            .find_map(|m| m.as_any().downcast_ref::<NcClassManager>())
+    }
+
+    pub fn class_manager_mut(&mut self) -> Option<&mut NcClassManager> {
+        self.members
+            .iter_mut()
+            .find_map(|m| m.as_any_mut().downcast_mut::<NcClassManager>())
+    }
+
+    /// The device manager among this block's members (only present in the root block)
+    pub fn device_manager(&self) -> Option<&NcDeviceManager> {
+        self.members
+            .iter()
+            .find_map(|m| m.as_any().downcast_ref::<NcDeviceManager>())
+    }
+
+    pub fn device_manager_mut(&mut self) -> Option<&mut NcDeviceManager> {
+        self.members
+            .iter_mut()
+            .find_map(|m| m.as_any_mut().downcast_mut::<NcDeviceManager>())
+    }
+
+    /// This block or any object nested under it
+    pub fn find_object(&self, oid: u64) -> Option<&dyn NcMember> {
+        match oid == self.base.oid {
+            true => Some(self),
+            false => self.find_member(oid),
+        }
+    }
+
+    /// Role path from this block down to the object with the given oid,
+    /// e.g. `root.my-block-01.my-worker-02`
+    pub fn role_path_of(&self, oid: u64) -> Option<NcRolePath> {
+        self.roles_to(oid).map(NcRolePath::from)
+    }
+
+    fn roles_to(&self, oid: u64) -> Option<Vec<String>> {
+        if oid == self.base.oid {
+            return Some(vec![self.base.role.clone()]);
+        }
+        for member in &self.members {
+            let nested = match member.as_any().downcast_ref::<NcBlock>() {
+                Some(block) => block.roles_to(oid),
+                None if member.get_oid() == oid => Some(vec![member.get_role().to_string()]),
+                None => None,
+            };
+            if let Some(mut roles) = nested {
+                roles.insert(0, self.base.role.clone());
+                return Some(roles);
+            }
+        }
+        None
+    }
+
+    /// Resolves a role path starting with this block's role to the object it names
+    pub fn object_at_role_path(&self, path: &NcRolePath) -> Option<&dyn NcMember> {
+        self.object_at_roles(path.roles())
+    }
+
+    /// Resolves a role path starting with this block's role to an oid
+    pub fn resolve_role_path(&self, path: &NcRolePath) -> Option<u64> {
+        self.object_at_role_path(path)
+            .map(|object| object.get_oid())
+    }
+
+    fn object_at_roles(&self, roles: &[String]) -> Option<&dyn NcMember> {
+        let (first, rest) = roles.split_first()?;
+        if *first != self.base.role {
+            return None;
+        }
+        let Some(next) = rest.first() else {
+            return Some(self);
+        };
+        let member = self.members.iter().find(|m| m.get_role() == next)?;
+        match member.as_any().downcast_ref::<NcBlock>() {
+            Some(block) => block.object_at_roles(rest),
+            None if rest.len() == 1 => Some(member.as_ref()),
+            None => None,
+        }
+    }
+
+    /// Touchpoints (1p7) of this block and of every object nested under it with the role path
+    /// of the object declaring them
+    pub fn touchpoints_by_role_path(&self) -> Vec<(NcRolePath, NcTouchpoint)> {
+        self.collect_oids()
+            .into_iter()
+            .filter_map(|oid| {
+                let (_, value, _) = self.get_property(
+                    oid,
+                    &IdArgs {
+                        id: NcElementId { level: 1, index: 7 },
+                    },
+                );
+                let touchpoints: Vec<NcTouchpoint> = serde_json::from_value(value).ok()?;
+                Some((self.role_path_of(oid)?, touchpoints))
+            })
+            .flat_map(|(path, touchpoints)| {
+                touchpoints
+                    .into_iter()
+                    .map(move |touchpoint| (path.clone(), touchpoint))
+            })
+            .collect()
+    }
+
     pub fn generate_members_descriptors(&self) -> Vec<NcBlockMemberDescriptor> {
         self.members
             .iter()
@@ -698,3 +893,53 @@ impl NcBlock {
         results
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(block: &mut NcBlock, level: u32, index: u32, value: Value) -> NcMethodStatus {
+        let (_, status) = block.set_property(
+            1,
+            IdArgsValue {
+                id: NcElementId { level, index },
+                value,
+            },
+        );
+        status
+    }
+
+    #[test]
+    fn block_properties_are_read_only() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut block = NcBlock::new(
+            true,
+            vec![1, 1],
+            1,
+            true,
+            None,
+            "root",
+            None,
+            true,
+            None,
+            None,
+            tx,
+        );
+        assert!(matches!(
+            set(&mut block, 2, 1, json!(false)),
+            NcMethodStatus::Readonly
+        ));
+        assert!(matches!(
+            set(&mut block, 2, 2, json!([])),
+            NcMethodStatus::Readonly
+        ));
+        assert!(matches!(
+            set(&mut block, 2, 3, json!(null)),
+            NcMethodStatus::PropertyNotImplemented
+        ));
+        assert!(matches!(
+            set(&mut block, 1, 6, json!("Root")),
+            NcMethodStatus::Ok
+        ));
+    }
+}
@@ -0,0 +1,620 @@
+use crate::data_types::{
+    IdArgs, IdArgsValue, NcBulkValuesHolder, NcClassDescriptor, NcElementId, NcMethodDescriptor,
+    NcMethodStatus, NcObjectPropertiesHolder, NcObjectPropertiesSetValidation,
+    NcParameterDescriptor, NcPropertyConstraints, NcPropertyDescriptor, NcPropertyHolder,
+    NcPropertyRestoreNotice, NcPropertyRestoreNoticeType, NcRestoreMode, NcRestoreValidationStatus,
+    NcTouchpoint, PropertyChangedEvent,
+};
+use crate::nc_block::NcBlock;
+use crate::nc_manager::NcManager;
+use crate::nc_object::NcMember;
+use crate::nc_role_path::NcRolePath;
+use serde_json::{Value, json};
+use std::any::Any;
+use tokio::sync::mpsc;
+
+pub struct NcBulkPropertiesManager {
+    pub base: NcManager,
+}
+
+impl NcMember for NcBulkPropertiesManager {
+    fn member_type(&self) -> &'static str {
+        "NcBulkPropertiesManager"
+    }
+    fn get_role(&self) -> &str {
+        self.base.get_role()
+    }
+    fn get_oid(&self) -> u64 {
+        self.base.get_oid()
+    }
+    fn get_constant_oid(&self) -> bool {
+        self.base.get_constant_oid()
+    }
+    fn get_class_id(&self) -> &[u32] {
+        self.base.get_class_id()
+    }
+    fn get_user_label(&self) -> Option<&str> {
+        self.base.get_user_label()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn get_property(&self, oid: u64, id_args: &IdArgs) -> (Option<String>, Value, NcMethodStatus) {
+        self.base.get_property(oid, id_args)
+    }
+
+    fn set_property(
+        &mut self,
+        oid: u64,
+        id_args_value: IdArgsValue,
+    ) -> (Option<String>, NcMethodStatus) {
+        self.base.set_property(oid, id_args_value)
+    }
+
+    // The bulk methods need the whole tree, the root block dispatches them (see NcBlock::invoke_method_mut)
+    fn invoke_method(
+        &self,
+        oid: u64,
+        method_id: NcElementId,
+        args: Value,
+    ) -> (Option<String>, Option<Value>, NcMethodStatus) {
+        self.base.invoke_method(oid, method_id, args)
+    }
+
+    fn is_mutating_method(&self, method_id: &NcElementId) -> bool {
+        matches!(
+            (method_id.level, method_id.index),
+            (1, 2) | (1, 4) | (1, 5) | (1, 6) | (3, 3)
+        )
+    }
+}
+
+impl NcBulkPropertiesManager {
+    pub fn get_class_descriptor(include_inherited: bool) -> NcClassDescriptor {
+        let path_parameter = NcParameterDescriptor {
+            base: crate::data_types::NcDescriptor {
+                description: Some("The target role path".to_string()),
+            },
+            name: "path".to_string(),
+            type_name: Some("NcRolePath".to_string()),
+            is_nullable: false,
+            is_sequence: false,
+            constraints: None,
+        };
+        let recurse_parameter = NcParameterDescriptor {
+            base: crate::data_types::NcDescriptor {
+                description: Some("If true will include the nested objects".to_string()),
+            },
+            name: "recurse".to_string(),
+            type_name: Some("NcBoolean".to_string()),
+            is_nullable: false,
+            is_sequence: false,
+            constraints: None,
+        };
+        let set_parameters = vec![
+            NcParameterDescriptor {
+                base: crate::data_types::NcDescriptor { description: Some("The values offered (this may include read-only values and also paths which are not the target role path)".to_string()) },
+                name: "dataSet".to_string(),
+                type_name: Some("NcBulkValuesHolder".to_string()),
+                is_nullable: false,
+                is_sequence: false,
+                constraints: None,
+            },
+            path_parameter.clone(),
+            recurse_parameter.clone(),
+            NcParameterDescriptor {
+                base: crate::data_types::NcDescriptor { description: Some("Defines the restore mode to be applied".to_string()) },
+                name: "restoreMode".to_string(),
+                type_name: Some("NcRestoreMode".to_string()),
+                is_nullable: false,
+                is_sequence: false,
+                constraints: None,
+            },
+        ];
+
+        let mut desc = NcClassDescriptor {
+            base: crate::data_types::NcDescriptor {
+                description: Some("NcBulkPropertiesManager class descriptor".to_string()),
+            },
+            class_id: vec![1, 3, 3],
+            name: "NcBulkPropertiesManager".to_string(),
+            fixed_role: Some("BulkPropertiesManager".to_string()),
+            properties: vec![],
+            methods: vec![
+                // 3m1 GetPropertiesByPath
+                NcMethodDescriptor {
+                    base: crate::data_types::NcDescriptor {
+                        description: Some("Get bulk object properties by given path".to_string()),
+                    },
+                    id: NcElementId { level: 3, index: 1 },
+                    name: "GetPropertiesByPath".to_string(),
+                    result_datatype: "NcMethodResultBulkValuesHolder".to_string(),
+                    parameters: vec![path_parameter, recurse_parameter],
+                    is_deprecated: false,
+                },
+                // 3m2 ValidateSetPropertiesByPath
+                NcMethodDescriptor {
+                    base: crate::data_types::NcDescriptor {
+                        description: Some(
+                            "Validate bulk properties for setting by given paths".to_string(),
+                        ),
+                    },
+                    id: NcElementId { level: 3, index: 2 },
+                    name: "ValidateSetPropertiesByPath".to_string(),
+                    result_datatype: "NcMethodResultObjectPropertiesSetValidation".to_string(),
+                    parameters: set_parameters.clone(),
+                    is_deprecated: false,
+                },
+                // 3m3 SetPropertiesByPath
+                NcMethodDescriptor {
+                    base: crate::data_types::NcDescriptor {
+                        description: Some("Set bulk properties by given paths".to_string()),
+                    },
+                    id: NcElementId { level: 3, index: 3 },
+                    name: "SetPropertiesByPath".to_string(),
+                    result_datatype: "NcMethodResultObjectPropertiesSetValidation".to_string(),
+                    parameters: set_parameters,
+                    is_deprecated: false,
+                },
+            ],
+            events: vec![],
+        };
+
+        if include_inherited {
+            let base_desc = crate::nc_manager::NcManager::get_class_descriptor(true);
+            desc.properties.extend(base_desc.properties);
+            desc.methods.extend(base_desc.methods);
+            desc.events.extend(base_desc.events);
+        }
+
+        desc
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+impl NcBulkPropertiesManager {
+    pub fn new(
+        oid: u64,
+        constant_oid: bool,
+        owner: Option<u64>,
+        role: &str,
+        user_label: Option<&str>,
+        touchpoints: Option<Vec<NcTouchpoint>>,
+        runtime_property_constraints: Option<Vec<NcPropertyConstraints>>,
+        notifier: mpsc::UnboundedSender<PropertyChangedEvent>,
+    ) -> Self {
+        NcBulkPropertiesManager {
+            base: NcManager::new(
+                vec![1, 3, 3], // Class ID for NcBulkPropertiesManager
+                oid,
+                constant_oid,
+                owner,
+                role,
+                user_label,
+                touchpoints,
+                runtime_property_constraints,
+                notifier,
+            ),
+        }
+    }
+}
+
+/// Arguments shared by ValidateSetPropertiesByPath and SetPropertiesByPath
+struct SetArgs {
+    data_set: NcBulkValuesHolder,
+    path: NcRolePath,
+    recurse: bool,
+    restore_mode: NcRestoreMode,
+}
+
+impl NcBulkPropertiesManager {
+    /// Runs the bulk methods against the tree under `root`
+    pub fn invoke_on_tree(
+        root: &mut NcBlock,
+        method_id: NcElementId,
+        args: Value,
+    ) -> (Option<String>, Option<Value>, NcMethodStatus) {
+        match (method_id.level, method_id.index) {
+            (3, 1) => {
+                let Some(path) = parse_role_path(&args) else {
+                    return invalid_path();
+                };
+                let recurse = args
+                    .get("recurse")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                match Self::get_properties_by_path(root, &path, recurse) {
+                    Some(holder) => (None, Some(json!(holder)), NcMethodStatus::Ok),
+                    None => path_not_found(),
+                }
+            }
+            (3, 2) | (3, 3) => {
+                let set_args = match parse_set_args(args) {
+                    Ok(set_args) => set_args,
+                    Err(message) => return (Some(message), None, NcMethodStatus::ParameterError),
+                };
+                if root.resolve_role_path(&set_args.path).is_none() {
+                    return path_not_found();
+                }
+                let apply = method_id.index == 3;
+                let validations = Self::set_properties_by_path(root, &set_args, apply);
+                (None, Some(json!(validations)), NcMethodStatus::Ok)
+            }
+            _ => (
+                Some("Method not implemented".to_string()),
+                None,
+                NcMethodStatus::MethodNotImplemented,
+            ),
+        }
+    }
+
+    /// Snapshot of every property of the object at `path` (and of its nested objects when recursing)
+    pub fn get_properties_by_path(
+        root: &NcBlock,
+        path: &NcRolePath,
+        recurse: bool,
+    ) -> Option<NcBulkValuesHolder> {
+        let oid = root.resolve_role_path(path)?;
+        let oids = if recurse {
+            root.subtree_oids(oid)?
+        } else {
+            vec![oid]
+        };
+
+        let values = oids
+            .into_iter()
+            .filter_map(|oid| {
+                let descriptor = class_descriptor_of(root, oid)?;
+                let values = descriptor
+                    .properties
+                    .into_iter()
+                    .map(|property| {
+                        let (_, value, _) = root.get_property(
+                            oid,
+                            &IdArgs {
+                                id: property.id.clone(),
+                            },
+                        );
+                        NcPropertyHolder {
+                            id: property.id.clone(),
+                            descriptor: property,
+                            value,
+                        }
+                    })
+                    .collect();
+                Some(NcObjectPropertiesHolder {
+                    path: root.role_path_of(oid)?,
+                    dependency_paths: Vec::new(),
+                    allowed_members_classes: Vec::new(),
+                    values,
+                    is_rebuildable: false,
+                })
+            })
+            .collect();
+
+        Some(NcBulkValuesHolder {
+            validation_fingerprint: None,
+            values,
+        })
+    }
+
+    /// Validates the data set entries under the target path and, when `apply` is set, writes the
+    /// values of the objects which passed validation. No object in this device is rebuildable so
+    /// Rebuild restores values like Modify, which the status message of each object reports.
+    fn set_properties_by_path(
+        root: &mut NcBlock,
+        args: &SetArgs,
+        apply: bool,
+    ) -> Vec<NcObjectPropertiesSetValidation> {
+        let in_scope = |path: &NcRolePath| {
+            if args.recurse {
+                path.starts_with(&args.path)
+            } else {
+                *path == args.path
+            }
+        };
+
+        let mut validations = Vec::new();
+        for holder in args
+            .data_set
+            .values
+            .iter()
+            .filter(|holder| in_scope(&holder.path))
+        {
+            let mut validation = NcObjectPropertiesSetValidation {
+                path: holder.path.clone(),
+                status: NcRestoreValidationStatus::Ok,
+                notices: Vec::new(),
+                status_message: None,
+            };
+
+            let Some(oid) = root.resolve_role_path(&holder.path) else {
+                validation.status = NcRestoreValidationStatus::NotFound;
+                validation.status_message = Some("Object not found".to_string());
+                validations.push(validation);
+                continue;
+            };
+            let Some(descriptor) = class_descriptor_of(root, oid) else {
+                validation.status = NcRestoreValidationStatus::DeviceError;
+                validation.status_message = Some("Object class is not known".to_string());
+                validations.push(validation);
+                continue;
+            };
+
+            let mut changes = Vec::new();
+            for property in &holder.values {
+                let Some(target) = descriptor
+                    .properties
+                    .iter()
+                    .find(|p| p.id.level == property.id.level && p.id.index == property.id.index)
+                else {
+                    validation.notices.push(notice(
+                        property,
+                        NcPropertyRestoreNoticeType::Warning,
+                        "Property not found on the object, it was ignored",
+                    ));
+                    continue;
+                };
+                // Read-only values are part of every backup and are not restored
+                if target.is_read_only {
+                    continue;
+                }
+                if let Err(message) = check_value(target, &property.value) {
+                    validation.notices.push(notice(
+                        property,
+                        NcPropertyRestoreNoticeType::Error,
+                        &message,
+                    ));
+                    continue;
+                }
+                let (_, current, _) = root.get_property(
+                    oid,
+                    &IdArgs {
+                        id: property.id.clone(),
+                    },
+                );
+                if current != property.value {
+                    changes.push(property);
+                }
+            }
+
+            let failed = validation
+                .notices
+                .iter()
+                .any(|n| n.notice_type == NcPropertyRestoreNoticeType::Error);
+            if failed {
+                validation.status = NcRestoreValidationStatus::Failed;
+                validation.status_message = Some("Some properties failed validation".to_string());
+            }
+            if args.restore_mode == NcRestoreMode::Rebuild {
+                let fallback = "Object is not rebuildable, values are restored as in Modify";
+                validation.status_message = Some(match validation.status_message.take() {
+                    Some(message) => format!("{message}. {fallback}"),
+                    None => fallback.to_string(),
+                });
+            }
+            if !failed && apply {
+                for property in changes {
+                    let (error, status) = root.set_property(
+                        oid,
+                        IdArgsValue {
+                            id: property.id.clone(),
+                            value: property.value.clone(),
+                        },
+                    );
+                    if !matches!(status, NcMethodStatus::Ok) {
+                        validation.status = NcRestoreValidationStatus::DeviceError;
+                        validation.notices.push(notice(
+                            property,
+                            NcPropertyRestoreNoticeType::Error,
+                            &error.unwrap_or_else(|| "Failed to set the value".to_string()),
+                        ));
+                    }
+                }
+            }
+
+            validations.push(validation);
+        }
+
+        validations
+    }
+}
+
+fn parse_role_path(args: &Value) -> Option<NcRolePath> {
+    let roles: Vec<String> = args
+        .get("path")?
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str().map(str::to_string))
+        .collect::<Option<_>>()?;
+    (!roles.is_empty()).then(|| NcRolePath::from(roles))
+}
+
+fn parse_set_args(args: Value) -> Result<SetArgs, String> {
+    let path = parse_role_path(&args).ok_or("Invalid path argument")?;
+    let recurse = args
+        .get("recurse")
+        .and_then(|v| v.as_bool())
+        .ok_or("Invalid recurse argument")?;
+    let restore_mode = args
+        .get("restoreMode")
+        .and_then(|v| v.as_u64())
+        .and_then(|v| NcRestoreMode::try_from(v as u32).ok())
+        .ok_or("Invalid restoreMode argument")?;
+    let data_set = args
+        .get("dataSet")
+        .cloned()
+        .ok_or("Missing dataSet argument".to_string())
+        .and_then(|v| {
+            serde_json::from_value(v).map_err(|e| format!("Invalid dataSet argument: {e}"))
+        })?;
+    Ok(SetArgs {
+        data_set,
+        path,
+        recurse,
+        restore_mode,
+    })
+}
+
+fn invalid_path() -> (Option<String>, Option<Value>, NcMethodStatus) {
+    (
+        Some("Invalid path argument".to_string()),
+        None,
+        NcMethodStatus::ParameterError,
+    )
+}
+
+fn path_not_found() -> (Option<String>, Option<Value>, NcMethodStatus) {
+    (
+        Some("Role path not found".to_string()),
+        None,
+        NcMethodStatus::ParameterError,
+    )
+}
+
+fn notice(
+    property: &NcPropertyHolder,
+    notice_type: NcPropertyRestoreNoticeType,
+    message: &str,
+) -> NcPropertyRestoreNotice {
+    NcPropertyRestoreNotice {
+        id: property.id.clone(),
+        name: property.descriptor.name.clone(),
+        notice_type,
+        notice_message: message.to_string(),
+    }
+}
+
+/// Class descriptor (including inherited elements) of the object with the given oid
+fn class_descriptor_of(root: &NcBlock, oid: u64) -> Option<NcClassDescriptor> {
+    let class_id = if oid == root.get_oid() {
+        root.get_class_id()
+    } else {
+        root.find_member(oid)?.get_class_id()
+    };
+    root.class_manager()?.find_class_descriptor(class_id)
+}
+
+/// Checks a value against the type, nullability and sequence flags of a property descriptor
+fn check_value(descriptor: &NcPropertyDescriptor, value: &Value) -> Result<(), String> {
+    if value.is_null() {
+        return if descriptor.is_nullable {
+            Ok(())
+        } else {
+            Err("Property is not nullable".to_string())
+        };
+    }
+    let type_name = descriptor.type_name.as_deref().unwrap_or_default();
+    if descriptor.is_sequence {
+        let items = value
+            .as_array()
+            .ok_or("Property value must be a sequence")?;
+        return match items.iter().all(|item| value_matches_type(type_name, item)) {
+            true => Ok(()),
+            false => Err(format!("Sequence items must be of type {type_name}")),
+        };
+    }
+    match value_matches_type(type_name, value) {
+        true => Ok(()),
+        false => Err(format!("Property value must be of type {type_name}")),
+    }
+}
+
+fn value_matches_type(type_name: &str, value: &Value) -> bool {
+    match type_name {
+        "NcBoolean" => value.is_boolean(),
+        "NcString" | "NcName" | "NcRole" | "NcRegex" | "NcUri" | "NcUuid" | "NcVersionCode" => {
+            value.is_string()
+        }
+        "NcInt16" | "NcInt32" | "NcInt64" | "NcOrganizationId" | "NcTimeInterval" => value.is_i64(),
+        "NcUint16" | "NcUint32" | "NcUint64" | "NcId" | "NcOid" => value.is_u64(),
+        "NcFloat32" | "NcFloat64" => value.is_number(),
+        "NcRolePath" | "NcClassId" => value.is_array(),
+        // Any other named type is a struct, enums are numbers; an empty type name allows any value
+        "" => true,
+        _ => value.is_object() || value.is_number(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_types::NcDescriptor;
+
+    fn descriptor(type_name: &str, is_nullable: bool, is_sequence: bool) -> NcPropertyDescriptor {
+        NcPropertyDescriptor {
+            base: NcDescriptor { description: None },
+            id: NcElementId { level: 1, index: 1 },
+            name: "property".to_string(),
+            type_name: Some(type_name.to_string()),
+            is_read_only: false,
+            is_nullable,
+            is_sequence,
+            is_deprecated: false,
+            constraints: None,
+        }
+    }
+
+    #[test]
+    fn values_must_match_the_property_type() {
+        let cases = [
+            ("NcBoolean", json!(true), true),
+            ("NcBoolean", json!(1), false),
+            ("NcString", json!("label"), true),
+            ("NcString", json!(1), false),
+            ("NcInt32", json!(-1), true),
+            ("NcInt32", json!(1.5), false),
+            ("NcUint16", json!(1), true),
+            ("NcUint16", json!(-1), false),
+            ("NcFloat64", json!(1.5), true),
+            ("NcFloat64", json!("1.5"), false),
+            ("NcRolePath", json!(["root"]), true),
+            ("NcRolePath", json!("root"), false),
+            ("NcDeviceGenericState", json!(1), true),
+            (
+                "NcTouchpoint",
+                json!({ "contextNamespace": "x-nmos" }),
+                true,
+            ),
+            ("NcTouchpoint", json!("x-nmos"), false),
+            ("", json!("anything"), true),
+        ];
+        for (type_name, value, valid) in cases {
+            assert_eq!(
+                check_value(&descriptor(type_name, false, false), &value).is_ok(),
+                valid,
+                "{type_name} {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn null_is_only_accepted_by_nullable_properties() {
+        assert!(check_value(&descriptor("NcString", true, false), &Value::Null).is_ok());
+        assert_eq!(
+            check_value(&descriptor("NcString", false, false), &Value::Null),
+            Err("Property is not nullable".to_string())
+        );
+    }
+
+    #[test]
+    fn sequences_are_checked_item_by_item() {
+        let sequence = descriptor("NcUint32", false, true);
+        assert!(check_value(&sequence, &json!([])).is_ok());
+        assert!(check_value(&sequence, &json!([1, 2])).is_ok());
+        assert_eq!(
+            check_value(&sequence, &json!(1)),
+            Err("Property value must be a sequence".to_string())
+        );
+        assert_eq!(
+            check_value(&sequence, &json!([1, "2"])),
+            Err("Sequence items must be of type NcUint32".to_string())
+        );
+    }
+}
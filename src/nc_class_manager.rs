@@ -322,9 +322,12 @@ impl NcClassManager {
             crate::nc_object::NcObject::get_class_descriptor(false),
             crate::nc_block::NcBlock::get_class_descriptor(false),
             crate::nc_worker::NcWorker::get_class_descriptor(false),
+            crate::nc_ident_beacon::NcIdentBeacon::get_class_descriptor(false),
             crate::nc_manager::NcManager::get_class_descriptor(false),
             crate::nc_device_manager::NcDeviceManager::get_class_descriptor(false),
+            crate::nc_device_manager::NcDeviceManager::get_resettable_class_descriptor(false),
             crate::nc_class_manager::NcClassManager::get_class_descriptor(false),
+            crate::nc_bulk_properties_manager::NcBulkPropertiesManager::get_class_descriptor(false),
         ];
 
         for desc in classes {
@@ -459,6 +462,29 @@ impl NcClassManager {
             "NcMethodResultLength" => {
                 Some(crate::data_types::NcMethodResultLength::get_type_descriptor(true))
             }
+            "NcPropertyHolder" => Some(crate::data_types::NcPropertyHolder::get_type_descriptor(
+                true,
+            )),
+            "NcObjectPropertiesHolder" => {
+                Some(crate::data_types::NcObjectPropertiesHolder::get_type_descriptor(true))
+            }
+            "NcBulkValuesHolder" => Some(
+                crate::data_types::NcBulkValuesHolder::get_type_descriptor(true),
+            ),
+            "NcPropertyRestoreNotice" => {
+                Some(crate::data_types::NcPropertyRestoreNotice::get_type_descriptor(true))
+            }
+            "NcObjectPropertiesSetValidation" => {
+                Some(crate::data_types::NcObjectPropertiesSetValidation::get_type_descriptor(true))
+            }
+            "NcMethodResultBulkValuesHolder" => {
+                Some(crate::data_types::NcMethodResultBulkValuesHolder::get_type_descriptor(true))
+            }
+            "NcMethodResultObjectPropertiesSetValidation" => Some(
+                crate::data_types::NcMethodResultObjectPropertiesSetValidation::get_type_descriptor(
+                    true,
+                ),
+            ),
 
             // Types registered directly or non-structs: fall back to registry
             _ => None,
@@ -571,6 +597,21 @@ impl NcClassManager {
             false,
         ));
         add_struct(crate::data_types::NcMethodResultLength::get_type_descriptor(false));
+        add_struct(crate::data_types::NcPropertyHolder::get_type_descriptor(
+            false,
+        ));
+        add_struct(crate::data_types::NcObjectPropertiesHolder::get_type_descriptor(false));
+        add_struct(crate::data_types::NcBulkValuesHolder::get_type_descriptor(
+            false,
+        ));
+        add_struct(crate::data_types::NcPropertyRestoreNotice::get_type_descriptor(false));
+        add_struct(crate::data_types::NcObjectPropertiesSetValidation::get_type_descriptor(false));
+        add_struct(crate::data_types::NcMethodResultBulkValuesHolder::get_type_descriptor(false));
+        add_struct(
+            crate::data_types::NcMethodResultObjectPropertiesSetValidation::get_type_descriptor(
+                false,
+            ),
+        );
 
         let mut add_enum = |name: &str, items: Vec<(&str, u16, &str)>, description: &str| {
             let enum_desc = crate::data_types::NcDatatypeDescriptorEnum {
@@ -658,6 +699,43 @@ impl NcClassManager {
             "Method status enumeration",
         );
 
+        add_enum(
+            "NcRestoreMode",
+            vec![
+                ("Modify", 1, "Restore mode is Modify"),
+                ("Rebuild", 2, "Restore mode is Rebuild"),
+            ],
+            "Restore mode enumeration",
+        );
+
+        add_enum(
+            "NcRestoreValidationStatus",
+            vec![
+                ("Ok", 200, "Restore was successful"),
+                ("Failed", 400, "Restore failed"),
+                (
+                    "NotFound",
+                    404,
+                    "Restore failed because the role path is not found in the device model or the device cannot create the role path from the data set",
+                ),
+                (
+                    "DeviceError",
+                    500,
+                    "Restore failed due to an internal device error preventing the restore from happening",
+                ),
+            ],
+            "Restore validation status enumeration",
+        );
+
+        add_enum(
+            "NcPropertyRestoreNoticeType",
+            vec![
+                ("Warning", 300, "Warning property restore notice"),
+                ("Error", 400, "Error property restore notice"),
+            ],
+            "Property restore notice type enumeration",
+        );
+
         add_enum(
             "NcDatatypeType",
             vec![
@@ -736,14 +814,61 @@ impl NcClassManager {
             [1] => Some(crate::nc_object::NcObject::get_class_descriptor(true)),
             [1, 1] => Some(crate::nc_block::NcBlock::get_class_descriptor(true)),
             [1, 2] => Some(crate::nc_worker::NcWorker::get_class_descriptor(true)),
+            [1, 2, 2] => Some(crate::nc_ident_beacon::NcIdentBeacon::get_class_descriptor(
+                true,
+            )),
             [1, 3] => Some(crate::nc_manager::NcManager::get_class_descriptor(true)),
             [1, 3, 1] => {
                 Some(crate::nc_device_manager::NcDeviceManager::get_class_descriptor(true))
             }
+            [1, 3, 1, 0, 1] => Some(
+                crate::nc_device_manager::NcDeviceManager::get_resettable_class_descriptor(true),
+            ),
             [1, 3, 2] => Some(crate::nc_class_manager::NcClassManager::get_class_descriptor(true)),
-            _ => None,
+            [1, 3, 3] => Some(
+                crate::nc_bulk_properties_manager::NcBulkPropertiesManager::get_class_descriptor(
+                    true,
+                ),
+            ),
+            // Registered classes inherit from their closest known ancestor
+            _ => {
+                let mut desc = self.control_classes_register.get(&key)?.clone();
+                if let Some(base_desc) = (1..class_id.len())
+                    .rev()
+                    .find_map(|len| self.get_control_class_descriptor(&class_id[..len], true))
+                {
+                    desc.properties.extend(base_desc.properties);
+                    desc.methods.extend(base_desc.methods);
+                    desc.events.extend(base_desc.events);
+                }
+                Some(desc)
+            }
         }
     }
+    /// Makes a class implemented outside this crate known to controllers, `descriptor` holds
+    /// only the elements the class adds to its parent
+    pub fn register_class(&mut self, descriptor: NcClassDescriptor) {
+        let key = NcClassManager::class_id_to_key(&descriptor.class_id);
+        self.control_classes_register.insert(key, descriptor);
+    }
+    /// Finds the descriptor of a class including inherited elements, derived class ids fall
+    /// back to the closest known ancestor
+    pub fn find_class_descriptor(&self, class_id: &[u32]) -> Option<NcClassDescriptor> {
+        (1..=class_id.len())
+            .rev()
+            .find_map(|len| self.get_control_class_descriptor(&class_id[..len], true))
+    }
+    /// Finds a property of a class including inherited ones
+    pub fn find_property_descriptor(
+        &self,
+        class_id: &[u32],
+        property_id: &NcElementId,
+    ) -> Option<NcPropertyDescriptor> {
+        self.find_class_descriptor(class_id)?
+            .properties
+            .into_iter()
+            .find(|p| p.id.level == property_id.level && p.id.index == property_id.index)
+    }
     pub fn get_class_descriptor(include_inherited: bool) -> NcClassDescriptor {
         let mut desc = NcClassDescriptor {
             base: crate::data_types::NcDescriptor { description: Some("NcClassManager class descriptor".to_string()) },
@@ -1,8 +1,8 @@
 use crate::data_types::{
     IdArgs, IdArgsValue, NcClassDescriptor, NcDeviceGenericState, NcDeviceOperationalState,
-    NcElementId, NcManufacturer, NcMethodStatus, NcProduct, NcPropertyChangeType,
-    NcPropertyConstraints, NcPropertyDescriptor, NcResetCause, NcTouchpoint, PropertyChangedEvent,
-    PropertyChangedEventData,
+    NcElementId, NcManufacturer, NcMethodDescriptor, NcMethodStatus, NcProduct,
+    NcPropertyChangeType, NcPropertyConstraints, NcPropertyDescriptor, NcResetCause, NcTouchpoint,
+    PropertyChangedEvent, PropertyChangedEventData,
 };
 use crate::nc_manager::NcManager;
 use crate::nc_object::NcMember;
@@ -10,6 +10,10 @@ use serde_json::{Value, json};
 use std::any::Any;
 use tokio::sync::mpsc;
 
+/// Vendor specific NcDeviceManager (authority key 0) adding the Reset method 4m1, used by every
+/// device manager of this device
+pub const RESETTABLE_DEVICE_MANAGER_CLASS_ID: [u32; 5] = [1, 3, 1, 0, 1];
+
 pub struct NcDeviceManager {
     pub base: NcManager,
     pub nc_version: String,
@@ -147,8 +151,12 @@ impl NcMember for NcDeviceManager {
                     ));
                     (None, NcMethodStatus::Ok)
                 }
+                1 | 2 | 3 | 4 | 8 | 9 | 10 => (
+                    Some("Property is readonly".to_string()),
+                    NcMethodStatus::Readonly,
+                ),
                 _ => (
-                    Some("Could not find the property or it is read-only".to_string()),
+                    Some("Could not find the property".to_string()),
                     NcMethodStatus::PropertyNotImplemented,
                 ),
             }
@@ -163,7 +171,18 @@ impl NcMember for NcDeviceManager {
         method_id: NcElementId,
         args: Value,
     ) -> (Option<String>, Option<Value>, NcMethodStatus) {
-        self.base.invoke_method(_oid, method_id, args)
+        match (method_id.level, method_id.index) {
+            // Reset, carried out by the application once the response has been sent
+            (4, 1) => (None, None, NcMethodStatus::Ok),
+            _ => self.base.invoke_method(_oid, method_id, args),
+        }
+    }
+
+    fn is_mutating_method(&self, method_id: &NcElementId) -> bool {
+        matches!(
+            (method_id.level, method_id.index),
+            (1, 2) | (1, 4) | (1, 5) | (1, 6) | (4, 1)
+        )
     }
 }
 
@@ -299,6 +318,49 @@ impl NcDeviceManager {
 
         desc
     }
+
+    /// Descriptor of the vendor specific class adding the Reset method
+    pub fn get_resettable_class_descriptor(include_inherited: bool) -> NcClassDescriptor {
+        let mut desc = NcClassDescriptor {
+            base: crate::data_types::NcDescriptor {
+                description: Some("Device manager which can be reset by controllers".to_string()),
+            },
+            class_id: RESETTABLE_DEVICE_MANAGER_CLASS_ID.to_vec(),
+            name: "ResettableDeviceManager".to_string(),
+            fixed_role: Some("DeviceManager".to_string()),
+            properties: vec![],
+            methods: vec![NcMethodDescriptor {
+                base: crate::data_types::NcDescriptor {
+                    description: Some(
+                        "Restarts the device, closing every control session".to_string(),
+                    ),
+                },
+                id: NcElementId { level: 4, index: 1 },
+                name: "Reset".to_string(),
+                result_datatype: "NcMethodResult".to_string(),
+                parameters: vec![],
+                is_deprecated: false,
+            }],
+            events: vec![],
+        };
+
+        if include_inherited {
+            let base_desc = NcDeviceManager::get_class_descriptor(true);
+            desc.properties.extend(base_desc.properties);
+            desc.methods.extend(base_desc.methods);
+            desc.events.extend(base_desc.events);
+        }
+
+        desc
+    }
+
+    /// True for a successful Reset of this device manager
+    pub fn is_reset(&self, oid: u64, method_id: &NcElementId, status: &NcMethodStatus) -> bool {
+        oid == self.get_oid()
+            && method_id.level == 4
+            && method_id.index == 1
+            && matches!(status, NcMethodStatus::Ok)
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -319,7 +381,7 @@ impl NcDeviceManager {
     ) -> Self {
         NcDeviceManager {
             base: NcManager::new(
-                vec![1, 3, 1], // Class ID for NcDeviceManager
+                RESETTABLE_DEVICE_MANAGER_CLASS_ID.to_vec(),
                 oid,
                 constant_oid,
                 owner,
@@ -337,7 +399,7 @@ impl NcDeviceManager {
             device_name: None,
             device_role: None,
             operational_state: NcDeviceOperationalState {
-                generic: NcDeviceGenericState::NormalOperation,
+                generic: NcDeviceGenericState::Initializing,
                 device_specific_details: None,
             },
             reset_cause: NcResetCause::PowerOn,
@@ -345,3 +407,257 @@ impl NcDeviceManager {
         }
     }
 }
+
+impl NcDeviceManager {
+    /// Moves the device to another generic operational state, refusing transitions the device
+    /// cannot make (e.g. back to Initializing while operating normally)
+    pub fn set_operational_state(
+        &mut self,
+        generic: NcDeviceGenericState,
+        device_specific_details: Option<String>,
+    ) -> anyhow::Result<()> {
+        let current = self.operational_state.generic;
+        if current != generic && !is_allowed_transition(current, generic) {
+            anyhow::bail!("Operational state cannot change from {current:?} to {generic:?}");
+        }
+        let state = NcDeviceOperationalState {
+            generic,
+            device_specific_details,
+        };
+        if state != self.operational_state {
+            self.operational_state = state;
+            self.notify_changed(8, json!(self.operational_state));
+        }
+        Ok(())
+    }
+
+    /// Enters Initializing for a device reset, whatever the current state. The change is returned
+    /// instead of being notified so the caller can deliver it before closing the connections.
+    pub fn begin_reset(&mut self) -> Option<PropertyChangedEvent> {
+        let state = NcDeviceOperationalState {
+            generic: NcDeviceGenericState::Initializing,
+            device_specific_details: None,
+        };
+        if state == self.operational_state {
+            return None;
+        }
+        self.operational_state = state;
+        Some(self.changed_event(8, json!(self.operational_state)))
+    }
+
+    pub fn set_reset_cause(&mut self, reset_cause: NcResetCause) {
+        if reset_cause != self.reset_cause {
+            self.reset_cause = reset_cause;
+            self.notify_changed(9, json!(self.reset_cause));
+        }
+    }
+
+    /// Sets the message shown to controllers, None clears it
+    pub fn set_message(&mut self, message: Option<String>) {
+        if message != self.message {
+            self.message = message;
+            self.notify_changed(10, json!(self.message));
+        }
+    }
+
+    fn notify_changed(&self, index: u32, value: Value) {
+        let _ = self
+            .base
+            .base
+            .notifier
+            .send(self.changed_event(index, value));
+    }
+
+    fn changed_event(&self, index: u32, value: Value) -> PropertyChangedEvent {
+        PropertyChangedEvent::new(
+            self.base.base.oid,
+            PropertyChangedEventData {
+                property_id: NcElementId { level: 3, index },
+                change_type: NcPropertyChangeType::ValueChanged,
+                value,
+                sequence_item_index: None,
+            },
+        )
+    }
+}
+
+/// Initializing leads to any state, afterwards the device only re-initializes after an update
+/// or an internal error, and errors are left for normal operation or another error
+fn is_allowed_transition(from: NcDeviceGenericState, to: NcDeviceGenericState) -> bool {
+    match (from, to) {
+        (_, NcDeviceGenericState::Unknown) => false,
+        (NcDeviceGenericState::Unknown | NcDeviceGenericState::Initializing, _) => true,
+        (
+            NcDeviceGenericState::NormalOperation,
+            NcDeviceGenericState::Updating
+            | NcDeviceGenericState::LicensingError
+            | NcDeviceGenericState::InternalError,
+        ) => true,
+        (
+            NcDeviceGenericState::Updating,
+            NcDeviceGenericState::NormalOperation
+            | NcDeviceGenericState::Initializing
+            | NcDeviceGenericState::InternalError,
+        ) => true,
+        (
+            NcDeviceGenericState::LicensingError,
+            NcDeviceGenericState::NormalOperation | NcDeviceGenericState::InternalError,
+        ) => true,
+        (
+            NcDeviceGenericState::InternalError,
+            NcDeviceGenericState::NormalOperation
+            | NcDeviceGenericState::Initializing
+            | NcDeviceGenericState::LicensingError,
+        ) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DeviceConfig;
+
+    fn device_manager() -> NcDeviceManager {
+        let device = DeviceConfig::default();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        NcDeviceManager::new(
+            2,
+            true,
+            Some(1),
+            "DeviceManager",
+            None,
+            None,
+            None,
+            "v1.0.0".to_string(),
+            device.manufacturer,
+            device.product,
+            device.serial_number,
+            tx,
+        )
+    }
+
+    fn set(manager: &mut NcDeviceManager, index: u32, value: Value) -> NcMethodStatus {
+        let (_, status) = manager.set_property(
+            2,
+            IdArgsValue {
+                id: NcElementId { level: 3, index },
+                value,
+            },
+        );
+        status
+    }
+
+    #[test]
+    fn read_only_properties_reject_set() {
+        let mut manager = device_manager();
+        for index in [1, 2, 3, 4, 8, 9, 10] {
+            let (_, value, _) = manager.get_property(
+                2,
+                &IdArgs {
+                    id: NcElementId { level: 3, index },
+                },
+            );
+            assert!(
+                matches!(set(&mut manager, index, value), NcMethodStatus::Readonly),
+                "3p{index}"
+            );
+        }
+    }
+
+    #[test]
+    fn writable_and_unknown_properties() {
+        let mut manager = device_manager();
+        for index in [5, 6, 7] {
+            assert!(
+                matches!(set(&mut manager, index, json!("x")), NcMethodStatus::Ok),
+                "3p{index}"
+            );
+        }
+        assert!(matches!(
+            set(&mut manager, 11, json!(null)),
+            NcMethodStatus::PropertyNotImplemented
+        ));
+    }
+
+    #[test]
+    fn generic_state_transitions() {
+        use NcDeviceGenericState::*;
+        // Every state a device can go to from each state
+        let table = [
+            (
+                Unknown,
+                vec![
+                    NormalOperation,
+                    Initializing,
+                    Updating,
+                    LicensingError,
+                    InternalError,
+                ],
+            ),
+            (
+                Initializing,
+                vec![
+                    NormalOperation,
+                    Initializing,
+                    Updating,
+                    LicensingError,
+                    InternalError,
+                ],
+            ),
+            (
+                NormalOperation,
+                vec![Updating, LicensingError, InternalError],
+            ),
+            (Updating, vec![NormalOperation, Initializing, InternalError]),
+            (LicensingError, vec![NormalOperation, InternalError]),
+            (
+                InternalError,
+                vec![NormalOperation, Initializing, LicensingError],
+            ),
+        ];
+        let states = [
+            Unknown,
+            NormalOperation,
+            Initializing,
+            Updating,
+            LicensingError,
+            InternalError,
+        ];
+        for (from, allowed) in table {
+            for to in states {
+                assert_eq!(
+                    is_allowed_transition(from, to),
+                    allowed.contains(&to),
+                    "{from:?} -> {to:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reset_initializes_from_any_state() {
+        let mut manager = device_manager();
+        manager
+            .set_operational_state(NcDeviceGenericState::NormalOperation, None)
+            .unwrap();
+        assert!(
+            manager
+                .set_operational_state(NcDeviceGenericState::Initializing, None)
+                .is_err()
+        );
+
+        let event = manager.begin_reset().unwrap();
+        assert_eq!(event.oid, 2);
+        assert_eq!(
+            event.event_data.property_id,
+            NcElementId { level: 3, index: 8 }
+        );
+        assert_eq!(event.event_data.value["generic"], json!(2));
+        assert_eq!(
+            manager.operational_state.generic,
+            NcDeviceGenericState::Initializing
+        );
+        assert!(manager.begin_reset().is_none());
+    }
+}
@@ -0,0 +1,324 @@
+use serde_json::{Value, json};
+use std::any::Any;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+
+use crate::data_types::{
+    IdArgs, IdArgsValue, NcClassDescriptor, NcDescriptor, NcElementId, NcMethodStatus,
+    NcPropertyChangeType, NcPropertyDescriptor, PropertyChangedEvent, PropertyChangedEventData,
+};
+use crate::nc_object::NcMember;
+use crate::nc_worker::NcWorker;
+
+/// Identification beacon of the device (e.g. a blinking front panel LED), switched off again
+/// automatically after a while
+pub struct NcIdentBeacon {
+    pub base: NcWorker,
+    active: Arc<AtomicBool>,
+    /// Bumped on every change of `active` so only the latest activation times out
+    activation: Arc<AtomicU64>,
+    timeout: Duration,
+}
+
+impl NcIdentBeacon {
+    pub fn get_class_descriptor(include_inherited: bool) -> NcClassDescriptor {
+        let mut desc = NcClassDescriptor {
+            base: NcDescriptor {
+                description: Some("NcIdentBeacon class descriptor".to_string()),
+            },
+            class_id: vec![1, 2, 2],
+            name: "NcIdentBeacon".to_string(),
+            fixed_role: None,
+            properties: vec![NcPropertyDescriptor {
+                base: NcDescriptor {
+                    description: Some("Indicator active state".to_string()),
+                },
+                id: NcElementId { level: 3, index: 1 },
+                name: "active".to_string(),
+                type_name: Some("NcBoolean".to_string()),
+                is_read_only: false,
+                is_nullable: false,
+                is_sequence: false,
+                is_deprecated: false,
+                constraints: None,
+            }],
+            methods: vec![],
+            events: vec![],
+        };
+
+        if include_inherited {
+            let base_desc = NcWorker::get_class_descriptor(true);
+            desc.properties.extend(base_desc.properties);
+            desc.methods.extend(base_desc.methods);
+            desc.events.extend(base_desc.events);
+        }
+
+        desc
+    }
+}
+
+impl NcIdentBeacon {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        oid: u64,
+        constant_oid: bool,
+        owner: Option<u64>,
+        role: &str,
+        user_label: Option<&str>,
+        touchpoints: Option<Vec<crate::data_types::NcTouchpoint>>,
+        runtime_property_constraints: Option<Vec<crate::data_types::NcPropertyConstraints>>,
+        timeout: Duration,
+        notifier: mpsc::UnboundedSender<PropertyChangedEvent>,
+    ) -> Self {
+        NcIdentBeacon {
+            base: NcWorker::new(
+                vec![1, 2, 2],
+                oid,
+                constant_oid,
+                owner,
+                role,
+                user_label,
+                touchpoints,
+                runtime_property_constraints,
+                notifier,
+            ),
+            active: Arc::new(AtomicBool::new(false)),
+            activation: Arc::new(AtomicU64::new(0)),
+            timeout,
+        }
+    }
+
+    /// Switches the beacon off once the timeout elapses, unless it was changed in the meantime
+    fn schedule_timeout(&self, runtime: &Handle, oid: u64, activation: u64) {
+        let active = self.active.clone();
+        let current = self.activation.clone();
+        let notifier = self.base.base.notifier.clone();
+        let timeout = self.timeout;
+        runtime.spawn(async move {
+            tokio::time::sleep(timeout).await;
+            if current.load(Ordering::SeqCst) == activation && active.swap(false, Ordering::SeqCst)
+            {
+                tracing::info!(oid, "identification beacon timed out");
+                let _ = notifier.send(active_changed(oid, false));
+            }
+        });
+    }
+}
+
+fn active_changed(oid: u64, active: bool) -> PropertyChangedEvent {
+    PropertyChangedEvent::new(
+        oid,
+        PropertyChangedEventData {
+            property_id: NcElementId { level: 3, index: 1 },
+            change_type: NcPropertyChangeType::ValueChanged,
+            value: json!(active),
+            sequence_item_index: None,
+        },
+    )
+}
+
+impl NcMember for NcIdentBeacon {
+    fn member_type(&self) -> &'static str {
+        "NcIdentBeacon"
+    }
+
+    fn get_role(&self) -> &str {
+        self.base.get_role()
+    }
+
+    fn get_oid(&self) -> u64 {
+        self.base.get_oid()
+    }
+
+    fn get_constant_oid(&self) -> bool {
+        self.base.get_constant_oid()
+    }
+
+    fn get_class_id(&self) -> &[u32] {
+        self.base.get_class_id()
+    }
+
+    fn get_user_label(&self) -> Option<&str> {
+        self.base.get_user_label()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn get_property(&self, oid: u64, id_args: &IdArgs) -> (Option<String>, Value, NcMethodStatus) {
+        if id_args.id.level == 3 && id_args.id.index == 1 {
+            return (
+                None,
+                json!(self.active.load(Ordering::SeqCst)),
+                NcMethodStatus::Ok,
+            );
+        }
+
+        self.base.get_property(oid, id_args)
+    }
+
+    fn set_property(
+        &mut self,
+        oid: u64,
+        id_args_value: IdArgsValue,
+    ) -> (Option<String>, NcMethodStatus) {
+        if id_args_value.id.level == 3 && id_args_value.id.index == 1 {
+            let Value::Bool(active) = id_args_value.value else {
+                return (
+                    Some("Property value was invalid".to_string()),
+                    NcMethodStatus::ParameterError,
+                );
+            };
+
+            // The timeout needs a runtime, e.g. not while a model is built synchronously
+            let runtime = match active {
+                true => match Handle::try_current() {
+                    Ok(runtime) => Some(runtime),
+                    Err(_) => {
+                        return (
+                            Some("Beacon cannot be activated without a runtime".to_string()),
+                            NcMethodStatus::DeviceError,
+                        );
+                    }
+                },
+                false => None,
+            };
+
+            // Every write restarts the timeout, even when the beacon is already on
+            let activation = self.activation.fetch_add(1, Ordering::SeqCst) + 1;
+            if let Some(runtime) = runtime {
+                self.schedule_timeout(&runtime, oid, activation);
+            }
+            if self.active.swap(active, Ordering::SeqCst) != active {
+                tracing::info!(oid, active, "identification beacon switched");
+                let _ = self.base.base.notifier.send(active_changed(oid, active));
+            }
+            return (None, NcMethodStatus::Ok);
+        }
+
+        self.base.set_property(oid, id_args_value)
+    }
+
+    fn invoke_method(
+        &self,
+        oid: u64,
+        method_id: NcElementId,
+        args: Value,
+    ) -> (Option<String>, Option<Value>, NcMethodStatus) {
+        self.base.invoke_method(oid, method_id, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OID: u64 = 10;
+    const ACTIVE: NcElementId = NcElementId { level: 3, index: 1 };
+
+    fn beacon(timeout: Duration) -> (NcIdentBeacon, mpsc::UnboundedReceiver<PropertyChangedEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let beacon = NcIdentBeacon::new(
+            OID,
+            true,
+            Some(1),
+            "IdentBeacon",
+            None,
+            None,
+            None,
+            timeout,
+            tx,
+        );
+        (beacon, rx)
+    }
+
+    fn set_active(beacon: &mut NcIdentBeacon, active: bool) -> NcMethodStatus {
+        let id_args_value = IdArgsValue {
+            id: ACTIVE,
+            value: json!(active),
+        };
+        beacon.set_property(OID, id_args_value).1
+    }
+
+    fn is_active(beacon: &NcIdentBeacon) -> Value {
+        beacon.get_property(OID, &IdArgs { id: ACTIVE }).1
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn beacon_switches_off_after_the_timeout() {
+        let (mut beacon, mut events) = beacon(Duration::from_secs(10));
+        assert!(matches!(set_active(&mut beacon, true), NcMethodStatus::Ok));
+        assert_eq!(events.recv().await.unwrap().event_data.value, json!(true));
+
+        tokio::time::sleep(Duration::from_secs(9)).await;
+        assert_eq!(is_active(&beacon), json!(true));
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(is_active(&beacon), json!(false));
+        assert_eq!(events.recv().await.unwrap().event_data.value, json!(false));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn activating_again_restarts_the_timeout() {
+        let (mut beacon, mut events) = beacon(Duration::from_secs(10));
+        set_active(&mut beacon, true);
+        tokio::time::sleep(Duration::from_secs(6)).await;
+        set_active(&mut beacon, true);
+
+        // Only the first activation would have timed out by now
+        tokio::time::sleep(Duration::from_secs(6)).await;
+        assert_eq!(is_active(&beacon), json!(true));
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(is_active(&beacon), json!(false));
+
+        // Switched on once and off once, the repeated activation changed nothing
+        assert_eq!(events.recv().await.unwrap().event_data.value, json!(true));
+        assert_eq!(events.recv().await.unwrap().event_data.value, json!(false));
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stale_activation_does_not_switch_off_a_later_one() {
+        let (mut beacon, mut events) = beacon(Duration::from_secs(10));
+        set_active(&mut beacon, true);
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        set_active(&mut beacon, false);
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        set_active(&mut beacon, true);
+
+        // The timer of the first activation fires while the second one is running
+        tokio::time::sleep(Duration::from_secs(4)).await;
+        assert_eq!(is_active(&beacon), json!(true));
+        tokio::time::sleep(Duration::from_secs(7)).await;
+        assert_eq!(is_active(&beacon), json!(false));
+
+        let values: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+            .map(|event| event.event_data.value)
+            .collect();
+        assert_eq!(
+            values,
+            [json!(true), json!(false), json!(true), json!(false)]
+        );
+    }
+
+    #[test]
+    fn activation_without_a_runtime_fails_cleanly() {
+        let (mut beacon, mut events) = beacon(Duration::from_secs(10));
+        assert!(matches!(
+            set_active(&mut beacon, true),
+            NcMethodStatus::DeviceError
+        ));
+        assert_eq!(is_active(&beacon), json!(false));
+        assert!(events.try_recv().is_err());
+
+        // Switching off does not need a timer
+        assert!(matches!(set_active(&mut beacon, false), NcMethodStatus::Ok));
+    }
+}
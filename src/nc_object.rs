@@ -33,6 +33,13 @@ pub trait NcMember: Send {
         _method_id: NcElementId,
         _args: Value,
     ) -> (Option<String>, Option<Value>, NcMethodStatus);
+    // Methods which change state and need write access (Set and the sequence modifiers by default)
+    fn is_mutating_method(&self, method_id: &NcElementId) -> bool {
+        matches!(
+            (method_id.level, method_id.index),
+            (1, 2) | (1, 4) | (1, 5) | (1, 6)
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Roles from the root block down to an object, e.g. `root.my-block-01.my-worker-02`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NcRolePath(Vec<String>);
+
+impl NcRolePath {
+    /// Parses a role path written with `.` or `/` separators (e.g. `root/my-block-01`),
+    /// None when it is empty or has an empty role
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim_matches('/');
+        let roles: Vec<String> = text.split(['.', '/']).map(str::to_string).collect();
+        (!roles.iter().any(String::is_empty)).then_some(NcRolePath(roles))
+    }
+
+    pub fn roles(&self) -> &[String] {
+        &self.0
+    }
+
+    /// True when this path is `prefix` or names an object nested under it
+    pub fn starts_with(&self, prefix: &NcRolePath) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+}
+
+impl fmt::Display for NcRolePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.join("."))
+    }
+}
+
+impl From<Vec<String>> for NcRolePath {
+    fn from(roles: Vec<String>) -> Self {
+        NcRolePath(roles)
+    }
+}
@@ -0,0 +1,226 @@
+use axum::{
+    Json,
+    extract::{Path, Request, State},
+    http::{StatusCode, Uri, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::{
+    AppState,
+    auth::bearer_token,
+    node_api_versions::{NODE_API_VERSIONS, NodeResourceType, downgrade, parse_version},
+    node_resources::find_by_id,
+};
+
+/// Requires a token with read access to the requested Node API path when authorization is enabled
+pub async fn node_auth_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(auth) = &state.auth {
+        // Claims are relative to the versioned API base, e.g. `self` or `senders/{id}`
+        let path = request
+            .uri()
+            .path()
+            .trim_start_matches("/x-nmos/node/")
+            .split_once('/')
+            .map_or("", |(_version, path)| path)
+            .trim_matches('/');
+        let token = bearer_token(request.headers(), request.uri().query());
+        if let Err(err) = auth.authorize_node(token, path) {
+            return err.into_response();
+        }
+    }
+    next.run(request).await
+}
+
+pub async fn root_listing_handler() -> impl IntoResponse {
+    Json(json!(["x-nmos/"]))
+}
+
+pub async fn x_nmos_listing_handler() -> impl IntoResponse {
+    Json(json!(["channelmapping/", "configuration/", "node/"]))
+}
+
+pub async fn node_versions_listing_handler() -> impl IntoResponse {
+    let versions: Vec<String> = NODE_API_VERSIONS.iter().map(|v| format!("{v}/")).collect();
+    Json(json!(versions))
+}
+
+pub async fn base_is_04_rest_api_handler(Path(version): Path<String>) -> Response {
+    if parse_version(&version).is_none() {
+        return unknown_version_error(&version);
+    }
+    Json(json!([
+        "self/",
+        "sources/",
+        "flows/",
+        "devices/",
+        "senders/",
+        "receivers/"
+    ]))
+    .into_response()
+}
+
+pub async fn node_self_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path(version): Path<String>,
+) -> Response {
+    let resources = state.resources.read().await;
+    resource_response(&version, NodeResourceType::Node, Some(resources.node()))
+}
+
+pub async fn sources_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path(version): Path<String>,
+) -> Response {
+    let resources = state.resources.read().await;
+    list_response(&version, NodeResourceType::Source, resources.sources())
+}
+
+pub async fn source_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path((version, id)): Path<(String, String)>,
+) -> Response {
+    let resources = state.resources.read().await;
+    let source = find_by_id(resources.sources(), &id);
+    resource_response(&version, NodeResourceType::Source, source)
+}
+
+pub async fn flows_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path(version): Path<String>,
+) -> Response {
+    let resources = state.resources.read().await;
+    list_response(&version, NodeResourceType::Flow, resources.flows())
+}
+
+pub async fn flow_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path((version, id)): Path<(String, String)>,
+) -> Response {
+    let resources = state.resources.read().await;
+    let flow = find_by_id(resources.flows(), &id);
+    resource_response(&version, NodeResourceType::Flow, flow)
+}
+
+pub async fn senders_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path(version): Path<String>,
+) -> Response {
+    let resources = state.resources.read().await;
+    list_response(&version, NodeResourceType::Sender, resources.senders())
+}
+
+pub async fn sender_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path((version, id)): Path<(String, String)>,
+) -> Response {
+    let resources = state.resources.read().await;
+    let sender = find_by_id(resources.senders(), &id);
+    resource_response(&version, NodeResourceType::Sender, sender)
+}
+
+pub async fn receivers_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path(version): Path<String>,
+) -> Response {
+    let resources = state.resources.read().await;
+    list_response(&version, NodeResourceType::Receiver, resources.receivers())
+}
+
+pub async fn receiver_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path((version, id)): Path<(String, String)>,
+) -> Response {
+    let resources = state.resources.read().await;
+    let receiver = find_by_id(resources.receivers(), &id);
+    resource_response(&version, NodeResourceType::Receiver, receiver)
+}
+
+pub async fn devices_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path(version): Path<String>,
+) -> Response {
+    let resources = state.resources.read().await;
+    list_response(
+        &version,
+        NodeResourceType::Device,
+        std::slice::from_ref(resources.device()),
+    )
+}
+
+pub async fn device_rest_api_handler(
+    State(state): State<Arc<AppState>>,
+    Path((version, id)): Path<(String, String)>,
+) -> Response {
+    let resources = state.resources.read().await;
+    let device = Some(resources.device()).filter(|device| device.base.id == id);
+    resource_response(&version, NodeResourceType::Device, device)
+}
+
+/// Serves a resource collection in the schema of the requested version
+fn list_response<T: serde::Serialize>(
+    version: &str,
+    resource_type: NodeResourceType,
+    resources: &[T],
+) -> Response {
+    let Some(minor) = parse_version(version) else {
+        return unknown_version_error(version);
+    };
+    let resources: Vec<_> = resources
+        .iter()
+        .map(|resource| downgrade(resource_type, json!(resource), minor))
+        .collect();
+    Json(json!(resources)).into_response()
+}
+
+/// Serves a single resource in the schema of the requested version, or the IS-04 error for an
+/// unknown id
+fn resource_response<T: serde::Serialize>(
+    version: &str,
+    resource_type: NodeResourceType,
+    resource: Option<&T>,
+) -> Response {
+    let Some(minor) = parse_version(version) else {
+        return unknown_version_error(version);
+    };
+    match resource {
+        Some(resource) => Json(downgrade(resource_type, json!(resource), minor)).into_response(),
+        None => is_04_error(
+            StatusCode::NOT_FOUND,
+            format!("No {} with this id", resource_type.name()),
+        ),
+    }
+}
+
+fn unknown_version_error(version: &str) -> Response {
+    is_04_error(
+        StatusCode::NOT_FOUND,
+        format!("Node API version {version} is not supported"),
+    )
+}
+
+/// Error body of the IS-04 and IS-08 APIs
+pub fn is_04_error(status: StatusCode, error: String) -> Response {
+    let body = json!({ "code": status.as_u16(), "error": error, "debug": null });
+    (status, Json(body)).into_response()
+}
+
+/// Single resources are served without a trailing slash, IS-04 clients are redirected there
+pub async fn remove_trailing_slash_handler(uri: Uri) -> Response {
+    let path = uri.path().trim_end_matches('/');
+    let location = match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_string(),
+    };
+    (
+        StatusCode::MOVED_PERMANENTLY,
+        [(header::LOCATION, location)],
+    )
+        .into_response()
+}
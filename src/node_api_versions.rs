@@ -0,0 +1,362 @@
+use serde_json::Value;
+
+/// Node API versions served, oldest first
+pub const NODE_API_VERSIONS: [&str; 4] = ["v1.0", "v1.1", "v1.2", "v1.3"];
+
+/// Resource types of the Node API
+#[derive(Debug, Clone, Copy)]
+pub enum NodeResourceType {
+    Node,
+    Device,
+    Source,
+    Flow,
+    Sender,
+    Receiver,
+}
+
+impl NodeResourceType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeResourceType::Node => "node",
+            NodeResourceType::Device => "device",
+            NodeResourceType::Source => "source",
+            NodeResourceType::Flow => "flow",
+            NodeResourceType::Sender => "sender",
+            NodeResourceType::Receiver => "receiver",
+        }
+    }
+
+    /// Top level attributes first defined by the schemas of the given minor version
+    fn added_attributes(&self, minor: usize) -> &'static [&'static str] {
+        match (self, minor) {
+            (NodeResourceType::Node, 1) => &["description", "tags", "api", "clocks"],
+            (NodeResourceType::Node, 2) => &["interfaces"],
+            (NodeResourceType::Device, 1) => &["description", "tags", "controls"],
+            (NodeResourceType::Source, 1) => &["grain_rate", "clock_name", "channels"],
+            (NodeResourceType::Flow, 1) => &[
+                "grain_rate",
+                "device_id",
+                "media_type",
+                "frame_width",
+                "frame_height",
+                "interlace_mode",
+                "colorspace",
+                "transfer_characteristic",
+                "components",
+                "sample_rate",
+                "bit_depth",
+                "DID_SDID",
+            ],
+            (NodeResourceType::Flow, 3) => &["event_type"],
+            (NodeResourceType::Sender, 2) => &["interface_bindings", "subscription"],
+            (NodeResourceType::Sender, 3) => &["caps"],
+            (NodeResourceType::Receiver, 2) => &["interface_bindings"],
+            _ => &[],
+        }
+    }
+}
+
+/// Minor version of a supported `v1.x` path segment
+pub fn parse_version(version: &str) -> Option<usize> {
+    NODE_API_VERSIONS.iter().position(|v| *v == version)
+}
+
+/// Strips what the schemas of the requested version do not define, newer versions only ever
+/// added attributes so the result stays valid for older clients
+pub fn downgrade(resource_type: NodeResourceType, mut resource: Value, minor: usize) -> Value {
+    let Value::Object(attributes) = &mut resource else {
+        return resource;
+    };
+    for newer in minor + 1..NODE_API_VERSIONS.len() {
+        for name in resource_type.added_attributes(newer) {
+            attributes.remove(*name);
+        }
+    }
+
+    // The authorization flags arrived with v1.3, as did senders without a transport file
+    if minor < 3 {
+        if let Some(manifest_href) = resource.get_mut("manifest_href")
+            && manifest_href.is_null()
+        {
+            *manifest_href = Value::String(String::new());
+        }
+        let pointers: &[&str] = match resource_type {
+            NodeResourceType::Node => &["/services", "/api/endpoints"],
+            NodeResourceType::Device => &["/controls"],
+            _ => &[],
+        };
+        for pointer in pointers {
+            let entries = resource.pointer_mut(pointer).and_then(Value::as_array_mut);
+            for entry in entries.into_iter().flatten() {
+                if let Some(entry) = entry.as_object_mut() {
+                    entry.remove("authorization");
+                }
+            }
+        }
+    }
+
+    resource
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const V1_0: usize = 0;
+    const V1_1: usize = 1;
+    const V1_2: usize = 2;
+
+    fn node() -> Value {
+        json!({
+            "id": "3b8be755-08ff-452b-b217-c9151eb21193",
+            "version": "1441973902:879053935",
+            "label": "node",
+            "description": "",
+            "tags": {},
+            "href": "http://127.0.0.1:8080/",
+            "hostname": "host",
+            "caps": {},
+            "api": {
+                "versions": ["v1.3"],
+                "endpoints": [{ "host": "127.0.0.1", "port": 8080, "protocol": "http", "authorization": false }]
+            },
+            "services": [{ "href": "ws://127.0.0.1:8080/ws", "type": "urn:x-nmos:control:ncp/v1.0", "authorization": false }],
+            "clocks": [{ "name": "clk0", "ref_type": "internal" }],
+            "interfaces": []
+        })
+    }
+
+    fn device() -> Value {
+        json!({
+            "id": "58f6b536-ca4c-43fd-880a-9df2501fc125",
+            "version": "1441973902:879053935",
+            "label": "device",
+            "description": "",
+            "tags": {},
+            "type": "urn:x-nmos:device:generic",
+            "node_id": "3b8be755-08ff-452b-b217-c9151eb21193",
+            "senders": [],
+            "receivers": [],
+            "controls": [{ "href": "ws://127.0.0.1:8080/ws", "type": "urn:x-nmos:control:ncp/v1.0", "authorization": false }]
+        })
+    }
+
+    fn source() -> Value {
+        json!({
+            "id": "2aa143ac-0ab7-4d75-bc32-5c00c13d186f",
+            "version": "1441973902:879053935",
+            "label": "source",
+            "description": "",
+            "tags": {},
+            "caps": {},
+            "format": "urn:x-nmos:format:audio",
+            "device_id": "58f6b536-ca4c-43fd-880a-9df2501fc125",
+            "parents": [],
+            "clock_name": "clk0",
+            "grain_rate": { "numerator": 48000 },
+            "channels": [{ "label": "L" }, { "label": "R" }]
+        })
+    }
+
+    fn flow() -> Value {
+        json!({
+            "id": "5fbec3b1-1b0f-417d-9059-8b94a47197ed",
+            "version": "1441973902:879053935",
+            "label": "flow",
+            "description": "",
+            "tags": {},
+            "format": "urn:x-nmos:format:data",
+            "source_id": "2aa143ac-0ab7-4d75-bc32-5c00c13d186f",
+            "parents": [],
+            "device_id": "58f6b536-ca4c-43fd-880a-9df2501fc125",
+            "grain_rate": { "numerator": 25 },
+            "media_type": "application/json",
+            "event_type": "boolean"
+        })
+    }
+
+    fn sender() -> Value {
+        json!({
+            "id": "d7aa5a30-681d-4e72-92fb-f0ba0f6f4c3e",
+            "version": "1441973902:879053935",
+            "label": "sender",
+            "description": "",
+            "tags": {},
+            "flow_id": "5fbec3b1-1b0f-417d-9059-8b94a47197ed",
+            "transport": "urn:x-nmos:transport:websocket",
+            "device_id": "58f6b536-ca4c-43fd-880a-9df2501fc125",
+            "manifest_href": null,
+            "interface_bindings": [],
+            "subscription": { "receiver_id": null, "active": false },
+            "caps": {}
+        })
+    }
+
+    fn receiver() -> Value {
+        json!({
+            "id": "db9ec6d1-d5b7-4b2b-b6b7-7cfa5f3a44d0",
+            "version": "1441973902:879053935",
+            "label": "receiver",
+            "description": "",
+            "tags": {},
+            "format": "urn:x-nmos:format:audio",
+            "caps": { "media_types": ["audio/L24"] },
+            "device_id": "58f6b536-ca4c-43fd-880a-9df2501fc125",
+            "transport": "urn:x-nmos:transport:rtp",
+            "subscription": { "sender_id": null, "active": false },
+            "interface_bindings": []
+        })
+    }
+
+    /// Sorted top level attribute names
+    fn attributes(resource: &Value) -> Vec<String> {
+        let mut names: Vec<String> = resource.as_object().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    fn assert_attributes(resource_type: NodeResourceType, fixture: Value, cases: [&[&str]; 3]) {
+        for (minor, expected) in cases.into_iter().enumerate() {
+            let resource = downgrade(resource_type, fixture.clone(), minor);
+            assert_eq!(
+                attributes(&resource),
+                sorted(expected),
+                "{} v1.{minor}",
+                resource_type.name()
+            );
+        }
+    }
+
+    #[test]
+    fn current_resources_are_left_untouched() {
+        let fixtures = [
+            (NodeResourceType::Node, node()),
+            (NodeResourceType::Device, device()),
+            (NodeResourceType::Source, source()),
+            (NodeResourceType::Flow, flow()),
+            (NodeResourceType::Sender, sender()),
+            (NodeResourceType::Receiver, receiver()),
+        ];
+        for (resource_type, fixture) in fixtures {
+            assert_eq!(downgrade(resource_type, fixture.clone(), 3), fixture);
+        }
+    }
+
+    #[test]
+    fn nodes_are_downgraded() {
+        let v1_0 = [
+            "id", "version", "label", "href", "hostname", "caps", "services",
+        ];
+        let v1_1 = [&v1_0[..], &["description", "tags", "api", "clocks"]].concat();
+        let v1_2 = [&v1_1[..], &["interfaces"]].concat();
+        assert_attributes(NodeResourceType::Node, node(), [&v1_0, &v1_1, &v1_2]);
+
+        let node = downgrade(NodeResourceType::Node, node(), V1_2);
+        assert_eq!(node["services"][0].get("authorization"), None);
+        assert_eq!(node["api"]["endpoints"][0].get("authorization"), None);
+        assert_eq!(node["api"]["endpoints"][0]["port"], json!(8080));
+    }
+
+    #[test]
+    fn devices_are_downgraded() {
+        let v1_0 = [
+            "id",
+            "version",
+            "label",
+            "type",
+            "node_id",
+            "senders",
+            "receivers",
+        ];
+        let v1_1 = [&v1_0[..], &["description", "tags", "controls"]].concat();
+        assert_attributes(NodeResourceType::Device, device(), [&v1_0, &v1_1, &v1_1]);
+
+        let device = downgrade(NodeResourceType::Device, device(), V1_1);
+        assert_eq!(device["controls"][0].get("authorization"), None);
+    }
+
+    #[test]
+    fn sources_are_downgraded() {
+        let v1_0 = [
+            "id",
+            "version",
+            "label",
+            "description",
+            "tags",
+            "caps",
+            "format",
+            "device_id",
+            "parents",
+        ];
+        let v1_1 = [&v1_0[..], &["clock_name", "grain_rate", "channels"]].concat();
+        assert_attributes(NodeResourceType::Source, source(), [&v1_0, &v1_1, &v1_1]);
+    }
+
+    #[test]
+    fn flows_are_downgraded() {
+        let v1_0 = [
+            "id",
+            "version",
+            "label",
+            "description",
+            "tags",
+            "format",
+            "source_id",
+            "parents",
+        ];
+        let v1_1 = [&v1_0[..], &["device_id", "grain_rate", "media_type"]].concat();
+        assert_attributes(NodeResourceType::Flow, flow(), [&v1_0, &v1_1, &v1_1]);
+    }
+
+    #[test]
+    fn senders_are_downgraded() {
+        let v1_0 = [
+            "id",
+            "version",
+            "label",
+            "description",
+            "tags",
+            "flow_id",
+            "transport",
+            "device_id",
+            "manifest_href",
+        ];
+        let v1_2 = [&v1_0[..], &["interface_bindings", "subscription"]].concat();
+        assert_attributes(NodeResourceType::Sender, sender(), [&v1_0, &v1_0, &v1_2]);
+
+        // Older schemas require a manifest_href string
+        for minor in [V1_0, V1_1, V1_2] {
+            let sender = downgrade(NodeResourceType::Sender, sender(), minor);
+            assert_eq!(sender["manifest_href"], json!(""));
+        }
+    }
+
+    #[test]
+    fn receivers_are_downgraded() {
+        let v1_0 = [
+            "id",
+            "version",
+            "label",
+            "description",
+            "tags",
+            "format",
+            "caps",
+            "device_id",
+            "transport",
+            "subscription",
+        ];
+        let v1_2 = [&v1_0[..], &["interface_bindings"]].concat();
+        assert_attributes(
+            NodeResourceType::Receiver,
+            receiver(),
+            [&v1_0, &v1_0, &v1_2],
+        );
+    }
+}
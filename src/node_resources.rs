@@ -0,0 +1,268 @@
+use crate::{
+    data_types::{
+        NmosDevice, NmosFlow, NmosNode, NmosReceiver, NmosResource, NmosSender, NmosSource,
+    },
+    tai,
+};
+
+/// Gives access to the attributes every IS-04 resource has
+pub trait NmosResourceBase {
+    fn resource(&self) -> &NmosResource;
+    fn resource_mut(&mut self) -> &mut NmosResource;
+}
+
+impl NmosResourceBase for NmosNode {
+    fn resource(&self) -> &NmosResource {
+        &self.base
+    }
+    fn resource_mut(&mut self) -> &mut NmosResource {
+        &mut self.base
+    }
+}
+
+impl NmosResourceBase for NmosDevice {
+    fn resource(&self) -> &NmosResource {
+        &self.base
+    }
+    fn resource_mut(&mut self) -> &mut NmosResource {
+        &mut self.base
+    }
+}
+
+impl NmosResourceBase for NmosSource {
+    fn resource(&self) -> &NmosResource {
+        &self.base
+    }
+    fn resource_mut(&mut self) -> &mut NmosResource {
+        &mut self.base
+    }
+}
+
+impl NmosResourceBase for NmosFlow {
+    fn resource(&self) -> &NmosResource {
+        &self.base
+    }
+    fn resource_mut(&mut self) -> &mut NmosResource {
+        &mut self.base
+    }
+}
+
+impl NmosResourceBase for NmosSender {
+    fn resource(&self) -> &NmosResource {
+        &self.base
+    }
+    fn resource_mut(&mut self) -> &mut NmosResource {
+        &mut self.base
+    }
+}
+
+impl NmosResourceBase for NmosReceiver {
+    fn resource(&self) -> &NmosResource {
+        &self.base
+    }
+    fn resource_mut(&mut self) -> &mut NmosResource {
+        &mut self.base
+    }
+}
+
+/// The IS-04 resources of the node and its device served by the Node API. Every change gives the resource a
+/// new version and the device lists of senders and receivers follow their registration.
+pub struct NodeResources {
+    node: NmosNode,
+    device: NmosDevice,
+    sources: Vec<NmosSource>,
+    flows: Vec<NmosFlow>,
+    senders: Vec<NmosSender>,
+    receivers: Vec<NmosReceiver>,
+    /// Label of the device while the device manager has no deviceName
+    configured_device_label: String,
+}
+
+impl NodeResources {
+    pub fn new(node: NmosNode, device: NmosDevice) -> Self {
+        let configured_device_label = device.base.label.clone();
+        NodeResources {
+            node,
+            device,
+            sources: Vec::new(),
+            flows: Vec::new(),
+            senders: Vec::new(),
+            receivers: Vec::new(),
+            configured_device_label,
+        }
+    }
+
+    pub fn node(&self) -> &NmosNode {
+        &self.node
+    }
+
+    pub fn device(&self) -> &NmosDevice {
+        &self.device
+    }
+
+    /// Changes the node resource and gives it a new version
+    pub fn update_node(&mut self, update: impl FnOnce(&mut NmosNode)) {
+        update(&mut self.node);
+        self.node.base.version = tai::next_version();
+    }
+
+    /// Changes the device resource and gives it a new version
+    pub fn update_device(&mut self, update: impl FnOnce(&mut NmosDevice)) {
+        update(&mut self.device);
+        self.device.base.version = tai::next_version();
+    }
+
+    /// Labels the device with the deviceName of the device manager, or the configured label
+    /// when it is null
+    pub fn set_device_name(&mut self, name: Option<&str>) {
+        let label = name.unwrap_or(&self.configured_device_label).to_string();
+        if self.device.base.label != label {
+            self.update_device(|device| device.base.label = label);
+        }
+    }
+
+    pub fn sources(&self) -> &[NmosSource] {
+        &self.sources
+    }
+
+    pub fn flows(&self) -> &[NmosFlow] {
+        &self.flows
+    }
+
+    pub fn senders(&self) -> &[NmosSender] {
+        &self.senders
+    }
+
+    pub fn receivers(&self) -> &[NmosReceiver] {
+        &self.receivers
+    }
+
+    /// Adds the source or replaces the one with the same id
+    pub fn insert_source(&mut self, source: NmosSource) {
+        upsert(&mut self.sources, source);
+    }
+
+    /// Adds the flow or replaces the one with the same id
+    pub fn insert_flow(&mut self, flow: NmosFlow) {
+        upsert(&mut self.flows, flow);
+    }
+
+    /// Adds the sender or replaces the one with the same id, listing it on the device
+    pub fn insert_sender(&mut self, sender: NmosSender) {
+        let id = sender.base.id.clone();
+        upsert(&mut self.senders, sender);
+        if !self.device.senders.contains(&id) {
+            self.update_device(|device| device.senders.push(id));
+        }
+    }
+
+    /// Adds the receiver or replaces the one with the same id, listing it on the device
+    pub fn insert_receiver(&mut self, receiver: NmosReceiver) {
+        let id = receiver.base.id.clone();
+        upsert(&mut self.receivers, receiver);
+        if !self.device.receivers.contains(&id) {
+            self.update_device(|device| device.receivers.push(id));
+        }
+    }
+
+    /// Removes the sender and takes it off the device, None when there is no such sender
+    pub fn remove_sender(&mut self, id: &str) -> Option<NmosSender> {
+        let sender = remove(&mut self.senders, id)?;
+        self.update_device(|device| device.senders.retain(|sender_id| sender_id != id));
+        Some(sender)
+    }
+
+    /// Removes the receiver and takes it off the device, None when there is no such receiver
+    pub fn remove_receiver(&mut self, id: &str) -> Option<NmosReceiver> {
+        let receiver = remove(&mut self.receivers, id)?;
+        self.update_device(|device| device.receivers.retain(|receiver_id| receiver_id != id));
+        Some(receiver)
+    }
+}
+
+/// Finds a resource by its id
+pub fn find_by_id<'a, T: NmosResourceBase>(resources: &'a [T], id: &str) -> Option<&'a T> {
+    resources.iter().find(|r| r.resource().id == id)
+}
+
+fn remove<T: NmosResourceBase>(resources: &mut Vec<T>, id: &str) -> Option<T> {
+    let index = resources.iter().position(|r| r.resource().id == id)?;
+    Some(resources.remove(index))
+}
+
+fn upsert<T: NmosResourceBase>(resources: &mut Vec<T>, mut resource: T) {
+    resource.resource_mut().version = tai::next_version();
+    let id = &resource.resource().id;
+    match resources.iter_mut().find(|r| r.resource().id == *id) {
+        Some(existing) => *existing = resource,
+        None => resources.push(resource),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data_types::NmosApi, model::build_default_resources};
+    use std::collections::HashMap;
+
+    fn resources() -> NodeResources {
+        let node = NmosNode::new(
+            "3b8be755-08ff-452b-b217-c9151eb21193".into(),
+            "Node".into(),
+            String::new(),
+            tai::next_version(),
+            HashMap::new(),
+            "http://localhost/".into(),
+            "localhost".into(),
+            Vec::new(),
+            Vec::new(),
+            NmosApi {
+                endpoints: Vec::new(),
+                versions: vec!["v1.3".into()],
+            },
+        );
+        let device = NmosDevice::new(
+            "67c25159-ce25-4000-a66c-f31fff890265".into(),
+            "Device".into(),
+            String::new(),
+            tai::next_version(),
+            HashMap::new(),
+            vec![],
+            vec![],
+            node.base.id.clone(),
+            "urn:x-nmos:device:generic".into(),
+            vec![],
+        );
+        let mut resources = NodeResources::new(node, device);
+        build_default_resources(&mut resources);
+        resources
+    }
+
+    #[test]
+    fn removed_senders_and_receivers_leave_the_device() {
+        let mut resources = resources();
+        let sender_id = resources.senders()[0].base.id.clone();
+        let receiver_id = resources.receivers()[0].base.id.clone();
+        let senders = resources.senders().len();
+        let receivers = resources.receivers().len();
+
+        let version = resources.device().base.version.clone();
+        let removed = resources.remove_sender(&sender_id).unwrap();
+        assert_eq!(removed.base.id, sender_id);
+        assert_eq!(resources.senders().len(), senders - 1);
+        assert!(!resources.device().senders.contains(&sender_id));
+        assert_ne!(resources.device().base.version, version);
+
+        let version = resources.device().base.version.clone();
+        assert!(resources.remove_receiver(&receiver_id).is_some());
+        assert_eq!(resources.receivers().len(), receivers - 1);
+        assert!(!resources.device().receivers.contains(&receiver_id));
+        assert_ne!(resources.device().base.version, version);
+
+        // Unknown ids change nothing
+        let version = resources.device().base.version.clone();
+        assert!(resources.remove_sender(&sender_id).is_none());
+        assert!(resources.remove_receiver("unknown").is_none());
+        assert_eq!(resources.device().base.version, version);
+    }
+}
@@ -0,0 +1,444 @@
+use axum::extract::ws::{CloseFrame, Message};
+use serde::Deserialize;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+use crate::data_types::{
+    MESSAGE_TYPE_NOTIFICATION, NcPropertyChangeType, PropertyChangedEvent, WsNotificationMessage,
+};
+
+/// WebSocket close code for a normal closure
+pub const CLOSE_CODE_NORMAL: u16 = 1000;
+/// WebSocket close code for a server closing an idle connection
+pub const CLOSE_CODE_GOING_AWAY: u16 = 1001;
+/// WebSocket close code sent when a connection is dropped for falling behind
+pub const CLOSE_CODE_POLICY_VIOLATION: u16 = 1008;
+/// WebSocket close code telling clients the device is restarting and they may reconnect
+pub const CLOSE_CODE_SERVICE_RESTART: u16 = 1012;
+
+/// Identifies the property of a value change: (oid, level, index)
+pub type CoalesceKey = (u64, u32, u32);
+
+/// Only plain value changes can be superseded by a newer notification
+fn coalesce_key(event: &PropertyChangedEvent) -> Option<CoalesceKey> {
+    match event.event_data.change_type {
+        NcPropertyChangeType::ValueChanged => Some((
+            event.oid,
+            event.event_data.property_id.level,
+            event.event_data.property_id.index,
+        )),
+        _ => None,
+    }
+}
+
+/// What to do when a connection's outbound queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Drop the queued value changes of the properties changed again, otherwise (or when that
+    /// frees no room) drop the oldest notification
+    Coalesce,
+    /// Drop the oldest queued notification, close the connection when only messages which must
+    /// not be lost (e.g. command responses) are queued
+    DropOldest,
+    /// Close the connection
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushOutcome {
+    Queued,
+    Coalesced,
+    DroppedOldest,
+    Disconnected,
+}
+
+/// Notification messages are kept as their notifications until written, so that queued value
+/// changes can be superseded one property at a time
+#[derive(Debug)]
+enum QueuedMessage {
+    Message(Message),
+    Notifications(Vec<PropertyChangedEvent>),
+}
+
+impl QueuedMessage {
+    /// Notifications can be dropped on overflow, anything else is only lost by disconnecting
+    fn droppable(&self) -> bool {
+        matches!(self, QueuedMessage::Notifications(_))
+    }
+
+    fn into_message(self) -> Message {
+        match self {
+            QueuedMessage::Message(message) => message,
+            QueuedMessage::Notifications(notifications) => {
+                let payload = serde_json::to_string(&WsNotificationMessage {
+                    message_type: MESSAGE_TYPE_NOTIFICATION,
+                    notifications,
+                })
+                .unwrap();
+                Message::Text(payload.into())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct QueueInner {
+    items: VecDeque<QueuedMessage>,
+    closed: bool,
+    dropped: u64,
+    coalesced: u64,
+}
+
+/// Bounded per-connection queue of messages waiting to be written to the socket
+#[derive(Debug)]
+pub struct OutboundQueue {
+    inner: Mutex<QueueInner>,
+    notify: Notify,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+impl OutboundQueue {
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        OutboundQueue {
+            inner: Mutex::new(QueueInner::default()),
+            notify: Notify::new(),
+            capacity: capacity.max(1),
+            policy,
+        }
+    }
+
+    /// Queues a message which must not be merged with others (e.g. command responses)
+    pub fn push(&self, message: Message) -> PushOutcome {
+        self.push_entry(QueuedMessage::Message(message))
+    }
+
+    /// Queues a notification message whose value changes may supersede queued ones
+    pub fn push_notification(&self, notifications: Vec<PropertyChangedEvent>) -> PushOutcome {
+        self.push_entry(QueuedMessage::Notifications(notifications))
+    }
+
+    fn push_entry(&self, entry: QueuedMessage) -> PushOutcome {
+        let outcome = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.closed {
+                return PushOutcome::Disconnected;
+            }
+
+            if inner.items.len() < self.capacity {
+                inner.items.push_back(entry);
+                PushOutcome::Queued
+            } else {
+                match self.policy {
+                    OverflowPolicy::Coalesce => {
+                        Self::coalesce_locked(&mut inner, &entry);
+                        if inner.items.len() < self.capacity {
+                            inner.items.push_back(entry);
+                            PushOutcome::Coalesced
+                        } else {
+                            Self::drop_oldest_locked(&mut inner, entry)
+                        }
+                    }
+                    OverflowPolicy::DropOldest => Self::drop_oldest_locked(&mut inner, entry),
+                    OverflowPolicy::Disconnect => {
+                        Self::close_locked(
+                            &mut inner,
+                            CLOSE_CODE_POLICY_VIOLATION,
+                            "Outbound queue overflow",
+                            true,
+                        );
+                        PushOutcome::Disconnected
+                    }
+                }
+            }
+        };
+
+        self.notify.notify_one();
+        outcome
+    }
+
+    /// Removes the queued value changes superseded by the notifications of `entry`, and the
+    /// notification messages left empty
+    fn coalesce_locked(inner: &mut QueueInner, entry: &QueuedMessage) {
+        let QueuedMessage::Notifications(notifications) = entry else {
+            return;
+        };
+        let keys: HashSet<CoalesceKey> = notifications.iter().filter_map(coalesce_key).collect();
+        if keys.is_empty() {
+            return;
+        }
+        let mut coalesced = 0;
+        for queued in inner.items.iter_mut() {
+            if let QueuedMessage::Notifications(queued) = queued {
+                let before = queued.len();
+                queued.retain(|event| !coalesce_key(event).is_some_and(|key| keys.contains(&key)));
+                coalesced += (before - queued.len()) as u64;
+            }
+        }
+        inner
+            .items
+            .retain(|queued| !matches!(queued, QueuedMessage::Notifications(n) if n.is_empty()));
+        inner.coalesced += coalesced;
+    }
+
+    /// Makes room by dropping the oldest notification, disconnecting when there is none
+    fn drop_oldest_locked(inner: &mut QueueInner, entry: QueuedMessage) -> PushOutcome {
+        match inner.items.iter().position(QueuedMessage::droppable) {
+            Some(position) => {
+                inner.items.remove(position);
+                inner.items.push_back(entry);
+                inner.dropped += 1;
+                PushOutcome::DroppedOldest
+            }
+            None => {
+                Self::close_locked(
+                    inner,
+                    CLOSE_CODE_POLICY_VIOLATION,
+                    "Outbound queue overflow",
+                    true,
+                );
+                PushOutcome::Disconnected
+            }
+        }
+    }
+
+    fn close_locked(inner: &mut QueueInner, code: u16, reason: &str, discard_pending: bool) {
+        if inner.closed {
+            return;
+        }
+        if discard_pending {
+            inner.dropped += inner.items.len() as u64;
+            inner.items.clear();
+        }
+        inner
+            .items
+            .push_back(QueuedMessage::Message(Message::Close(Some(CloseFrame {
+                code,
+                reason: reason.into(),
+            }))));
+        inner.closed = true;
+    }
+
+    /// Discards pending messages, sends a close frame and stops accepting new messages
+    pub fn close(&self, code: u16, reason: &str) {
+        self.close_with(code, reason, true);
+    }
+
+    /// Sends a close frame after the pending messages and stops accepting new messages
+    pub fn close_after_pending(&self, code: u16, reason: &str) {
+        self.close_with(code, reason, false);
+    }
+
+    fn close_with(&self, code: u16, reason: &str, discard_pending: bool) {
+        Self::close_locked(
+            &mut self.inner.lock().unwrap(),
+            code,
+            reason,
+            discard_pending,
+        );
+        self.notify.notify_one();
+    }
+
+    /// Waits for the next message; returns None once the queue is closed and drained
+    pub async fn pop(&self) -> Option<Message> {
+        loop {
+            let notified = self.notify.notified();
+            {
+                let mut inner = self.inner.lock().unwrap();
+                if let Some(entry) = inner.items.pop_front() {
+                    return Some(entry.into_message());
+                }
+                if inner.closed {
+                    return None;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.inner.lock().unwrap().dropped
+    }
+
+    pub fn coalesced(&self) -> u64 {
+        self.inner.lock().unwrap().coalesced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_types::{NcElementId, PropertyChangedEventData};
+    use serde_json::{Value, json};
+
+    fn text(message: Option<Message>) -> String {
+        match message {
+            Some(Message::Text(text)) => text.to_string(),
+            other => panic!("expected a text message, got {other:?}"),
+        }
+    }
+
+    fn value_changed(oid: u64, index: u32, value: &str) -> PropertyChangedEvent {
+        PropertyChangedEvent::new(
+            oid,
+            PropertyChangedEventData {
+                property_id: NcElementId { level: 1, index },
+                change_type: NcPropertyChangeType::ValueChanged,
+                value: json!(value),
+                sequence_item_index: None,
+            },
+        )
+    }
+
+    /// Queues a userLabel (1p6) change
+    fn notification(queue: &OutboundQueue, value: &str, oid: u64) -> PushOutcome {
+        queue.push_notification(vec![value_changed(oid, 6, value)])
+    }
+
+    /// The (oid, value) of each notification of a notification message
+    fn notified(message: Option<Message>) -> Vec<(u64, String)> {
+        let message: Value = serde_json::from_str(&text(message)).unwrap();
+        assert_eq!(message["messageType"], json!(MESSAGE_TYPE_NOTIFICATION));
+        message["notifications"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| {
+                let value = n["eventData"]["value"].as_str().unwrap().to_string();
+                (n["oid"].as_u64().unwrap(), value)
+            })
+            .collect()
+    }
+
+    fn response(queue: &OutboundQueue, text: &str) -> PushOutcome {
+        queue.push(Message::Text(text.into()))
+    }
+
+    fn is_closed_with(message: Option<Message>, code: u16) -> bool {
+        matches!(message, Some(Message::Close(Some(frame))) if frame.code == code)
+    }
+
+    #[tokio::test]
+    async fn coalesce_replaces_a_notification_for_the_same_properties() {
+        let queue = OutboundQueue::new(2, OverflowPolicy::Coalesce);
+        assert_eq!(notification(&queue, "a1", 1), PushOutcome::Queued);
+        assert_eq!(response(&queue, "r1"), PushOutcome::Queued);
+        assert_eq!(notification(&queue, "a2", 1), PushOutcome::Coalesced);
+        assert_eq!(queue.coalesced(), 1);
+
+        // A notification for other properties replaces the oldest notification
+        assert_eq!(notification(&queue, "b", 2), PushOutcome::DroppedOldest);
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(text(queue.pop().await), "r1");
+        assert_eq!(notified(queue.pop().await), vec![(2, "b".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn coalesce_drops_superseded_changes_from_overlapping_batches() {
+        let queue = OutboundQueue::new(2, OverflowPolicy::Coalesce);
+        let first = vec![value_changed(1, 6, "a1"), value_changed(2, 6, "b1")];
+        let second = vec![value_changed(2, 6, "b2"), value_changed(3, 6, "c1")];
+        assert_eq!(queue.push_notification(first), PushOutcome::Queued);
+        assert_eq!(queue.push_notification(second), PushOutcome::Queued);
+
+        // Supersedes a1 and c1, the first batch keeps b1 only and the second batch b2 only
+        let third = vec![value_changed(1, 6, "a2"), value_changed(3, 6, "c2")];
+        assert_eq!(queue.push_notification(third), PushOutcome::DroppedOldest);
+        assert_eq!(queue.coalesced(), 2);
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(notified(queue.pop().await), vec![(2, "b2".to_string())]);
+        assert_eq!(
+            notified(queue.pop().await),
+            vec![(1, "a2".to_string()), (3, "c2".to_string())]
+        );
+
+        // A batch left empty makes room for the new one
+        let queue = OutboundQueue::new(2, OverflowPolicy::Coalesce);
+        queue.push_notification(vec![value_changed(1, 6, "a1"), value_changed(1, 7, "x")]);
+        queue.push_notification(vec![value_changed(2, 6, "b1")]);
+        let overlapping = vec![value_changed(2, 6, "b2"), value_changed(1, 7, "y")];
+        assert_eq!(queue.push_notification(overlapping), PushOutcome::Coalesced);
+        assert_eq!(queue.coalesced(), 2);
+        assert_eq!(queue.dropped(), 0);
+        assert_eq!(notified(queue.pop().await), vec![(1, "a1".to_string())]);
+        assert_eq!(
+            notified(queue.pop().await),
+            vec![(2, "b2".to_string()), (1, "y".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn coalesce_disconnects_when_only_responses_are_queued() {
+        let queue = OutboundQueue::new(1, OverflowPolicy::Coalesce);
+        response(&queue, "r1");
+        assert_eq!(notification(&queue, "a", 1), PushOutcome::Disconnected);
+        assert!(is_closed_with(
+            queue.pop().await,
+            CLOSE_CODE_POLICY_VIOLATION
+        ));
+        assert!(queue.pop().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_drops_notifications_but_never_responses() {
+        let queue = OutboundQueue::new(3, OverflowPolicy::DropOldest);
+        response(&queue, "r1");
+        notification(&queue, "a", 1);
+        response(&queue, "r2");
+        assert_eq!(response(&queue, "r3"), PushOutcome::DroppedOldest);
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(text(queue.pop().await), "r1");
+        assert_eq!(text(queue.pop().await), "r2");
+        assert_eq!(text(queue.pop().await), "r3");
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_disconnects_when_only_responses_are_queued() {
+        let queue = OutboundQueue::new(2, OverflowPolicy::DropOldest);
+        response(&queue, "r1");
+        response(&queue, "r2");
+        assert_eq!(response(&queue, "r3"), PushOutcome::Disconnected);
+        assert!(is_closed_with(
+            queue.pop().await,
+            CLOSE_CODE_POLICY_VIOLATION
+        ));
+        assert_eq!(response(&queue, "r4"), PushOutcome::Disconnected);
+    }
+
+    #[tokio::test]
+    async fn disconnect_closes_on_the_first_overflow() {
+        let queue = OutboundQueue::new(1, OverflowPolicy::Disconnect);
+        assert_eq!(notification(&queue, "a", 1), PushOutcome::Queued);
+        assert_eq!(notification(&queue, "a", 1), PushOutcome::Disconnected);
+        assert_eq!(queue.dropped(), 1);
+        assert!(is_closed_with(
+            queue.pop().await,
+            CLOSE_CODE_POLICY_VIOLATION
+        ));
+        assert!(queue.pop().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn close_after_pending_keeps_the_queued_messages() {
+        let queue = OutboundQueue::new(4, OverflowPolicy::Coalesce);
+        response(&queue, "r1");
+        queue.close_after_pending(CLOSE_CODE_NORMAL, "");
+        assert_eq!(text(queue.pop().await), "r1");
+        assert!(is_closed_with(queue.pop().await, CLOSE_CODE_NORMAL));
+        assert!(queue.pop().await.is_none());
+    }
+}
@@ -0,0 +1,310 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{
+    data_types::{
+        IdArgsValue, NcElementId, NcMethodStatus, NcPropertyChangeType, NcResetCause,
+        PropertyChangedEvent,
+    },
+    nc_block::NcBlock,
+    nc_ident_beacon::NcIdentBeacon,
+    nc_object::NcMember,
+    nc_role_path::NcRolePath,
+};
+
+/// Where persisted property values are kept and which ones are left out
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PersistenceConfig {
+    /// JSON file holding the persisted values
+    pub file: PathBuf,
+    /// Properties which are never persisted, as `<role path>/<level>p<index>`
+    /// where the role path may be `*` (e.g. `root.my-worker-01/2p1` or `*/1p6`)
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// Persisted values keyed by dot separated role path, then by property id (e.g. `3p6`)
+type Journal = BTreeMap<String, BTreeMap<String, Value>>;
+
+/// Keeps the values of writable properties across restarts
+pub struct Persistence {
+    config: PersistenceConfig,
+    journal: Mutex<Journal>,
+}
+
+impl Persistence {
+    /// Loads the journal, a missing file means nothing was persisted yet
+    pub fn open(config: PersistenceConfig) -> anyhow::Result<Self> {
+        let journal = match std::fs::read_to_string(&config.file) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| {
+                anyhow::anyhow!("Invalid persistence file {}: {e}", config.file.display())
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Journal::new(),
+            Err(e) => anyhow::bail!("Failed to read {}: {e}", config.file.display()),
+        };
+        Ok(Persistence {
+            config,
+            journal: Mutex::new(journal),
+        })
+    }
+
+    /// Forgets every persisted value so the model keeps its configured defaults
+    pub fn factory_reset(&self) -> anyhow::Result<()> {
+        self.journal.lock().unwrap().clear();
+        match std::fs::remove_file(&self.config.file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the persisted values to the model, dropping entries which no longer apply
+    pub fn restore(&self, root: &mut NcBlock) {
+        let mut journal = self.journal.lock().unwrap();
+        let mut dropped = false;
+
+        for (role_path, properties) in journal.iter_mut() {
+            let Some(oid) = NcRolePath::parse(role_path).and_then(|p| root.resolve_role_path(&p))
+            else {
+                tracing::warn!(role_path, "persisted object no longer exists");
+                properties.clear();
+                dropped = true;
+                continue;
+            };
+
+            properties.retain(|key, value| {
+                let Some(id) = parse_property_key(key) else {
+                    tracing::warn!(role_path, property = key, "invalid persisted property id");
+                    dropped = true;
+                    return false;
+                };
+                if !is_persistable(root, oid, &id) || self.is_excluded(role_path, key) {
+                    dropped = true;
+                    return false;
+                }
+                let (error, status) = root.set_property(
+                    oid,
+                    IdArgsValue {
+                        id,
+                        value: value.clone(),
+                    },
+                );
+                if !matches!(status, NcMethodStatus::Ok) {
+                    tracing::warn!(
+                        role_path,
+                        property = key,
+                        error = error.unwrap_or_default(),
+                        "failed to restore persisted value"
+                    );
+                    dropped = true;
+                    return false;
+                }
+                true
+            });
+        }
+        journal.retain(|_, properties| !properties.is_empty());
+
+        let restored: usize = journal.values().map(|p| p.len()).sum();
+        tracing::info!(restored, "restored persisted property values");
+
+        if dropped && let Err(e) = write_journal(&self.config.file, &journal) {
+            tracing::warn!(error = %e, "failed to write persistence file");
+        }
+    }
+
+    /// Journals value changes of writable properties, returns true when the file needs saving
+    pub fn record(&self, root: &NcBlock, events: &[PropertyChangedEvent]) -> bool {
+        let mut journal = self.journal.lock().unwrap();
+        let mut changed = false;
+
+        for event in events {
+            if !matches!(
+                event.event_data.change_type,
+                NcPropertyChangeType::ValueChanged
+            ) {
+                continue;
+            }
+            let id = &event.event_data.property_id;
+            if !is_persistable(root, event.oid, id) {
+                continue;
+            }
+            let Some(role_path) = root.role_path_of(event.oid).map(|p| p.to_string()) else {
+                continue;
+            };
+            let key = format!("{}p{}", id.level, id.index);
+            if self.is_excluded(&role_path, &key) {
+                continue;
+            }
+
+            let properties = journal.entry(role_path).or_default();
+            if properties.get(&key) != Some(&event.event_data.value) {
+                properties.insert(key, event.event_data.value.clone());
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    /// Writes the journal without blocking the runtime
+    pub async fn save(&self) -> anyhow::Result<()> {
+        let path = self.config.file.clone();
+        let journal = self.journal.lock().unwrap().clone();
+        tokio::task::spawn_blocking(move || write_journal(&path, &journal))
+            .await?
+            .map_err(|e| {
+                tracing::warn!(error = %e, "failed to write persistence file");
+                e.into()
+            })
+    }
+
+    /// Why the device was restarted, taken from the marker left by the previous run. Without a
+    /// marker the device was powered on (or crashed).
+    pub fn take_reset_cause(&self) -> NcResetCause {
+        let path = self.reset_marker_path();
+        let cause = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(text.trim()).unwrap_or_else(|e| {
+                tracing::warn!(marker = %path.display(), error = %e, "invalid reset cause marker");
+                NcResetCause::Unknown
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => NcResetCause::PowerOn,
+            Err(e) => {
+                tracing::warn!(marker = %path.display(), error = %e, "cannot read reset cause marker");
+                NcResetCause::Unknown
+            }
+        };
+        if let Err(e) = std::fs::remove_file(&path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!(marker = %path.display(), error = %e, "cannot remove reset cause marker");
+        }
+        cause
+    }
+
+    /// Leaves the reset cause for the next start, so it is reported even if this process does
+    /// not survive the restart
+    pub fn record_reset_cause(&self, cause: NcResetCause) -> anyhow::Result<()> {
+        let path = self.reset_marker_path();
+        std::fs::write(&path, serde_json::to_string(&cause)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", path.display()))
+    }
+
+    /// `<file>.reset-cause` next to the journal, holding the numeric NcResetCause of the next
+    /// start (e.g. `3` written by an upgrade script)
+    fn reset_marker_path(&self) -> PathBuf {
+        let mut name = self
+            .config
+            .file
+            .file_name()
+            .unwrap_or_default()
+            .to_os_string();
+        name.push(".reset-cause");
+        self.config.file.with_file_name(name)
+    }
+
+    fn is_excluded(&self, role_path: &str, key: &str) -> bool {
+        self.config.exclude.iter().any(|entry| {
+            parse_exclude(entry).is_some_and(|(path, property)| {
+                (path == "*" || path == role_path) && property == key
+            })
+        })
+    }
+}
+
+/// Writes to a temporary file first so a crash never leaves a truncated journal
+fn write_journal(path: &Path, journal: &Journal) -> std::io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let tmp = path.with_file_name(name);
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&serde_json::to_vec_pretty(journal)?)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)
+}
+
+/// Splits an exclude entry into role path and property key, None when malformed
+pub fn parse_exclude(entry: &str) -> Option<(&str, &str)> {
+    let (path, property) = entry.rsplit_once('/')?;
+    (!path.is_empty() && parse_property_key(property).is_some()).then_some((path, property))
+}
+
+/// Parses `3p6` style property ids
+fn parse_property_key(key: &str) -> Option<NcElementId> {
+    let (level, index) = key.split_once('p')?;
+    Some(NcElementId {
+        level: level.parse().ok()?,
+        index: index.parse().ok()?,
+    })
+}
+
+/// Only properties the class descriptors declare writable are persisted
+fn is_persistable(root: &NcBlock, oid: u64, id: &NcElementId) -> bool {
+    let Some(object) = root.find_object(oid) else {
+        return false;
+    };
+    // The identification beacon only stays on for a while, never across restarts
+    if object.as_any().is::<NcIdentBeacon>() && id.level == 3 && id.index == 1 {
+        return false;
+    }
+    root.class_manager()
+        .and_then(|class_manager| class_manager.find_property_descriptor(object.get_class_id(), id))
+        .is_some_and(|descriptor| !descriptor.is_read_only)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Persistence in a directory of its own, removed by the caller
+    fn persistence() -> (Persistence, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("persistence-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let persistence = Persistence::open(PersistenceConfig {
+            file: dir.join("values.json"),
+            exclude: Vec::new(),
+        })
+        .unwrap();
+        (persistence, dir)
+    }
+
+    #[test]
+    fn reset_cause_markers() {
+        let (persistence, dir) = persistence();
+        let marker = dir.join("values.json.reset-cause");
+        // Marker contents, None when the marker is missing, and the cause they give
+        let table = [
+            (None, NcResetCause::PowerOn),
+            (Some("3"), NcResetCause::Upgrade),
+            (Some("4\n"), NcResetCause::ControllerRequest),
+            (Some("garbage"), NcResetCause::Unknown),
+            (Some("42"), NcResetCause::Unknown),
+        ];
+        for (contents, cause) in table {
+            if let Some(contents) = contents {
+                std::fs::write(&marker, contents).unwrap();
+            }
+            assert_eq!(persistence.take_reset_cause(), cause, "{contents:?}");
+            assert!(!marker.exists(), "{contents:?}");
+        }
+
+        persistence
+            .record_reset_cause(NcResetCause::ManualReset)
+            .unwrap();
+        assert_eq!(persistence.take_reset_cause(), NcResetCause::ManualReset);
+        assert_eq!(persistence.take_reset_cause(), NcResetCause::PowerOn);
+
+        // A marker that cannot be read as a file
+        std::fs::create_dir(&marker).unwrap();
+        assert_eq!(persistence.take_reset_cause(), NcResetCause::Unknown);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,236 @@
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::data_types::{
+    MESSAGE_TYPE_COMMAND, MESSAGE_TYPE_COMMAND_RESPONSE, MESSAGE_TYPE_ERROR,
+    MESSAGE_TYPE_NOTIFICATION, MESSAGE_TYPE_SUBSCRIPTION, MESSAGE_TYPE_SUBSCRIPTION_RESPONSE,
+    NcMethodStatus, WsCommandMessage, WsErrorMessage, WsSubscriptionMessage,
+};
+
+/// WebSocket close code for data the endpoint cannot accept (e.g. binary frames)
+pub const CLOSE_CODE_UNSUPPORTED_DATA: u16 = 1003;
+/// WebSocket close code for messages exceeding the configured maximum size
+pub const CLOSE_CODE_MESSAGE_TOO_BIG: u16 = 1009;
+
+/// Lowest and highest command handle allowed by IS-12
+pub const MIN_HANDLE: u64 = 1;
+pub const MAX_HANDLE: u64 = 65535;
+
+/// A client message which passed validation
+#[derive(Debug)]
+pub enum IncomingMessage {
+    Command(WsCommandMessage),
+    Subscription(WsSubscriptionMessage),
+}
+
+/// A protocol violation reported to the client with an IS-12 Error message
+#[derive(Debug)]
+pub struct ProtocolError {
+    pub status: NcMethodStatus,
+    pub error_message: String,
+    /// Close code and reason when the violation also terminates the connection
+    pub close: Option<(u16, &'static str)>,
+}
+
+impl ProtocolError {
+    pub fn bad_format(error_message: impl Into<String>) -> Self {
+        ProtocolError {
+            status: NcMethodStatus::BadCommandFormat,
+            error_message: error_message.into(),
+            close: None,
+        }
+    }
+
+    pub fn message_too_big(size: usize, max_size: usize) -> Self {
+        ProtocolError {
+            status: NcMethodStatus::BufferOverflow,
+            error_message: format!(
+                "Message of {} bytes exceeds the maximum of {} bytes",
+                size, max_size
+            ),
+            close: Some((CLOSE_CODE_MESSAGE_TOO_BIG, "Message too big")),
+        }
+    }
+
+    pub fn binary_not_supported() -> Self {
+        ProtocolError {
+            status: NcMethodStatus::BadCommandFormat,
+            error_message: "Binary messages are not supported".to_string(),
+            close: Some((
+                CLOSE_CODE_UNSUPPORTED_DATA,
+                "Binary messages are not supported",
+            )),
+        }
+    }
+
+    pub fn to_message(&self) -> WsErrorMessage {
+        WsErrorMessage {
+            message_type: MESSAGE_TYPE_ERROR,
+            status: u16::from(self.status.clone()) as u64,
+            error_message: self.error_message.clone(),
+        }
+    }
+}
+
+/// Parses and validates a text message received from a client
+pub fn parse_message(text: &str) -> Result<IncomingMessage, ProtocolError> {
+    let value: Value = serde_json::from_str(text)
+        .map_err(|e| ProtocolError::bad_format(format!("Malformed JSON: {e}")))?;
+
+    let Some(obj) = value.as_object() else {
+        return Err(ProtocolError::bad_format("Message must be a JSON object"));
+    };
+
+    let message_type = match obj.get("messageType") {
+        None => return Err(ProtocolError::bad_format("Missing messageType")),
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| ProtocolError::bad_format(format!("Invalid messageType: {v}")))?,
+    };
+
+    match message_type {
+        t if t == MESSAGE_TYPE_COMMAND as u64 => {
+            let cmd: WsCommandMessage = serde_json::from_value(value)
+                .map_err(|e| ProtocolError::bad_format(format!("Invalid command message: {e}")))?;
+            validate_command_message(&cmd)?;
+            Ok(IncomingMessage::Command(cmd))
+        }
+        t if t == MESSAGE_TYPE_SUBSCRIPTION as u64 => {
+            let sub: WsSubscriptionMessage = serde_json::from_value(value).map_err(|e| {
+                ProtocolError::bad_format(format!("Invalid subscription message: {e}"))
+            })?;
+            Ok(IncomingMessage::Subscription(sub))
+        }
+        t if t == MESSAGE_TYPE_COMMAND_RESPONSE as u64
+            || t == MESSAGE_TYPE_NOTIFICATION as u64
+            || t == MESSAGE_TYPE_SUBSCRIPTION_RESPONSE as u64
+            || t == MESSAGE_TYPE_ERROR as u64 =>
+        {
+            Err(ProtocolError::bad_format(format!(
+                "Unexpected messageType {t}: only Command and Subscription messages can be sent to a device"
+            )))
+        }
+        t => Err(ProtocolError::bad_format(format!(
+            "Unknown messageType {t}"
+        ))),
+    }
+}
+
+fn validate_command_message(cmd: &WsCommandMessage) -> Result<(), ProtocolError> {
+    if cmd.commands.is_empty() {
+        return Err(ProtocolError::bad_format(
+            "Command message does not contain any commands",
+        ));
+    }
+
+    let mut handles = HashSet::new();
+    for command in &cmd.commands {
+        if command.handle < MIN_HANDLE || command.handle > MAX_HANDLE {
+            return Err(ProtocolError::bad_format(format!(
+                "Command handle {} is out of range ({}-{})",
+                command.handle, MIN_HANDLE, MAX_HANDLE
+            )));
+        }
+        if !handles.insert(command.handle) {
+            return Err(ProtocolError::bad_format(format!(
+                "Duplicate command handle {} in message",
+                command.handle
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn command_message(handles: &[u64]) -> String {
+        let commands: Vec<Value> = handles
+            .iter()
+            .map(|handle| {
+                json!({
+                    "handle": handle,
+                    "oid": 1,
+                    "methodId": { "level": 1, "index": 1 },
+                    "arguments": { "id": { "level": 1, "index": 1 } }
+                })
+            })
+            .collect();
+        json!({ "messageType": MESSAGE_TYPE_COMMAND, "commands": commands }).to_string()
+    }
+
+    fn error_message(text: &str) -> String {
+        match parse_message(text) {
+            Err(err) => {
+                assert!(matches!(err.status, NcMethodStatus::BadCommandFormat));
+                assert!(err.close.is_none());
+                err.error_message
+            }
+            Ok(message) => panic!("expected an error, got {message:?}"),
+        }
+    }
+
+    #[test]
+    fn commands_and_subscriptions_are_accepted() {
+        assert!(matches!(
+            parse_message(&command_message(&[1, 65535])),
+            Ok(IncomingMessage::Command(cmd)) if cmd.commands.len() == 2
+        ));
+        let subscription =
+            json!({ "messageType": MESSAGE_TYPE_SUBSCRIPTION, "subscriptions": [1] });
+        assert!(matches!(
+            parse_message(&subscription.to_string()),
+            Ok(IncomingMessage::Subscription(sub)) if sub.subscriptions == Some(vec![1])
+        ));
+    }
+
+    #[test]
+    fn message_types_are_checked() {
+        assert!(error_message("[]").contains("JSON object"));
+        assert!(error_message("{").contains("Malformed JSON"));
+        assert!(error_message("{}").contains("Missing messageType"));
+        assert!(error_message(r#"{"messageType": "0"}"#).contains("Invalid messageType"));
+        assert!(error_message(r#"{"messageType": 9}"#).contains("Unknown messageType 9"));
+        for message_type in [
+            MESSAGE_TYPE_COMMAND_RESPONSE,
+            MESSAGE_TYPE_NOTIFICATION,
+            MESSAGE_TYPE_SUBSCRIPTION_RESPONSE,
+            MESSAGE_TYPE_ERROR,
+        ] {
+            let text = json!({ "messageType": message_type }).to_string();
+            assert!(error_message(&text).contains("Unexpected messageType"));
+        }
+        let text = json!({ "messageType": MESSAGE_TYPE_COMMAND }).to_string();
+        assert!(error_message(&text).contains("Invalid command message"));
+    }
+
+    #[test]
+    fn command_handles_are_checked() {
+        assert!(error_message(&command_message(&[])).contains("does not contain any commands"));
+        assert!(error_message(&command_message(&[0])).contains("out of range"));
+        assert!(error_message(&command_message(&[65536])).contains("out of range"));
+        assert!(error_message(&command_message(&[7, 8, 7])).contains("Duplicate command handle 7"));
+    }
+
+    #[test]
+    fn size_and_binary_errors_close_the_connection() {
+        let err = ProtocolError::message_too_big(2048, 1024);
+        assert!(matches!(err.status, NcMethodStatus::BufferOverflow));
+        assert_eq!(
+            err.close.map(|(code, _)| code),
+            Some(CLOSE_CODE_MESSAGE_TOO_BIG)
+        );
+        let message = err.to_message();
+        assert_eq!(message.status, 413);
+        assert_eq!(message.message_type, MESSAGE_TYPE_ERROR);
+
+        let err = ProtocolError::binary_not_supported();
+        assert_eq!(
+            err.close.map(|(code, _)| code),
+            Some(CLOSE_CODE_UNSUPPORTED_DATA)
+        );
+    }
+}
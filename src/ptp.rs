@@ -0,0 +1,228 @@
+use itertools::Itertools;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    AppState,
+    config::duration_from_secs,
+    data_types::{NmosClock, NmosClockReference},
+};
+
+/// Advertised while the PTP source cannot be read
+const UNKNOWN_GMID: &str = "00-00-00-00-00-00-00-00";
+
+/// Where the state of the PTP clock advertised by the node is read from, exactly one of
+/// `pmc_socket` and `file` is set
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PtpConfig {
+    /// Name of the clock in the node resource
+    #[serde(default = "default_clock_name")]
+    pub clock: String,
+    /// ptp4l management socket (e.g. `/var/run/ptp4l`), queried with the linuxptp `pmc` tool
+    pub pmc_socket: Option<PathBuf>,
+    /// JSON file like `{"gmid": "08-00-11-ff-fe-21-e1-b0", "locked": true, "traceable": false}`
+    /// kept up to date by another process
+    pub file: Option<PathBuf>,
+    /// How often the clock state is read again, a change gives the node resource a new version
+    #[serde(
+        rename = "poll_interval_secs",
+        default = "default_poll_interval",
+        deserialize_with = "duration_from_secs"
+    )]
+    pub poll_interval: Duration,
+}
+
+fn default_clock_name() -> String {
+    "clk1".to_string()
+}
+
+fn default_poll_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+#[derive(Debug, Deserialize)]
+struct PtpStatus {
+    gmid: String,
+    locked: bool,
+    #[serde(default)]
+    traceable: bool,
+}
+
+impl PtpConfig {
+    /// Describes the PTP clock, unlocked with an unknown grandmaster when the source fails
+    pub async fn read_clock(&self) -> NmosClock {
+        let status = match (&self.pmc_socket, &self.file) {
+            (Some(socket), _) => query_pmc(socket).await,
+            (None, Some(file)) => read_status_file(file),
+            (None, None) => Err(anyhow::anyhow!("no PTP source configured")),
+        };
+        let status = status.unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "cannot read the PTP clock state");
+            PtpStatus {
+                gmid: UNKNOWN_GMID.to_string(),
+                locked: false,
+                traceable: false,
+            }
+        });
+        NmosClock {
+            name: self.clock.clone(),
+            reference: NmosClockReference::Ptp {
+                traceable: status.traceable,
+                version: "IEEE1588-2008".to_string(),
+                gmid: status.gmid,
+                locked: status.locked,
+            },
+        }
+    }
+}
+
+/// Reads the PTP clock at its poll interval and updates the node resource when the grandmaster,
+/// lock or traceability changed
+pub async fn track_clock(state: Arc<AppState>, ptp: PtpConfig) {
+    let mut current = state
+        .resources
+        .read()
+        .await
+        .node()
+        .clocks
+        .iter()
+        .find(|clock| clock.name == ptp.clock)
+        .cloned();
+    let mut interval = tokio::time::interval(ptp.poll_interval);
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let clock = ptp.read_clock().await;
+        if current.as_ref() == Some(&clock) {
+            continue;
+        }
+        tracing::info!(clock = ptp.clock, reference = ?clock.reference, "PTP clock changed");
+        state.resources.write().await.update_node(|node| {
+            match node.clocks.iter_mut().find(|c| c.name == clock.name) {
+                Some(advertised) => *advertised = clock.clone(),
+                None => node.clocks.push(clock.clone()),
+            }
+        });
+        current = Some(clock);
+    }
+}
+
+async fn query_pmc(socket: &Path) -> anyhow::Result<PtpStatus> {
+    let output = tokio::process::Command::new("pmc")
+        .args(["-u", "-b", "0", "-s"])
+        .arg(socket)
+        .args(["GET TIME_STATUS_NP", "GET TIME_PROPERTIES_DATA_SET"])
+        .output()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to run pmc: {e}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "pmc failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_pmc_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Picks `gmIdentity`, `gmPresent` and `timeTraceable` out of the pmc responses
+fn parse_pmc_output(text: &str) -> anyhow::Result<PtpStatus> {
+    let field = |name: &str| {
+        text.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            (parts.next()? == name).then(|| parts.next()).flatten()
+        })
+    };
+    let identity = field("gmIdentity")
+        .ok_or_else(|| anyhow::anyhow!("pmc did not report a grandmaster identity"))?;
+    Ok(PtpStatus {
+        gmid: clock_identity_to_gmid(identity)?,
+        locked: field("gmPresent") == Some("true"),
+        traceable: field("timeTraceable") == Some("1"),
+    })
+}
+
+fn read_status_file(path: &Path) -> anyhow::Result<PtpStatus> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+    let status: PtpStatus = serde_json::from_str(&text)
+        .map_err(|e| anyhow::anyhow!("Invalid PTP status file {}: {e}", path.display()))?;
+    if !is_gmid(&status.gmid) {
+        anyhow::bail!(
+            "Invalid gmid {:?} in {}, expected 08-00-11-ff-fe-21-e1-b0 style",
+            status.gmid,
+            path.display()
+        );
+    }
+    Ok(status)
+}
+
+/// Converts a linuxptp clock identity (`001122.fffe.334455`) to the IS-04 form
+fn clock_identity_to_gmid(identity: &str) -> anyhow::Result<String> {
+    let hex = identity.replace('.', "").to_lowercase();
+    if hex.len() != 16 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("Invalid clock identity {identity:?}");
+    }
+    Ok(hex
+        .as_bytes()
+        .chunks(2)
+        .map(String::from_utf8_lossy)
+        .join("-"))
+}
+
+/// IS-04 grandmaster identities are eight dash separated pairs of lowercase hex digits
+fn is_gmid(gmid: &str) -> bool {
+    gmid.len() == 23
+        && gmid.split('-').count() == 8
+        && gmid.split('-').all(|byte| {
+            byte.len() == 2
+                && byte
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gmids_are_lowercase_hex_pairs() {
+        assert!(is_gmid("08-00-11-ff-fe-21-e1-b0"));
+        for invalid in [
+            "08-00-11-FF-FE-21-E1-B0",
+            "08-00-11-ff-fe-21-e1",
+            "08:00:11:ff:fe:21:e1:b0",
+            "08-00-11-ff-fe-21-e1-bg",
+            "080-0-11-ff-fe-21-e1-b0",
+        ] {
+            assert!(!is_gmid(invalid), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn pmc_output_gives_the_grandmaster_state() {
+        let output = "\
+sending: GET TIME_STATUS_NP
+\t001122.fffe.334455-0 seq 0 RESPONSE MANAGEMENT TIME_STATUS_NP
+\t\tmaster_offset              12
+\t\tgmPresent                  true
+\t\tgmIdentity                 08000B.FFFE.21E1B0
+sending: GET TIME_PROPERTIES_DATA_SET
+\t001122.fffe.334455-0 seq 1 RESPONSE MANAGEMENT TIME_PROPERTIES_DATA_SET
+\t\ttimeTraceable         1
+";
+        let status = parse_pmc_output(output).unwrap();
+        assert_eq!(status.gmid, "08-00-0b-ff-fe-21-e1-b0");
+        assert!(status.locked);
+        assert!(status.traceable);
+
+        assert!(parse_pmc_output("sending: GET TIME_STATUS_NP").is_err());
+        assert!(clock_identity_to_gmid("0800.fffe.21e1b0").is_err());
+    }
+}
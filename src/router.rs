@@ -0,0 +1,345 @@
+use axum::{Router, middleware, routing::get};
+use std::sync::Arc;
+
+use crate::{
+    AppState, channel_mapping, configuration,
+    node_api::{self, remove_trailing_slash_handler},
+    websocket::{connections_diagnostics_handler, diagnostics_auth_middleware, websocket_handler},
+};
+
+/// Routes of the Node, Configuration and Channel Mapping APIs, the IS-12 WebSocket at `/ws` and,
+/// when enabled, the connection diagnostics, each API behind its authorization middleware
+pub fn build_router(state: Arc<AppState>) -> Router {
+    // IS-14 Configuration API routes
+    let configuration_api = Router::new()
+        .route(
+            "/x-nmos/configuration/v1.0",
+            get(configuration::base_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/",
+            get(configuration::base_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths",
+            get(configuration::role_paths_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/",
+            get(configuration::role_paths_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}",
+            get(configuration::role_path_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/",
+            get(configuration::role_path_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/descriptor",
+            get(configuration::descriptor_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/properties",
+            get(configuration::properties_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/properties/",
+            get(configuration::properties_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/properties/{property_id}",
+            get(configuration::property_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/properties/{property_id}/",
+            get(configuration::property_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/properties/{property_id}/descriptor",
+            get(configuration::property_descriptor_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/properties/{property_id}/value",
+            get(configuration::get_property_value_handler)
+                .put(configuration::set_property_value_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/methods",
+            get(configuration::methods_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/methods/",
+            get(configuration::methods_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/methods/{method_id}",
+            get(configuration::method_handler).post(configuration::invoke_method_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/methods/{method_id}/",
+            get(configuration::method_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/methods/{method_id}/descriptor",
+            get(configuration::method_descriptor_handler),
+        )
+        .route(
+            "/x-nmos/configuration/v1.0/rolePaths/{role_path}/bulkProperties",
+            get(configuration::get_bulk_properties_handler)
+                .put(configuration::set_bulk_properties_handler)
+                .patch(configuration::validate_bulk_properties_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            configuration::configuration_auth_middleware,
+        ));
+
+    // IS-08 Channel Mapping API routes
+    let channel_mapping_api = Router::new()
+        .route(
+            "/x-nmos/channelmapping/v1.0",
+            get(channel_mapping::base_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/",
+            get(channel_mapping::base_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/inputs",
+            get(channel_mapping::inputs_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/inputs/",
+            get(channel_mapping::inputs_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/inputs/{id}",
+            get(channel_mapping::input_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/inputs/{id}/",
+            get(channel_mapping::input_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/inputs/{id}/{attribute}",
+            get(channel_mapping::input_attribute_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/inputs/{id}/{attribute}/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/outputs",
+            get(channel_mapping::outputs_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/outputs/",
+            get(channel_mapping::outputs_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/outputs/{id}",
+            get(channel_mapping::output_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/outputs/{id}/",
+            get(channel_mapping::output_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/outputs/{id}/{attribute}",
+            get(channel_mapping::output_attribute_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/outputs/{id}/{attribute}/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/io",
+            get(channel_mapping::io_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/io/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/map",
+            get(channel_mapping::map_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/map/",
+            get(channel_mapping::map_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/map/active",
+            get(channel_mapping::active_map_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/map/active/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/map/activations",
+            get(channel_mapping::activations_handler).post(channel_mapping::activate_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/map/activations/",
+            get(channel_mapping::activations_handler).post(channel_mapping::activate_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/v1.0/map/activations/{id}",
+            get(channel_mapping::activation_handler)
+                .delete(channel_mapping::delete_activation_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            channel_mapping::channel_mapping_auth_middleware,
+        ));
+
+    // Routes
+    let router = Router::new()
+        .route(
+            "/x-nmos/node/{version}",
+            get(node_api::base_is_04_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/",
+            get(node_api::base_is_04_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/self",
+            get(node_api::node_self_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/self/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/sources",
+            get(node_api::sources_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/sources/",
+            get(node_api::sources_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/sources/{id}",
+            get(node_api::source_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/sources/{id}/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/flows",
+            get(node_api::flows_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/flows/",
+            get(node_api::flows_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/flows/{id}",
+            get(node_api::flow_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/flows/{id}/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/devices",
+            get(node_api::devices_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/devices/",
+            get(node_api::devices_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/devices/{id}",
+            get(node_api::device_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/devices/{id}/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/senders",
+            get(node_api::senders_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/senders/",
+            get(node_api::senders_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/senders/{id}",
+            get(node_api::sender_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/senders/{id}/",
+            get(remove_trailing_slash_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/receivers",
+            get(node_api::receivers_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/receivers/",
+            get(node_api::receivers_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/receivers/{id}",
+            get(node_api::receiver_rest_api_handler),
+        )
+        .route(
+            "/x-nmos/node/{version}/receivers/{id}/",
+            get(remove_trailing_slash_handler),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            node_api::node_auth_middleware,
+        ))
+        .merge(configuration_api)
+        .merge(channel_mapping_api)
+        .route("/", get(node_api::root_listing_handler))
+        .route("/x-nmos", get(node_api::x_nmos_listing_handler))
+        .route("/x-nmos/", get(node_api::x_nmos_listing_handler))
+        .route("/x-nmos/node", get(node_api::node_versions_listing_handler))
+        .route(
+            "/x-nmos/node/",
+            get(node_api::node_versions_listing_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping",
+            get(channel_mapping::versions_listing_handler),
+        )
+        .route(
+            "/x-nmos/channelmapping/",
+            get(channel_mapping::versions_listing_handler),
+        )
+        .route(
+            "/x-nmos/configuration",
+            get(configuration::versions_listing_handler),
+        )
+        .route(
+            "/x-nmos/configuration/",
+            get(configuration::versions_listing_handler),
+        )
+        .route("/ws", get(websocket_handler));
+
+    let router = match state.ws_config.diagnostics {
+        true => router.merge(
+            Router::new()
+                .route(
+                    "/diagnostics/connections",
+                    get(connections_diagnostics_handler),
+                )
+                .route_layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    diagnostics_auth_middleware,
+                )),
+        ),
+        false => router,
+    };
+
+    router.with_state(state)
+}
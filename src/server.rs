@@ -0,0 +1,357 @@
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use gethostname::gethostname;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tokio::{
+    net::TcpListener,
+    sync::{Mutex, RwLock, Semaphore, mpsc},
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    advertisement::{AdvertisedHosts, Advertisement, refresh_advertisement},
+    auth::Authorizer,
+    channel_mapping::{ChannelMapping, build_default_channel_mapping},
+    config::Config,
+    data_types::{
+        NcDeviceGenericState, NcResetCause, NmosApi, NmosDevice, NmosNode, PropertyChangedEvent,
+    },
+    model::{build_default_resources, build_model, prepare_model},
+    model_builder::ModelBuilder,
+    nc_block::NcBlock,
+    node_api_versions::NODE_API_VERSIONS,
+    node_resources::NodeResources,
+    persistence::Persistence,
+    ptp::track_clock,
+    router::build_router,
+    tai,
+    tls::{TlsConfig, watch_certificates},
+    websocket::{WebSocketConfig, run_event_loop},
+};
+
+/// Builds the model of the device, called at startup and again whenever a controller resets the
+/// device. Every object of the model must be given the notifier.
+pub type ModelFactory = Arc<
+    dyn Fn(
+            &Config,
+            &ChannelMapping,
+            mpsc::UnboundedSender<PropertyChangedEvent>,
+        ) -> anyhow::Result<NcBlock>
+        + Send
+        + Sync,
+>;
+
+type ResourcesSetup = Box<dyn FnOnce(&mut NodeResources, &mut ChannelMapping) + Send>;
+
+/// Assembles a device from its configuration: by default the model comes from the configured
+/// model file, or is the built-in example model with its example resources
+pub struct DeviceServerBuilder {
+    config: Config,
+    factory_reset: bool,
+    model_factory: Option<ModelFactory>,
+    resources: Option<ResourcesSetup>,
+}
+
+impl DeviceServerBuilder {
+    pub fn new(config: Config) -> Self {
+        DeviceServerBuilder {
+            config,
+            factory_reset: false,
+            model_factory: None,
+            resources: None,
+        }
+    }
+
+    /// Discards the persisted property values instead of restoring them
+    pub fn factory_reset(mut self, factory_reset: bool) -> Self {
+        self.factory_reset = factory_reset;
+        self
+    }
+
+    /// Builds the model with `factory` instead of the model file or the built-in example model
+    pub fn model<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(
+                &Config,
+                &ChannelMapping,
+                mpsc::UnboundedSender<PropertyChangedEvent>,
+            ) -> anyhow::Result<NcBlock>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.model_factory = Some(Arc::new(factory));
+        self
+    }
+
+    /// Builds the model from `model` instead of the model file or the built-in example model
+    pub fn model_builder(self, model: ModelBuilder) -> Self {
+        self.model(move |config, _, tx| Ok(model.build(&config.device, tx)?))
+    }
+
+    /// Keep-alive, limits and queueing of the WebSocket connections instead of the `[websocket]`
+    /// section of the configuration
+    pub fn websocket(mut self, websocket: WebSocketConfig) -> Self {
+        self.config.websocket = websocket;
+        self
+    }
+
+    /// Registers the IS-04 sources, flows, senders and receivers and the IS-08 inputs and outputs
+    /// of the device, before the model is built
+    pub fn resources<F>(mut self, setup: F) -> Self
+    where
+        F: FnOnce(&mut NodeResources, &mut ChannelMapping) + Send + 'static,
+    {
+        self.resources = Some(Box::new(setup));
+        self
+    }
+
+    /// Binds the listener and builds the node, the device and the model, restoring persisted
+    /// values. Nothing is served until [`DeviceServer::serve`].
+    pub async fn build(self) -> anyhow::Result<DeviceServer> {
+        let config = self.config;
+        tai::init(&config.time)?;
+        let hostname = gethostname();
+
+        // Bound before anything is advertised so the hrefs carry the port actually listened on
+        let listener =
+            TcpListener::bind(SocketAddr::new(config.server.bind, config.server.port)).await?;
+        let local_addr = listener.local_addr()?;
+
+        // TLS setup
+        let tls = match config.tls.clone() {
+            Some(tls_config) => {
+                let rustls_config = tls_config.load().await?;
+                Some((tls_config, rustls_config))
+            }
+            None => None,
+        };
+
+        // Interfaces and addresses of the host
+        let advertisement = Advertisement {
+            local_addr,
+            tls: tls.is_some(),
+            authorization: config.auth.is_some(),
+            advertised_host: config.server.advertised_host.clone(),
+            configured_interfaces: config.node.interfaces.clone(),
+        };
+        let hosts = advertisement.discover();
+
+        // Authorization setup
+        let auth = match config.auth.clone() {
+            Some(mut auth_config) => {
+                if auth_config.audience.is_empty() {
+                    auth_config.audience = vec![hosts.hosts[0].clone()];
+                }
+                Some(Authorizer::new(auth_config)?)
+            }
+            None => None,
+        };
+        if auth.is_some() {
+            tracing::info!("authorization enabled");
+        }
+
+        let mut clocks = config.node.clocks.clone();
+        if let Some(ptp) = &config.node.ptp {
+            clocks.push(ptp.read_clock().await);
+        }
+
+        // Create node
+        let node = NmosNode::new(
+            config
+                .node
+                .id
+                .clone()
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            config.node.label.clone(),
+            config.node.description.clone(),
+            tai::next_version(),
+            HashMap::new(),
+            advertisement.node_href(&hosts),
+            hostname.to_string_lossy().into(),
+            clocks,
+            hosts.interfaces.clone(),
+            NmosApi {
+                endpoints: advertisement.endpoints(&hosts),
+                versions: NODE_API_VERSIONS.iter().map(|v| v.to_string()).collect(),
+            },
+        );
+
+        // Create device
+        let device = NmosDevice::new(
+            config.device.id.clone(),
+            config.device.label.clone(),
+            config.device.description.clone(),
+            tai::next_version(),
+            HashMap::new(),
+            vec![],
+            vec![],
+            node.base.id.clone(),
+            "urn:x-nmos:device:generic".into(),
+            advertisement.device_controls(&hosts),
+        );
+
+        // Model setup, the examples only go with the built-in model
+        let (tx, mut rx) = mpsc::unbounded_channel::<PropertyChangedEvent>();
+        let mut resources = NodeResources::new(node, device);
+        let mut channel_mapping = ChannelMapping::default();
+        match self.resources {
+            Some(setup) => setup(&mut resources, &mut channel_mapping),
+            None if self.model_factory.is_none() && config.model_file.is_none() => {
+                build_default_resources(&mut resources);
+                build_default_channel_mapping(&mut channel_mapping, &resources);
+            }
+            None => {}
+        }
+        let model_factory = self.model_factory.unwrap_or_else(|| Arc::new(build_model));
+        let mut root = model_factory(&config, &channel_mapping, tx.clone())?;
+
+        // Restore persisted values before serving
+        let persistence = match config.persistence.clone() {
+            Some(persistence_config) => {
+                let persistence = Persistence::open(persistence_config)?;
+                if self.factory_reset {
+                    persistence.factory_reset()?;
+                    tracing::info!("factory reset: discarded persisted property values");
+                }
+                Some(persistence)
+            }
+            None => None,
+        };
+        let reset_cause = persistence
+            .as_ref()
+            .map_or(NcResetCause::PowerOn, Persistence::take_reset_cause);
+        prepare_model(
+            &mut root,
+            &mut resources,
+            &channel_mapping,
+            persistence.as_ref(),
+            reset_cause,
+        );
+        // Initial values of the model file and restored values are neither changes to persist
+        // nor to notify
+        while rx.try_recv().is_ok() {}
+
+        let state = Arc::new(AppState {
+            resources: RwLock::new(resources),
+            connections: RwLock::new(HashMap::new()),
+            connection_slots: config
+                .websocket
+                .max_connections
+                .map(|max| Arc::new(Semaphore::new(max.min(Semaphore::MAX_PERMITS)))),
+            root_block: Mutex::new(root),
+            channel_mapping: RwLock::new(channel_mapping),
+            event_tx: tx,
+            model_factory,
+            event_rx: Mutex::new(rx),
+            ws_config: config.websocket.clone(),
+            auth,
+            persistence,
+            config,
+        });
+
+        Ok(DeviceServer {
+            state,
+            listener,
+            tls,
+            advertisement,
+            hosts,
+            started: false,
+        })
+    }
+}
+
+/// A device ready to be served on its bound listener
+pub struct DeviceServer {
+    state: Arc<AppState>,
+    listener: TcpListener,
+    tls: Option<(TlsConfig, RustlsConfig)>,
+    advertisement: Advertisement,
+    hosts: AdvertisedHosts,
+    started: bool,
+}
+
+impl DeviceServer {
+    pub fn builder(config: Config) -> DeviceServerBuilder {
+        DeviceServerBuilder::new(config)
+    }
+
+    pub fn state(&self) -> &Arc<AppState> {
+        &self.state
+    }
+
+    /// Address the listener is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.advertisement.local_addr
+    }
+
+    /// Starts the event loop sending notifications and persisting changes and the address
+    /// refresh, then reports normal operation. [`DeviceServer::serve`] does it itself, call it
+    /// before serving [`DeviceServer::router`] on your own.
+    pub async fn start(&mut self) -> anyhow::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+
+        // Event loop background task
+        tokio::spawn(run_event_loop(self.state.clone()));
+        tokio::spawn(refresh_advertisement(
+            self.state.clone(),
+            self.advertisement.clone(),
+            self.hosts.clone(),
+            self.state.config.server.address_refresh_interval,
+        ));
+        if let Some(ptp) = &self.state.config.node.ptp {
+            tokio::spawn(track_clock(self.state.clone(), ptp.clone()));
+        }
+
+        // Initialization is over once the model is restored and the listener bound
+        if let Some(device_manager) = self.state.root_block.lock().await.device_manager_mut() {
+            device_manager.set_operational_state(NcDeviceGenericState::NormalOperation, None)?;
+        }
+        Ok(())
+    }
+
+    /// All routes of the device, to serve them with an application of your own once
+    /// [`DeviceServer::start`] was called. The advertised hrefs carry the address of the listener
+    /// bound by the builder, and remote addresses are only logged when serving with
+    /// `into_make_service_with_connect_info::<SocketAddr>()`.
+    pub fn router(&self) -> Router {
+        build_router(self.state.clone())
+    }
+
+    /// Starts the background tasks, reports normal operation and serves until the server fails
+    pub async fn serve(mut self) -> anyhow::Result<()> {
+        self.start().await?;
+        let DeviceServer {
+            state,
+            listener,
+            tls,
+            advertisement,
+            ..
+        } = self;
+
+        let app = build_router(state);
+        let local_addr = advertisement.local_addr;
+        match tls {
+            Some((config, rustls_config)) => {
+                tokio::spawn(watch_certificates(config, rustls_config.clone()));
+                tracing::info!("listening on {} (TLS)", local_addr);
+                axum_server::from_tcp_rustls(listener.into_std()?, rustls_config)?
+                    .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                    .await?;
+            }
+            None => {
+                tracing::info!("listening on {}", local_addr);
+                axum::serve(
+                    listener,
+                    app.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
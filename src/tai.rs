@@ -0,0 +1,281 @@
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// TAI-UTC since 1 January 2017, used when no better source is available
+const FIXED_OFFSET: u64 = 37;
+
+/// Seconds between the NTP epoch (1900) used by leap second tables and the Unix epoch
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Where TAI is obtained from for IS-04 versions and timestamps
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaiConfig {
+    pub source: TaiSource,
+    /// IERS/NIST `leap-seconds.list` style table
+    pub leap_seconds_file: PathBuf,
+}
+
+impl Default for TaiConfig {
+    fn default() -> Self {
+        TaiConfig {
+            source: TaiSource::Auto,
+            leap_seconds_file: PathBuf::from("/usr/share/zoneinfo/leap-seconds.list"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaiSource {
+    /// The kernel when its TAI offset is set, else the leap second table, else the fixed offset
+    Auto,
+    /// `CLOCK_TAI`, requires the kernel TAI offset to be set (e.g. by ptp4l, chrony or ntpd).
+    /// Linux only, `auto` falls back to the leap second table elsewhere.
+    Kernel,
+    /// The system clock plus the offset from the leap second table
+    LeapSecondsFile,
+    /// The system clock plus 37 seconds
+    Fixed,
+}
+
+/// Unix time each TAI-UTC offset applies from, oldest first
+type LeapSeconds = Vec<(u64, u64)>;
+
+enum TaiClock {
+    Kernel,
+    LeapSeconds(LeapSeconds),
+    Fixed,
+}
+
+static CLOCK: OnceLock<TaiClock> = OnceLock::new();
+
+/// Latest version handed out, versions keep increasing even if the clock steps back
+static LAST_VERSION: Mutex<Duration> = Mutex::new(Duration::ZERO);
+
+/// Selects the TAI source, called at startup before any version is generated. Without it the
+/// fixed offset is used, later calls keep the source selected first.
+pub fn init(config: &TaiConfig) -> anyhow::Result<()> {
+    if CLOCK.get().is_some() {
+        return Ok(());
+    }
+    let clock = match config.source {
+        TaiSource::Kernel => {
+            kernel_tai_offset()?;
+            TaiClock::Kernel
+        }
+        TaiSource::LeapSecondsFile => {
+            TaiClock::LeapSeconds(read_leap_seconds(&config.leap_seconds_file)?)
+        }
+        TaiSource::Fixed => TaiClock::Fixed,
+        TaiSource::Auto => match kernel_tai_offset() {
+            Ok(_) => TaiClock::Kernel,
+            Err(kernel_error) => match read_leap_seconds(&config.leap_seconds_file) {
+                Ok(table) => TaiClock::LeapSeconds(table),
+                Err(file_error) => {
+                    tracing::warn!(
+                        %kernel_error,
+                        %file_error,
+                        "no TAI source available, assuming TAI-UTC is {FIXED_OFFSET}s"
+                    );
+                    TaiClock::Fixed
+                }
+            },
+        },
+    };
+    let name = match &clock {
+        TaiClock::Kernel => "CLOCK_TAI",
+        TaiClock::LeapSeconds(_) => "leap second table",
+        TaiClock::Fixed => "fixed offset",
+    };
+    if CLOCK.set(clock).is_err() {
+        return Ok(());
+    }
+    let tai_utc = now().saturating_sub(utc_now()).as_secs_f64().round() as u64;
+    tracing::info!(source = name, tai_utc, "TAI source selected");
+    Ok(())
+}
+
+/// Time elapsed since the TAI epoch
+pub fn now() -> Duration {
+    match CLOCK.get().unwrap_or(&TaiClock::Fixed) {
+        TaiClock::Kernel => clock_tai().unwrap_or_else(|| utc_now() + fixed_offset()),
+        TaiClock::LeapSeconds(table) => {
+            let utc = utc_now();
+            let offset = table
+                .iter()
+                .rev()
+                .find(|(from, _)| *from <= utc.as_secs())
+                .map_or(FIXED_OFFSET, |(_, offset)| *offset);
+            utc + Duration::from_secs(offset)
+        }
+        TaiClock::Fixed => utc_now() + fixed_offset(),
+    }
+}
+
+/// A TAI timestamp in `<seconds>:<nanoseconds>` format, later than every one returned before
+/// so it can be used as an IS-04 version
+pub fn next_version() -> String {
+    let mut last = LAST_VERSION.lock().unwrap_or_else(|e| e.into_inner());
+    *last = now().max(*last + Duration::from_nanos(1));
+    format!("{}:{}", last.as_secs(), last.subsec_nanos())
+}
+
+fn utc_now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time before Unix epoch")
+}
+
+fn fixed_offset() -> Duration {
+    Duration::from_secs(FIXED_OFFSET)
+}
+
+#[cfg(target_os = "linux")]
+fn clock_tai() -> Option<Duration> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: clock_gettime only writes to the timespec it is given
+    let result = unsafe { libc::clock_gettime(libc::CLOCK_TAI, &mut ts) };
+    (result == 0).then(|| Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn clock_tai() -> Option<Duration> {
+    None
+}
+
+/// The TAI-UTC offset the kernel applies to `CLOCK_TAI`, an error while it is not set
+#[cfg(target_os = "linux")]
+fn kernel_tai_offset() -> anyhow::Result<i64> {
+    // SAFETY: timex is plain data, all zero means a read-only query
+    let mut timex: libc::timex = unsafe { std::mem::zeroed() };
+    // SAFETY: adjtimex only reads and writes the timex it is given
+    if unsafe { libc::adjtimex(&mut timex) } < 0 {
+        anyhow::bail!(
+            "Failed to read the kernel time state: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    if timex.tai <= 0 {
+        anyhow::bail!("The kernel TAI offset is not set");
+    }
+    Ok(timex.tai.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kernel_tai_offset() -> anyhow::Result<i64> {
+    anyhow::bail!("CLOCK_TAI is only available on Linux")
+}
+
+/// Reads a leap second table, warning when it has expired as a leap second announced since
+/// would be missing
+fn read_leap_seconds(path: &Path) -> anyhow::Result<LeapSeconds> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", path.display()))?;
+    let (table, expires) =
+        parse_leap_seconds(&text).map_err(|e| anyhow::anyhow!("{e} in {}", path.display()))?;
+
+    if let Some(expires) = expires
+        && expires < utc_now().as_secs()
+    {
+        tracing::warn!(
+            file = %path.display(),
+            "leap second table has expired, update it to pick up new leap seconds"
+        );
+    }
+    Ok(table)
+}
+
+/// Parses the `<NTP seconds> <TAI-UTC>` entries of a leap second table into Unix times, oldest
+/// first, plus the Unix time of the `#@` expiry line
+fn parse_leap_seconds(text: &str) -> anyhow::Result<(LeapSeconds, Option<u64>)> {
+    let invalid = |line: &str| anyhow::anyhow!("Invalid line {line:?}");
+
+    let mut table = Vec::new();
+    let mut expires = None;
+    for line in text.lines() {
+        if let Some(expiry) = line.strip_prefix("#@") {
+            let ntp: u64 = expiry.trim().parse().map_err(|_| invalid(line))?;
+            expires = Some(ntp.saturating_sub(NTP_UNIX_OFFSET));
+            continue;
+        }
+        let data = line.split('#').next().unwrap_or_default();
+        let mut fields = data.split_whitespace();
+        let (Some(ntp), Some(offset)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let ntp: u64 = ntp.parse().map_err(|_| invalid(line))?;
+        let offset: u64 = offset.parse().map_err(|_| invalid(line))?;
+        table.push((ntp.saturating_sub(NTP_UNIX_OFFSET), offset));
+    }
+    if table.is_empty() {
+        anyhow::bail!("No leap seconds");
+    }
+    table.sort_unstable();
+    Ok((table, expires))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leap_second_tables_are_parsed() {
+        let text = "\
+#	Updated through IERS Bulletin C 69
+#$	 3913697179
+#@	3960057600
+#
+2272060800	10	# 1 Jan 1972
+2287785600	11	# 1 Jul 1972
+3692217600	37	# 1 Jan 2017
+";
+        let (table, expires) = parse_leap_seconds(text).unwrap();
+        assert_eq!(
+            table,
+            [(63_072_000, 10), (78_796_800, 11), (1_483_228_800, 37)]
+        );
+        // 28 June 2025
+        assert_eq!(expires, Some(1_751_068_800));
+    }
+
+    #[test]
+    fn entries_are_sorted_and_comments_skipped() {
+        let text = "3692217600 37\n# 2287785600 11\n\n2272060800 10 # first\n";
+        let (table, expires) = parse_leap_seconds(text).unwrap();
+        assert_eq!(table, [(63_072_000, 10), (1_483_228_800, 37)]);
+        assert_eq!(expires, None);
+    }
+
+    #[test]
+    fn invalid_tables_are_rejected() {
+        for text in [
+            "",
+            "# only comments\n",
+            "2272060800 ten\n",
+            "x 10\n",
+            "#@ soon\n2272060800 10\n",
+        ] {
+            assert!(parse_leap_seconds(text).is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn versions_keep_increasing() {
+        let versions: Vec<Duration> = (0..100)
+            .map(|_| {
+                let version = next_version();
+                let (seconds, nanos) = version.split_once(':').unwrap();
+                Duration::new(seconds.parse().unwrap(), nanos.parse().unwrap())
+            })
+            .collect();
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,169 @@
+use axum_server::tls_rustls::RustlsConfig;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::config::duration_from_secs;
+
+/// Certificate and key used to serve HTTPS and WSS
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain
+    pub cert_file: PathBuf,
+    /// PEM private key
+    pub key_file: PathBuf,
+    /// How often the files are checked for changes
+    #[serde(
+        rename = "reload_interval_secs",
+        default = "default_reload_interval",
+        deserialize_with = "duration_from_secs"
+    )]
+    pub reload_interval: Duration,
+}
+
+fn default_reload_interval() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl TlsConfig {
+    /// Loads the certificate and key into a server configuration
+    pub async fn load(&self) -> anyhow::Result<RustlsConfig> {
+        // Only the ring provider is compiled in, make it the process default
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        RustlsConfig::from_pem_file(&self.cert_file, &self.key_file)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to load certificate {} and key {}: {e}",
+                    self.cert_file.display(),
+                    self.key_file.display()
+                )
+            })
+    }
+}
+
+/// Polls the certificate and key files and reloads them into the running server when they change.
+/// A failed reload keeps the previous certificate in use.
+pub async fn watch_certificates(config: TlsConfig, rustls_config: RustlsConfig) {
+    let mut last_modified = modified_times(&config);
+    let mut interval = tokio::time::interval(config.reload_interval);
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        let modified = modified_times(&config);
+        if modified == last_modified {
+            continue;
+        }
+
+        match rustls_config
+            .reload_from_pem_file(&config.cert_file, &config.key_file)
+            .await
+        {
+            Ok(()) => {
+                tracing::info!(
+                    cert_file = %config.cert_file.display(),
+                    "reloaded TLS certificate"
+                );
+                last_modified = modified;
+            }
+            // Certificate and key may be mid-update, retry on the next tick
+            Err(e) => tracing::warn!(
+                cert_file = %config.cert_file.display(),
+                error = %e,
+                "failed to reload TLS certificate"
+            ),
+        }
+    }
+}
+
+fn modified_times(config: &TlsConfig) -> (Option<SystemTime>, Option<SystemTime>) {
+    (
+        modified_time(&config.cert_file),
+        modified_time(&config.key_file),
+    )
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn write_certificate(config: &TlsConfig) {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        std::fs::write(&config.cert_file, certified.cert.pem()).unwrap();
+        std::fs::write(&config.key_file, certified.signing_key.serialize_pem()).unwrap();
+    }
+
+    async fn wait_for_reload(rustls_config: &RustlsConfig, previous: &Arc<rustls::ServerConfig>) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while Arc::ptr_eq(previous, &rustls_config.get_inner()) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("certificate was not reloaded");
+    }
+
+    #[test]
+    fn reload_interval_defaults_and_unknown_fields_are_rejected() {
+        let config: TlsConfig =
+            toml::from_str("cert_file = \"cert.pem\"\nkey_file = \"key.pem\"").unwrap();
+        assert_eq!(config.reload_interval, Duration::from_secs(10));
+
+        let config: TlsConfig = toml::from_str(
+            "cert_file = \"cert.pem\"\nkey_file = \"key.pem\"\nreload_interval_secs = 2",
+        )
+        .unwrap();
+        assert_eq!(config.reload_interval, Duration::from_secs(2));
+
+        assert!(
+            toml::from_str::<TlsConfig>(
+                "cert_file = \"cert.pem\"\nkey_file = \"key.pem\"\nreload_interval = 2"
+            )
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn changed_certificates_are_reloaded_and_broken_ones_are_ignored() {
+        let dir = std::env::temp_dir().join(format!("tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let config = TlsConfig {
+            cert_file: dir.join("cert.pem"),
+            key_file: dir.join("key.pem"),
+            reload_interval: Duration::from_millis(20),
+        };
+        write_certificate(&config);
+        let rustls_config = config.load().await.unwrap();
+        let watcher = tokio::spawn(watch_certificates(config.clone(), rustls_config.clone()));
+        // Let the watcher record the initial modification times
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let initial = rustls_config.get_inner();
+        write_certificate(&config);
+        wait_for_reload(&rustls_config, &initial).await;
+
+        // A broken replacement keeps the previous certificate in use
+        let reloaded = rustls_config.get_inner();
+        std::fs::write(&config.cert_file, "not a certificate").unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(Arc::ptr_eq(&reloaded, &rustls_config.get_inner()));
+
+        // and is retried until the files are valid again
+        write_certificate(&config);
+        wait_for_reload(&rustls_config, &reloaded).await;
+
+        watcher.abort();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,65 +1,348 @@
-use axum::extract::{State, ws::*};
-use axum::response::IntoResponse;
+use axum::Extension;
+use axum::Json;
+use axum::extract::{ConnectInfo, RawQuery, Request, State, ws::*};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response as HttpResponse};
 use futures_util::{SinkExt, StreamExt};
-use serde_json::{from_value, json};
-use std::{collections::HashSet, sync::Arc};
-use tokio::sync::mpsc;
+use serde::Deserialize;
+use serde_json::{Value, from_value, json};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::time::Instant;
 use uuid::Uuid;
 
-use crate::{AppState, ConnectionState, data_types::*, nc_object::NcMember};
+use crate::{
+    AppState, ConnectionState,
+    auth::{ControlGrant, bearer_token},
+    config::{
+        duration_from_millis, duration_from_secs, optional_duration_from_secs, optional_limit,
+    },
+    data_types::*,
+    device_reset::restart_device,
+    nc_block::NcBlock,
+    nc_object::NcMember,
+    outbound_queue::{CLOSE_CODE_GOING_AWAY, CLOSE_CODE_NORMAL, OutboundQueue, OverflowPolicy},
+    protocol::{IncomingMessage, ProtocolError, parse_message},
+};
+
+/// Settings applied to every WebSocket connection, the `[websocket]` configuration section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebSocketConfig {
+    /// Maximum number of messages waiting to be written to a client
+    pub queue_capacity: usize,
+    /// What to do when a client does not keep up with outgoing messages
+    pub overflow_policy: OverflowPolicy,
+    /// Largest text message accepted from a client, in bytes
+    pub max_message_size: usize,
+    /// How long to wait for further property changes before sending a notification message
+    #[serde(
+        rename = "notification_batch_window_ms",
+        deserialize_with = "duration_from_millis"
+    )]
+    pub notification_batch_window: Duration,
+    /// Maximum number of notifications in a single notification message
+    pub max_notification_batch: usize,
+    /// Interval between keep-alive pings, None (0 in the configuration file) disables pings
+    #[serde(
+        rename = "ping_interval_secs",
+        deserialize_with = "optional_duration_from_secs"
+    )]
+    pub ping_interval: Option<Duration>,
+    /// Time allowed for a pong before the peer is considered dead
+    #[serde(rename = "pong_timeout_secs", deserialize_with = "duration_from_secs")]
+    pub pong_timeout: Duration,
+    /// Connections without any client message for this long are closed, None (0 in the
+    /// configuration file) disables it
+    #[serde(
+        rename = "idle_timeout_secs",
+        deserialize_with = "optional_duration_from_secs"
+    )]
+    pub idle_timeout: Option<Duration>,
+    /// Maximum number of concurrent connections, further upgrades get HTTP 503. None (0 in the
+    /// configuration file) allows any number.
+    #[serde(deserialize_with = "optional_limit")]
+    pub max_connections: Option<usize>,
+    /// Serve the connection diagnostics at `/diagnostics/connections`, behind the control
+    /// protocol authorization when IS-10 is enabled
+    pub diagnostics: bool,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        WebSocketConfig {
+            queue_capacity: 1024,
+            overflow_policy: OverflowPolicy::Coalesce,
+            max_message_size: 1024 * 1024,
+            notification_batch_window: Duration::from_millis(5),
+            max_notification_batch: 256,
+            ping_interval: Some(Duration::from_secs(30)),
+            pong_timeout: Duration::from_secs(10),
+            idle_timeout: None,
+            max_connections: Some(64),
+            diagnostics: false,
+        }
+    }
+}
 
 /// WebSocket entrypoint
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+) -> HttpResponse {
     let conn_id = Uuid::new_v4();
-    ws.on_upgrade(move |socket| handle_socket(socket, state, conn_id))
+    // Only known when the router is served with connect info, as DeviceServer::serve does
+    let remote_addr = connect_info.map_or_else(
+        || "unknown".to_string(),
+        |Extension(ConnectInfo(remote_addr))| remote_addr.to_string(),
+    );
+
+    let grant = match &state.auth {
+        Some(auth) => match auth.authorize_control(bearer_token(&headers, query.as_deref())) {
+            Ok(grant) => Some(grant),
+            Err(err) => {
+                tracing::warn!(%conn_id, %remote_addr, ?err, "WebSocket upgrade rejected: unauthorized");
+                return err.into_response();
+            }
+        },
+        None => None,
+    };
+
+    // The slot is released when the connection ends, or when the upgrade never completes
+    let slot = match &state.connection_slots {
+        Some(slots) => match slots.clone().try_acquire_owned() {
+            Ok(slot) => Some(slot),
+            Err(_) => {
+                tracing::warn!(
+                    %conn_id,
+                    %remote_addr,
+                    max_connections = state.ws_config.max_connections,
+                    "WebSocket upgrade rejected: connection limit reached"
+                );
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [(header::RETRY_AFTER, "5")],
+                    "Maximum number of connections reached",
+                )
+                    .into_response();
+            }
+        },
+        None => None,
+    };
+
+    // Oversized frames are refused while reading their header, before anything is buffered
+    let max_message_size = state.ws_config.max_message_size;
+    ws.max_message_size(max_message_size)
+        .max_frame_size(max_message_size)
+        .on_upgrade(move |socket| async move {
+            handle_socket(socket, state, conn_id, remote_addr, grant).await;
+            drop(slot);
+        })
 }
 
 /// Background event loop
 pub async fn run_event_loop(state: Arc<AppState>) {
-    while let Some(event) = state.event_rx.lock().await.recv().await {
-        state.notify_subscribers(event).await;
+    let mut event_rx = state.event_rx.lock().await;
+    let window = state.ws_config.notification_batch_window;
+    let max_batch = state.ws_config.max_notification_batch.max(1);
+
+    while let Some(first) = event_rx.recv().await {
+        // Collect everything produced within the batching window
+        let mut events = vec![first];
+        let deadline = Instant::now() + window;
+        while events.len() < max_batch {
+            match event_rx.try_recv() {
+                Ok(event) => events.push(event),
+                Err(_) => match tokio::time::timeout_at(deadline, event_rx.recv()).await {
+                    Ok(Some(event)) => events.push(event),
+                    _ => break,
+                },
+            }
+        }
+
+        let events = coalesce_events(events);
+
+        if let Some(persistence) = &state.persistence {
+            let changed = persistence.record(&*state.root_block.lock().await, &events);
+            if changed {
+                let result = persistence.save().await;
+                report_persistence(&state, result).await;
+            }
+        }
+
+        sync_node_resources(&state, &events).await;
+
+        // Block membership changed (2p2): added objects may fall under subtree subscriptions
+        let members_changed = events
+            .iter()
+            .any(|e| e.event_data.property_id.level == 2 && e.event_data.property_id.index == 2);
+        if members_changed {
+            state.refresh_subscriptions().await;
+        }
+
+        state.notify_subscribers(events).await;
+    }
+}
+
+/// Details of the InternalError state entered while property values cannot be persisted
+const PERSISTENCE_FAILURE: &str = "Cannot persist property values";
+
+/// Reports failing writes of persisted values through the operational state of the device
+/// manager, returning to normal operation once a write succeeds again
+async fn report_persistence(state: &AppState, result: anyhow::Result<()>) {
+    let mut root = state.root_block.lock().await;
+    let Some(manager) = root.device_manager_mut() else {
+        return;
+    };
+    let failing = manager.operational_state.generic == NcDeviceGenericState::InternalError
+        && manager.operational_state.device_specific_details.as_deref()
+            == Some(PERSISTENCE_FAILURE);
+    let transition = match result {
+        Err(e) if !failing => manager
+            .set_operational_state(
+                NcDeviceGenericState::InternalError,
+                Some(PERSISTENCE_FAILURE.to_string()),
+            )
+            .map(|_| manager.set_message(Some(e.to_string()))),
+        Ok(()) if failing => manager
+            .set_operational_state(NcDeviceGenericState::NormalOperation, None)
+            .map(|_| manager.set_message(None)),
+        _ => Ok(()),
+    };
+    if let Err(e) = transition {
+        tracing::debug!(error = %e, "operational state left unchanged");
     }
 }
 
-/// Processes a WsCommandMessage into a response
-async fn process_command(msg: WsCommandMessage, state: Arc<AppState>) -> WsCommandResponseMessage {
+/// Carries control model changes over to the IS-04 resources, the device label follows the
+/// deviceName (3p6) of the device manager
+async fn sync_node_resources(state: &AppState, events: &[PropertyChangedEvent]) {
+    let device_name_changes: Vec<u64> = events
+        .iter()
+        .filter(|e| e.event_data.property_id.level == 3 && e.event_data.property_id.index == 6)
+        .map(|e| e.oid)
+        .collect();
+    if device_name_changes.is_empty() {
+        return;
+    }
+    let device_name = {
+        let root = state.root_block.lock().await;
+        match root.device_manager() {
+            Some(manager) if device_name_changes.contains(&manager.get_oid()) => {
+                manager.device_name.clone()
+            }
+            _ => return,
+        }
+    };
+    state
+        .resources
+        .write()
+        .await
+        .set_device_name(device_name.as_deref());
+}
+
+/// Keeps only the latest ValueChanged per property, a full value supersedes earlier changes
+fn coalesce_events(events: Vec<PropertyChangedEvent>) -> Vec<PropertyChangedEvent> {
+    let mut result: Vec<PropertyChangedEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        if matches!(
+            event.event_data.change_type,
+            NcPropertyChangeType::ValueChanged
+        ) {
+            let property_id = &event.event_data.property_id;
+            result.retain(|e| {
+                e.oid != event.oid
+                    || e.event_data.property_id.level != property_id.level
+                    || e.event_data.property_id.index != property_id.index
+            });
+        }
+        result.push(event);
+    }
+    result
+}
+
+/// Runs one command against the model, shared by the WebSocket and the configuration REST API
+pub fn execute_command(
+    root: &mut NcBlock,
+    oid: u64,
+    method_id: NcElementId,
+    arguments: Value,
+) -> (NcMethodStatus, Option<String>, Value) {
+    match (method_id.level, method_id.index) {
+        (1, 1) => match from_value::<IdArgs>(arguments) {
+            Ok(id_args) => {
+                let (err, val, status) = root.get_property(oid, &id_args);
+                (status, err, val)
+            }
+            Err(e) => (
+                NcMethodStatus::BadCommandFormat,
+                Some(format!("Invalid args: {e}")),
+                json!(null),
+            ),
+        },
+        (1, 2) => match from_value::<IdArgsValue>(arguments) {
+            Ok(id_val) => {
+                let (err, status_code) = root.set_property(oid, id_val);
+                (status_code, err, json!(null))
+            }
+            Err(e) => (
+                NcMethodStatus::BadCommandFormat,
+                Some(format!("Invalid args: {e}")),
+                json!(null),
+            ),
+        },
+        _ => {
+            let (err, resp, status) = root.invoke_method_mut(oid, method_id, arguments);
+            (status, err, resp.unwrap_or(json!(null)))
+        }
+    }
+}
+
+/// Processes a WsCommandMessage into a response, also telling whether the device manager was
+/// asked to reset the device
+async fn process_command(
+    msg: WsCommandMessage,
+    state: Arc<AppState>,
+    grant: Option<&ControlGrant>,
+) -> (WsCommandResponseMessage, bool) {
     let mut responses = Vec::new();
+    let mut reset = false;
 
     for cmd in msg.commands {
         let mut root = state.root_block.lock().await;
 
-        let (status, error_message, value) = match (cmd.method_id.level, cmd.method_id.index) {
-            (1, 1) => match from_value::<IdArgs>(cmd.arguments) {
-                Ok(id_args) => {
-                    let (err, val, status) = root.get_property(cmd.oid, &id_args);
-                    (status, err, val)
-                }
-                Err(e) => (
-                    NcMethodStatus::BadCommandFormat,
-                    Some(format!("Invalid args: {e}")),
-                    json!(null),
-                ),
-            },
-            (1, 2) => match from_value::<IdArgsValue>(cmd.arguments) {
-                Ok(id_val) => {
-                    let (err, status_code) = root.set_property(cmd.oid, id_val);
-                    (status_code, err, json!(null))
-                }
-                Err(e) => (
-                    NcMethodStatus::BadCommandFormat,
-                    Some(format!("Invalid args: {e}")),
-                    json!(null),
-                ),
-            },
-            _ => {
-                let (err, resp, status) = root.invoke_method(cmd.oid, cmd.method_id, cmd.arguments);
-                (status, err, resp.unwrap_or(json!(null)))
+        let authorized = match grant {
+            Some(grant) => {
+                let mutating = root
+                    .find_object(cmd.oid)
+                    .is_some_and(|object| object.is_mutating_method(&cmd.method_id));
+                grant.check_command(mutating)
             }
+            None => Ok(()),
         };
 
+        let (status, error_message, value) = match authorized {
+            Err((status, error_message)) => (status, Some(error_message), json!(null)),
+            Ok(()) => execute_command(&mut root, cmd.oid, cmd.method_id.clone(), cmd.arguments),
+        };
+        reset |= root
+            .device_manager()
+            .is_some_and(|manager| manager.is_reset(cmd.oid, &cmd.method_id, &status));
+
+        if !matches!(status, NcMethodStatus::Ok) {
+            tracing::debug!(
+                oid = cmd.oid,
+                role_path = root.role_path_of(cmd.oid).map(|p| p.to_string()),
+                method_id = format!("{}m{}", cmd.method_id.level, cmd.method_id.index),
+                ?status,
+                error = error_message.as_deref(),
+                "Command failed"
+            );
+        }
+
         let result = match status {
             NcMethodStatus::Ok => ResponsePayload::Result(NcMethodResultPropertyValue {
                 base: NcMethodResult { status },
@@ -77,113 +360,257 @@ async fn process_command(msg: WsCommandMessage, state: Arc<AppState>) -> WsComma
         });
     }
 
-    WsCommandResponseMessage {
+    let response = WsCommandResponseMessage {
         message_type: MESSAGE_TYPE_COMMAND_RESPONSE,
         responses,
-    }
+    };
+    (response, reset)
 }
 
 /// Handles a single client connection
-async fn handle_socket(socket: WebSocket, state: Arc<AppState>, conn_id: Uuid) {
+async fn handle_socket(
+    socket: WebSocket,
+    state: Arc<AppState>,
+    conn_id: Uuid,
+    remote_addr: String,
+    grant: Option<ControlGrant>,
+) {
+    let connected_at = Instant::now();
+    tracing::info!(%conn_id, %remote_addr, "WebSocket connected");
+
     let (mut sender, mut receiver) = socket.split();
-    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+    let queue = Arc::new(OutboundQueue::new(
+        state.ws_config.queue_capacity,
+        state.ws_config.overflow_policy,
+    ));
 
     // Spawn sending loop
+    let queue_s = queue.clone();
     let send_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
+        while let Some(msg) = queue_s.pop().await {
+            let is_close = matches!(msg, Message::Close(_));
             if sender.send(msg).await.is_err() {
-                break;
+                return "send failed";
+            }
+            if is_close {
+                return "closed by server";
             }
         }
+        "closed by server"
     });
 
     // Register connection
     {
         let mut conns = state.connections.write().await;
-        conns.insert(
-            conn_id,
-            ConnectionState {
-                subscribed_oids: HashSet::new(),
-                sender: tx.clone(),
-            },
-        );
+        conns.insert(conn_id, ConnectionState::new(queue.clone()));
     }
 
     let state_c = state.clone();
-    let tx_c = tx.clone();
+    let queue_c = queue.clone();
+    let config = state.ws_config.clone();
 
     let recv_task = tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
-            if let Message::Text(text) = msg {
-                // Try command
-                if let Ok(cmd) = serde_json::from_str::<WsCommandMessage>(&text)
-                    && cmd.message_type == MESSAGE_TYPE_COMMAND
-                {
-                    // Validate handle
-                    let invalid_handle = cmd
-                        .commands
-                        .iter()
-                        .any(|c| c.handle == 0 || c.handle > 65535);
-                    if invalid_handle {
-                        let _ = tx_c.send(Message::Text(
-                            serde_json::to_string(&WsErrorMessage {
-                                message_type: MESSAGE_TYPE_ERROR,
-                                status: 400,
-                                error_message: "Invalid message".into(),
-                            })
-                            .unwrap()
-                            .into(),
-                        ));
-                        continue;
-                    }
+        let mut ping_timer = config.ping_interval.map(|interval| {
+            let mut timer = tokio::time::interval_at(Instant::now() + interval, interval);
+            timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            timer
+        });
+        let mut pong_deadline: Option<Instant> = None;
+        let mut idle_deadline = config.idle_timeout.map(|timeout| Instant::now() + timeout);
 
-                    let response = process_command(cmd, state_c.clone()).await;
-                    if let Ok(txt) = serde_json::to_string(&response) {
-                        let _ = tx_c.send(Message::Text(txt.into()));
+        loop {
+            let msg = tokio::select! {
+                msg = receiver.next() => match msg {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(err)) => {
+                        if let Some(size) = message_too_big(err) {
+                            send_protocol_error(
+                                &queue_c,
+                                ProtocolError::message_too_big(size, config.max_message_size),
+                            );
+                            return "message too big";
+                        }
+                        return "receive failed";
+                    }
+                    None => return "closed by client",
+                },
+                _ = tick(&mut ping_timer) => {
+                    if pong_deadline.is_none() {
+                        queue_c.push(Message::Ping(Default::default()));
+                        pong_deadline = Some(Instant::now() + config.pong_timeout);
                     }
                     continue;
                 }
+                _ = sleep_until(pong_deadline) => {
+                    tracing::warn!(%conn_id, "No pong received, dropping dead peer");
+                    return "dead peer";
+                }
+                _ = sleep_until(idle_deadline) => {
+                    queue_c.close_after_pending(CLOSE_CODE_GOING_AWAY, "Idle timeout");
+                    return "idle timeout";
+                }
+            };
 
-                // Try subscription
-                if let Ok(sub) = serde_json::from_str::<WsSubscriptionMessage>(&text)
-                    && sub.message_type == MESSAGE_TYPE_SUBSCRIPTION
-                {
-                    let mut conns = state_c.connections.write().await;
-                    if let Some(c) = conns.get_mut(&conn_id) {
-                        c.subscribed_oids = sub.subscriptions.iter().cloned().collect();
-                        println!(
-                            "Connection {} updated subscriptions: {:?}",
-                            conn_id, c.subscribed_oids
-                        );
+            // Any traffic proves the peer is alive
+            pong_deadline = None;
+
+            let text = match msg {
+                Message::Text(text) => text,
+                Message::Binary(_) => {
+                    send_protocol_error(&queue_c, ProtocolError::binary_not_supported());
+                    return "binary message";
+                }
+                // Pings are answered by the WebSocket layer
+                Message::Ping(_) | Message::Pong(_) => continue,
+                Message::Close(_) => return "closed by client",
+            };
+
+            idle_deadline = config.idle_timeout.map(|timeout| Instant::now() + timeout);
+
+            if text.len() > config.max_message_size {
+                send_protocol_error(
+                    &queue_c,
+                    ProtocolError::message_too_big(text.len(), config.max_message_size),
+                );
+                return "message too big";
+            }
+
+            match parse_message(&text) {
+                Ok(IncomingMessage::Command(cmd)) => {
+                    let (response, reset) =
+                        process_command(cmd, state_c.clone(), grant.as_ref()).await;
+                    if let Ok(txt) = serde_json::to_string(&response) {
+                        queue_c.push(Message::Text(txt.into()));
+                    }
+                    if reset {
+                        tokio::spawn(restart_device(state_c.clone()));
                     }
+                }
+                Ok(IncomingMessage::Subscription(sub)) => {
+                    let root = state_c.root_block.lock().await;
+                    let mut conns = state_c.connections.write().await;
+                    let subscriptions = match conns.get_mut(&conn_id) {
+                        Some(c) => {
+                            c.apply_subscription(&sub, &root);
+                            c.subscriptions()
+                        }
+                        None => Vec::new(),
+                    };
+                    drop(conns);
+                    drop(root);
+                    tracing::debug!(%conn_id, ?subscriptions, "Updated subscriptions");
+
                     let resp = WsSubscriptionResponseMessage {
                         message_type: MESSAGE_TYPE_SUBSCRIPTION_RESPONSE,
-                        subscriptions: sub.subscriptions,
+                        subscriptions,
                     };
                     if let Ok(txt) = serde_json::to_string(&resp) {
-                        let _ = tx_c.send(Message::Text(txt.into()));
+                        queue_c.push(Message::Text(txt.into()));
+                    }
+                }
+                Err(err) => {
+                    tracing::debug!(%conn_id, error = %err.error_message, "Protocol error");
+                    let closes = err.close.is_some();
+                    send_protocol_error(&queue_c, err);
+                    if closes {
+                        return "protocol error";
                     }
-                    continue;
                 }
-
-                // Otherwise, error
-                let _ = tx_c.send(Message::Text(
-                    serde_json::to_string(&WsErrorMessage {
-                        message_type: MESSAGE_TYPE_ERROR,
-                        status: 400,
-                        error_message: "Invalid message".into(),
-                    })
-                    .unwrap()
-                    .into(),
-                ));
             }
         }
     });
 
-    tokio::select! {
-        _ = send_task => {},
-        _ = recv_task => {},
-    }
+    let reason = tokio::select! {
+        reason = send_task => reason.unwrap_or("send task failed"),
+        reason = recv_task => reason.unwrap_or("receive task failed"),
+    };
 
     state.connections.write().await.remove(&conn_id);
+
+    // Lets the sending loop finish once the client is gone
+    queue.close_after_pending(CLOSE_CODE_NORMAL, "");
+
+    tracing::info!(
+        %conn_id,
+        %remote_addr,
+        reason,
+        duration_ms = connected_at.elapsed().as_millis() as u64,
+        "WebSocket disconnected"
+    );
+}
+
+/// Waits for the next tick, or forever when the timer is disabled
+async fn tick(timer: &mut Option<tokio::time::Interval>) {
+    match timer {
+        Some(timer) => {
+            timer.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Sleeps until the deadline, or forever when there is none
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Size of a message refused by the WebSocket layer for exceeding the configured maximum
+fn message_too_big(err: axum::Error) -> Option<usize> {
+    match *err.into_inner().downcast::<tungstenite::Error>().ok()? {
+        tungstenite::Error::Capacity(tungstenite::error::CapacityError::MessageTooLong {
+            size,
+            ..
+        }) => Some(size),
+        _ => None,
+    }
+}
+
+/// Reports a protocol error to the client, closing the connection when required
+fn send_protocol_error(queue: &OutboundQueue, err: ProtocolError) {
+    if let Ok(txt) = serde_json::to_string(&err.to_message()) {
+        queue.push(Message::Text(txt.into()));
+    }
+    if let Some((code, reason)) = err.close {
+        queue.close_after_pending(code, reason);
+    }
+}
+
+/// Requires the access token of a control protocol client for the connection diagnostics
+pub async fn diagnostics_auth_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> HttpResponse {
+    if let Some(auth) = &state.auth {
+        let token = bearer_token(request.headers(), request.uri().query());
+        if let Err(err) = auth.authorize_control(token) {
+            return err.into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Lists open connections with their subscriptions and outbound queue usage
+pub async fn connections_diagnostics_handler(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let conns = state.connections.read().await;
+    let list: Vec<_> = conns
+        .iter()
+        .map(|(conn_id, conn)| {
+            json!({
+                "id": conn_id.to_string(),
+                "subscriptions": conn.subscriptions(),
+                "subscribeSubtree": conn.subscribe_subtree,
+                "queueDepth": conn.queue.len(),
+                "queueCapacity": conn.queue.capacity(),
+                "droppedMessages": conn.queue.dropped(),
+                "coalescedMessages": conn.queue.coalesced(),
+            })
+        })
+        .collect();
+    Json(list)
 }
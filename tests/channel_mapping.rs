@@ -0,0 +1,153 @@
+//! Schedules and cancels activations of the IS-08 Channel Mapping API of the example device
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use nmos_control_rusty_device::{DeviceServer, config::Config};
+use serde_json::{Value, json};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
+use tower::ServiceExt;
+
+const ACTIVATIONS: &str = "/x-nmos/channelmapping/v1.0/map/activations";
+const ACTIVE_MAP: &str = "/x-nmos/channelmapping/v1.0/map/active";
+
+async fn router() -> Router {
+    let mut config = Config::default();
+    config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    config.server.port = 0;
+    DeviceServer::builder(config)
+        .build()
+        .await
+        .unwrap()
+        .router()
+}
+
+async fn request(
+    router: &Router,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    let response = router.clone().oneshot(request.unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+/// Routes the test tone to both channels of the example audio sender
+fn test_tone(mode: &str, requested_time: Option<&str>) -> Value {
+    json!({
+        "activation": { "mode": mode, "requested_time": requested_time },
+        "action": {
+            "sender-audio": {
+                "0": { "input": "test-tone", "channel_index": 0 },
+                "1": { "input": "test-tone", "channel_index": 1 }
+            }
+        }
+    })
+}
+
+fn activation_id(body: &Value) -> String {
+    body.as_object().unwrap().keys().next().unwrap().clone()
+}
+
+#[tokio::test]
+async fn relative_activations_are_applied_once_due() {
+    let router = router().await;
+    let (status, body) = request(
+        &router,
+        Method::POST,
+        ACTIVATIONS,
+        Some(test_tone(
+            "activate_scheduled_relative",
+            Some("0:100000000"),
+        )),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let id = activation_id(&body);
+    assert!(body[&id]["activation"]["activation_time"].is_string());
+
+    let (_, active) = request(&router, Method::GET, ACTIVE_MAP, None).await;
+    assert_eq!(
+        active["map"]["sender-audio"]["0"]["input"],
+        "receiver-audio"
+    );
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let (_, active) = request(&router, Method::GET, ACTIVE_MAP, None).await;
+    assert_eq!(active["map"]["sender-audio"]["0"]["input"], "test-tone");
+    let (_, scheduled) = request(&router, Method::GET, ACTIVATIONS, None).await;
+    assert_eq!(scheduled, json!({}));
+}
+
+#[tokio::test]
+async fn cancelled_activations_are_never_applied() {
+    let router = router().await;
+    let (status, body) = request(
+        &router,
+        Method::POST,
+        ACTIVATIONS,
+        Some(test_tone(
+            "activate_scheduled_relative",
+            Some("0:200000000"),
+        )),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let id = activation_id(&body);
+
+    // The output is locked while the activation is pending
+    let (status, _) = request(
+        &router,
+        Method::POST,
+        ACTIVATIONS,
+        Some(test_tone("activate_immediate", None)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::LOCKED);
+
+    let uri = format!("{ACTIVATIONS}/{id}");
+    let (status, _) = request(&router, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = request(&router, Method::DELETE, &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = request(&router, Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, scheduled) = request(&router, Method::GET, ACTIVATIONS, None).await;
+    assert_eq!(scheduled, json!({}));
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    let (_, active) = request(&router, Method::GET, ACTIVE_MAP, None).await;
+    assert_eq!(
+        active["map"]["sender-audio"]["0"]["input"],
+        "receiver-audio"
+    );
+
+    // Unlocked again
+    let (status, _) = request(
+        &router,
+        Method::POST,
+        ACTIVATIONS,
+        Some(test_tone("activate_immediate", None)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
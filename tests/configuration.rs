@@ -0,0 +1,227 @@
+//! Reads, sets and restores properties of the example device through the IS-14 Configuration API
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use nmos_control_rusty_device::{DeviceServer, auth::AuthConfig, config::Config};
+use serde_json::{Value, json};
+use std::net::{IpAddr, Ipv4Addr};
+use tower::ServiceExt;
+
+const WORKER: &str = "/x-nmos/configuration/v1.0/rolePaths/root.my-worker-01";
+
+fn config() -> Config {
+    let mut config = Config::default();
+    config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    config.server.port = 0;
+    config
+}
+
+async fn router(config: Config) -> Router {
+    DeviceServer::builder(config)
+        .build()
+        .await
+        .unwrap()
+        .router()
+}
+
+async fn request(
+    router: &Router,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    let response = router.clone().oneshot(request.unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn property_values_are_read_and_set() {
+    let router = router(config()).await;
+    let user_label = format!("{WORKER}/properties/1p6/value");
+
+    let (status, body) = request(&router, Method::GET, &user_label, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "status": 200, "value": "My worker 01" }));
+
+    let value = json!({ "value": "Configured worker" });
+    let (status, body) = request(&router, Method::PUT, &user_label, Some(value)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "status": 200 }));
+    let (_, body) = request(&router, Method::GET, &user_label, None).await;
+    assert_eq!(body["value"], json!("Configured worker"));
+
+    // The class id is read-only
+    let class_id = format!("{WORKER}/properties/1p1/value");
+    let (status, body) = request(
+        &router,
+        Method::PUT,
+        &class_id,
+        Some(json!({ "value": [1, 2] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(body["status"], json!(405));
+    assert!(body["errorMessage"].is_string());
+
+    let (status, body) = request(
+        &router,
+        Method::GET,
+        "/x-nmos/configuration/v1.0/rolePaths/root.missing/properties/1p6/value",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["status"], json!(404));
+    let (status, _) = request(
+        &router,
+        Method::GET,
+        &format!("{WORKER}/properties/9p9/value"),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+}
+
+#[tokio::test]
+async fn descriptors_and_methods_are_served() {
+    let router = router(config()).await;
+
+    let (status, body) = request(&router, Method::GET, &format!("{WORKER}/descriptor"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"]["classId"], json!([1, 2]));
+    let (_, body) = request(
+        &router,
+        Method::GET,
+        &format!("{WORKER}/properties/1p6/descriptor"),
+        None,
+    )
+    .await;
+    assert_eq!(body["value"]["name"], json!("userLabel"));
+    let (_, body) = request(
+        &router,
+        Method::GET,
+        &format!("{WORKER}/methods/1m1/descriptor"),
+        None,
+    )
+    .await;
+    assert_eq!(body["value"]["name"], json!("Get"));
+
+    let (status, body) = request(
+        &router,
+        Method::POST,
+        &format!("{WORKER}/methods/1m1"),
+        Some(json!({ "arguments": { "id": { "level": 1, "index": 6 } } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"], json!("My worker 01"));
+}
+
+#[tokio::test]
+async fn bulk_properties_are_validated_and_restored() {
+    let router = router(config()).await;
+    let bulk_properties = format!("{WORKER}/bulkProperties");
+    let user_label = format!("{WORKER}/properties/1p6/value");
+
+    let (status, body) = request(
+        &router,
+        Method::GET,
+        &format!("{bulk_properties}?recurse=false"),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut data_set = body["value"].clone();
+    assert_eq!(
+        data_set["values"][0]["path"],
+        json!(["root", "my-worker-01"])
+    );
+    for property in data_set["values"][0]["values"].as_array_mut().unwrap() {
+        if property["id"] == json!({ "level": 1, "index": 6 }) {
+            property["value"] = json!("Restored worker");
+        }
+    }
+    let arguments = json!({
+        "arguments": { "dataSet": data_set, "recurse": false, "restoreMode": 1 }
+    });
+
+    // PATCH only validates
+    let (status, body) = request(
+        &router,
+        Method::PATCH,
+        &bulk_properties,
+        Some(arguments.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"][0]["status"], json!(200));
+    let (_, body) = request(&router, Method::GET, &user_label, None).await;
+    assert_eq!(body["value"], json!("My worker 01"));
+
+    let (status, body) = request(&router, Method::PUT, &bulk_properties, Some(arguments)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["value"][0]["status"], json!(200));
+    let (_, body) = request(&router, Method::GET, &user_label, None).await;
+    assert_eq!(body["value"], json!("Restored worker"));
+
+    let (status, _) = request(&router, Method::PUT, &bulk_properties, Some(json!({}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn requests_without_a_token_are_rejected_when_authorization_is_enabled() {
+    let jwks_file = std::env::temp_dir().join(format!("jwks-{}.json", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &jwks_file,
+        json!({
+            "keys": [{
+                "kty": "RSA",
+                "kid": "test-key",
+                "alg": "RS512",
+                "use": "sig",
+                "n": "ycAmVzhC0272_CC66PELli7c0nLLkPkUV2NSc1NA-Bck_lcwRcCijx2b3GX650zaDzO-Ehn3c6_0s55jJ72vXHgfN5pY0rBuCVSyLSiswIovgcjE6ABujTlxrxhTL3zf6oKF4xg0zVitCtdL67huaJud2vbDLlTVrByDtD2fSyQDPnAHXstqfAvQOzQIaRx-jtkYI9LQQkdnaHAEahE1n-GiWIkUYSTnw4etpXrOEA9F9rx7heMqPr2aVcHPXOx8V4YzGp12hrXjnAZFYwOM6RryAWkGsYxIPwDExbgBpTdvb4_WxxAyQs3oUe_5U0uaaZlGTHkEiDXxxA2o_OmTCw",
+                "e": "AQAB"
+            }]
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let mut config = config();
+    config.auth = Some(AuthConfig {
+        jwks_file: jwks_file.clone(),
+        issuer: "https://auth.example.com".to_string(),
+        audience: vec!["device.example.com".to_string()],
+    });
+    let router = router(config).await;
+    let user_label = format!("{WORKER}/properties/1p6/value");
+
+    let (status, _) = request(&router, Method::GET, &user_label, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = request(
+        &router,
+        Method::PUT,
+        &format!("{user_label}?access_token=not-a-token"),
+        Some(json!({ "value": "Unauthorized" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let _ = std::fs::remove_file(&jwks_file);
+}
@@ -0,0 +1,776 @@
+//! Drives the built-in example device through its IS-12 WebSocket with the client of the crate,
+//! covering the properties and methods of every class in the model.
+
+use nmos_control_rusty_device::{
+    DeviceServer,
+    client::{ClientError, ControlClient},
+    config::Config,
+    data_types::{
+        MESSAGE_TYPE_SUBSCRIPTION, NcBlockMemberDescriptor, NcDescriptor, NcElementId,
+        NcMethodStatus, NcPropertyChangeType,
+    },
+    nc_block::NcBlock,
+    nc_worker::NcWorker,
+};
+use serde_json::{Value, json};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
+
+const ROOT_OID: u64 = 1;
+const DEVICE_MANAGER_OID: u64 = 2;
+const CLASS_MANAGER_OID: u64 = 3;
+const WORKER_OID: u64 = 5;
+const BLOCK_OID: u64 = 6;
+const NESTED_WORKER_OID: u64 = 8;
+const BULK_PROPERTIES_MANAGER_OID: u64 = 9;
+const IDENT_BEACON_OID: u64 = 10;
+const CHANNEL_MAPPING_BLOCK_OID: u64 = 11;
+
+fn id(level: u32, index: u32) -> NcElementId {
+    NcElementId { level, index }
+}
+
+/// Serves the example device on an ephemeral port, returns the URL of its WebSocket
+async fn start_device(configure: impl FnOnce(&mut Config)) -> String {
+    let mut config = Config::default();
+    config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    config.server.port = 0;
+    configure(&mut config);
+    let server = DeviceServer::builder(config)
+        .build()
+        .await
+        .expect("device should build");
+    let url = format!("ws://{}/ws", server.local_addr());
+    tokio::spawn(server.serve());
+    url
+}
+
+async fn connect() -> ControlClient {
+    let url = start_device(|_| {}).await;
+    ControlClient::connect(&url)
+        .await
+        .expect("device should accept the connection")
+}
+
+/// The root block and everything nested under it
+async fn all_members(client: &ControlClient) -> Vec<NcBlockMemberDescriptor> {
+    let mut members = client.get_member_descriptors(ROOT_OID, true).await.unwrap();
+    members.insert(
+        0,
+        NcBlockMemberDescriptor {
+            base: NcDescriptor { description: None },
+            role: "root".to_string(),
+            oid: ROOT_OID,
+            constant_oid: true,
+            class_id: vec![1, 1],
+            user_label: String::new(),
+            owner: 0,
+        },
+    );
+    members
+}
+
+#[tokio::test]
+async fn every_property_of_every_object_can_be_read() {
+    let client = connect().await;
+
+    for member in all_members(&client).await {
+        let class = client
+            .get_control_class(CLASS_MANAGER_OID, &member.class_id, true)
+            .await
+            .unwrap_or_else(|e| panic!("class of {}: {e}", member.role));
+        assert!(!class.properties.is_empty());
+
+        for property in &class.properties {
+            let value = client
+                .get(member.oid, property.id.clone())
+                .await
+                .unwrap_or_else(|e| panic!("{} {}: {e}", member.role, property.name));
+            if !property.is_nullable {
+                assert!(
+                    !value.is_null(),
+                    "{} {} should not be null",
+                    member.role,
+                    property.name
+                );
+            }
+            if property.is_sequence && !value.is_null() {
+                assert!(value.is_array(), "{} {}", member.role, property.name);
+            }
+        }
+
+        assert_eq!(
+            client.get(member.oid, id(1, 1)).await.unwrap(),
+            json!(member.class_id)
+        );
+        assert_eq!(
+            client.get(member.oid, id(1, 2)).await.unwrap(),
+            json!(member.oid)
+        );
+        assert_eq!(
+            client.get(member.oid, id(1, 5)).await.unwrap(),
+            json!(member.role)
+        );
+    }
+}
+
+#[tokio::test]
+async fn read_only_properties_reject_set_and_writable_ones_accept_their_value() {
+    let client = connect().await;
+
+    for member in all_members(&client).await {
+        let class = client
+            .get_control_class(CLASS_MANAGER_OID, &member.class_id, true)
+            .await
+            .unwrap();
+        for property in &class.properties {
+            let value = client.get(member.oid, property.id.clone()).await.unwrap();
+            let result = client.set(member.oid, property.id.clone(), value).await;
+            if property.is_read_only {
+                assert!(
+                    matches!(result, Err(ClientError::Method { status: 405, .. })),
+                    "{} {}: {result:?}",
+                    member.role,
+                    property.name
+                );
+            } else {
+                assert_eq!(result, Ok(()), "{} {}", member.role, property.name);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn unknown_objects_and_properties_are_reported() {
+    let client = connect().await;
+
+    let result = client
+        .command(999, id(1, 1), json!({ "id": id(1, 1) }))
+        .await;
+    assert!(matches!(result.unwrap().status, NcMethodStatus::BadOid));
+
+    let result = client.get(WORKER_OID, id(9, 9)).await;
+    assert!(matches!(
+        result,
+        Err(ClientError::Method { status: 502, .. })
+    ));
+
+    let result = client.command(WORKER_OID, id(9, 9), json!({})).await;
+    assert!(matches!(
+        result.unwrap().status,
+        NcMethodStatus::MethodNotImplemented
+    ));
+}
+
+#[tokio::test]
+async fn sequence_items_can_be_read() {
+    let client = connect().await;
+
+    let length = client
+        .command(DEVICE_MANAGER_OID, id(1, 7), json!({ "id": id(1, 7) }))
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(length, json!(1));
+
+    let touchpoints = client.get(DEVICE_MANAGER_OID, id(1, 7)).await.unwrap();
+    let item = client
+        .command(
+            DEVICE_MANAGER_OID,
+            id(1, 3),
+            json!({ "id": id(1, 7), "index": 0 }),
+        )
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(item, touchpoints[0]);
+
+    let result = client
+        .command(
+            DEVICE_MANAGER_OID,
+            id(1, 3),
+            json!({ "id": id(1, 7), "index": 1 }),
+        )
+        .await
+        .unwrap();
+    assert!(matches!(result.status, NcMethodStatus::IndexOutOfBounds));
+
+    // Block members are a sequence too
+    let length = client
+        .command(BLOCK_OID, id(1, 7), json!({ "id": id(2, 2) }))
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(length, json!(2));
+    let item = client
+        .command(BLOCK_OID, id(1, 3), json!({ "id": id(2, 2), "index": 1 }))
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(item["oid"], json!(NESTED_WORKER_OID));
+}
+
+#[tokio::test]
+async fn block_members_can_be_searched() {
+    let client = connect().await;
+
+    let members = client
+        .get_member_descriptors(ROOT_OID, false)
+        .await
+        .unwrap();
+    assert!(members.iter().all(|member| member.owner == ROOT_OID));
+    let nested = client.get_member_descriptors(ROOT_OID, true).await.unwrap();
+    assert!(nested.len() > members.len());
+    assert!(nested.iter().any(|member| member.oid == NESTED_WORKER_OID));
+
+    let found = client
+        .command(
+            ROOT_OID,
+            id(2, 2),
+            json!({ "path": ["my-block-01", "my-worker-02"] }),
+        )
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    let found: Vec<NcBlockMemberDescriptor> = serde_json::from_value(found).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].oid, NESTED_WORKER_OID);
+
+    let found = client
+        .command(
+            ROOT_OID,
+            id(2, 3),
+            json!({
+                "role": "MY-WORKER",
+                "caseSensitive": false,
+                "matchWholeString": false,
+                "recurse": true,
+            }),
+        )
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    let oids: Vec<u64> = found
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|member| member["oid"].as_u64().unwrap())
+        .collect();
+    assert_eq!(oids, vec![WORKER_OID, NESTED_WORKER_OID]);
+
+    let found = client
+        .command(
+            ROOT_OID,
+            id(2, 4),
+            json!({ "classId": [1, 2], "includeDerived": false, "recurse": true }),
+        )
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    let found: Vec<NcBlockMemberDescriptor> = serde_json::from_value(found).unwrap();
+    assert!(found.iter().any(|member| member.oid == WORKER_OID));
+    assert!(found.iter().all(|member| member.class_id == vec![1, 2]));
+}
+
+#[tokio::test]
+async fn class_manager_describes_every_class_and_datatype() {
+    let client = connect().await;
+
+    let classes = client.get(CLASS_MANAGER_OID, id(3, 1)).await.unwrap();
+    for class in classes.as_array().unwrap() {
+        let class_id: Vec<u32> = serde_json::from_value(class["classId"].clone()).unwrap();
+        let inherited = client
+            .get_control_class(CLASS_MANAGER_OID, &class_id, true)
+            .await
+            .unwrap();
+        let own = client
+            .get_control_class(CLASS_MANAGER_OID, &class_id, false)
+            .await
+            .unwrap();
+        assert_eq!(inherited.class_id, class_id);
+        assert!(inherited.properties.len() >= own.properties.len());
+        assert!(
+            inherited
+                .properties
+                .iter()
+                .any(|property| property.id == id(1, 1)),
+            "{} should inherit classId",
+            inherited.name
+        );
+    }
+
+    let datatypes = client.get(CLASS_MANAGER_OID, id(3, 2)).await.unwrap();
+    for datatype in datatypes.as_array().unwrap() {
+        let name = datatype["name"].as_str().unwrap();
+        let descriptor = client
+            .command(
+                CLASS_MANAGER_OID,
+                id(3, 2),
+                json!({ "name": name, "includeInherited": true }),
+            )
+            .await
+            .unwrap()
+            .into_value()
+            .unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert_eq!(descriptor["name"], json!(name));
+    }
+
+    let result = client
+        .get_control_class(CLASS_MANAGER_OID, &[1, 99], true)
+        .await;
+    assert!(matches!(result, Err(ClientError::Method { .. })));
+}
+
+#[tokio::test]
+async fn property_changes_are_notified_to_subscribers() {
+    let mut client = connect().await;
+
+    assert_eq!(
+        client.subscribe(&[WORKER_OID]).await.unwrap(),
+        vec![WORKER_OID]
+    );
+
+    client
+        .set(WORKER_OID, id(1, 6), json!("Renamed worker"))
+        .await
+        .unwrap();
+    let event = client.wait_for_change(WORKER_OID, id(1, 6)).await.unwrap();
+    assert!(matches!(
+        event.event_data.change_type,
+        NcPropertyChangeType::ValueChanged
+    ));
+    assert_eq!(event.event_data.value, json!("Renamed worker"));
+
+    // Objects the connection did not subscribe to stay silent
+    client
+        .set(NESTED_WORKER_OID, id(1, 6), json!("Not notified"))
+        .await
+        .unwrap();
+    client
+        .set(WORKER_OID, id(2, 1), json!(false))
+        .await
+        .unwrap();
+    let event = client.next_notification().await.unwrap();
+    assert_eq!(event.oid, WORKER_OID);
+    assert_eq!(event.event_data.property_id, id(2, 1));
+    assert_eq!(
+        client.get(WORKER_OID, id(2, 1)).await.unwrap(),
+        json!(false)
+    );
+}
+
+#[tokio::test]
+async fn removed_objects_leave_their_block_and_the_subscriptions() {
+    let mut config = Config::default();
+    config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    config.server.port = 0;
+    let server = DeviceServer::builder(config).build().await.unwrap();
+    let state = server.state().clone();
+    let url = format!("ws://{}/ws", server.local_addr());
+    tokio::spawn(server.serve());
+    let mut client = ControlClient::connect(&url).await.unwrap();
+
+    assert_eq!(
+        client
+            .subscribe(&[BLOCK_OID, NESTED_WORKER_OID])
+            .await
+            .unwrap(),
+        vec![BLOCK_OID, NESTED_WORKER_OID]
+    );
+    assert!(state.remove_member(NESTED_WORKER_OID).await.is_some());
+    assert!(state.remove_member(NESTED_WORKER_OID).await.is_none());
+
+    let event = client.wait_for_change(BLOCK_OID, id(2, 2)).await.unwrap();
+    let members = event.event_data.value.as_array().unwrap();
+    assert!(members.iter().all(|m| m["oid"] != json!(NESTED_WORKER_OID)));
+    let subscriptions: Vec<Vec<u64>> = state
+        .connections
+        .read()
+        .await
+        .values()
+        .map(|conn| conn.subscriptions())
+        .collect();
+    assert_eq!(subscriptions, vec![vec![BLOCK_OID]]);
+    let result = client.get(NESTED_WORKER_OID, id(1, 6)).await;
+    assert!(matches!(result, Err(ClientError::Method { .. })));
+}
+
+#[tokio::test]
+async fn objects_added_to_a_subscribed_block_are_notified_in_subtree_mode() {
+    let mut config = Config::default();
+    config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    config.server.port = 0;
+    let server = DeviceServer::builder(config).build().await.unwrap();
+    let state = server.state().clone();
+    let url = format!("ws://{}/ws", server.local_addr());
+    tokio::spawn(server.serve());
+    let mut client = ControlClient::connect(&url).await.unwrap();
+
+    client
+        .send_text(
+            json!({
+                "messageType": MESSAGE_TYPE_SUBSCRIPTION,
+                "subscriptions": [BLOCK_OID],
+                "subtree": true,
+            })
+            .to_string(),
+        )
+        .await
+        .unwrap();
+    // Answered in order, so the subscription is in place once this returns
+    client.get(BLOCK_OID, id(1, 6)).await.unwrap();
+
+    const ADDED_OID: u64 = 50;
+    {
+        let mut root = state.root_block.lock().await;
+        let block = root
+            .find_member_mut(BLOCK_OID)
+            .and_then(|member| member.as_any_mut().downcast_mut::<NcBlock>())
+            .unwrap();
+        block.add_member(Box::new(NcWorker::new(
+            vec![1, 2],
+            ADDED_OID,
+            true,
+            Some(BLOCK_OID),
+            "added-worker",
+            None,
+            None,
+            None,
+            state.event_tx.clone(),
+        )));
+    }
+    let event = client.wait_for_change(BLOCK_OID, id(2, 2)).await.unwrap();
+    let members = event.event_data.value.as_array().unwrap();
+    assert!(members.iter().any(|m| m["oid"] == json!(ADDED_OID)));
+
+    client
+        .set(ADDED_OID, id(1, 6), json!("Added later"))
+        .await
+        .unwrap();
+    let event = client.wait_for_change(ADDED_OID, id(1, 6)).await.unwrap();
+    assert_eq!(event.event_data.value, json!("Added later"));
+}
+
+#[tokio::test]
+async fn commands_are_matched_to_their_responses() {
+    let client = connect().await;
+
+    let oids = [ROOT_OID, DEVICE_MANAGER_OID, CLASS_MANAGER_OID, WORKER_OID];
+    let values =
+        futures_util::future::join_all(oids.iter().map(|oid| client.get(*oid, id(1, 2)))).await;
+    for (oid, value) in oids.iter().zip(values) {
+        assert_eq!(value.unwrap(), json!(oid));
+    }
+}
+
+#[tokio::test]
+async fn malformed_messages_get_an_error_message() {
+    let mut client = connect().await;
+
+    client.send_text("not json".to_string()).await.unwrap();
+    let error = client.next_error().await.unwrap();
+    assert!(matches!(error, ClientError::Device { status: 400, .. }));
+
+    // The connection stays usable
+    assert_eq!(
+        client.get(ROOT_OID, id(1, 2)).await.unwrap(),
+        json!(ROOT_OID)
+    );
+}
+
+#[tokio::test]
+async fn oversized_messages_close_the_connection() {
+    let url = start_device(|config| {
+        config.websocket = toml::from_str("max_message_size = 1024").unwrap();
+    })
+    .await;
+    let mut client = ControlClient::connect(&url).await.unwrap();
+
+    // A subscription message padded to the given size
+    let message = |size: usize| {
+        let message = json!({
+            "messageType": MESSAGE_TYPE_SUBSCRIPTION,
+            "subscriptions": [WORKER_OID],
+            "padding": "",
+        })
+        .to_string();
+        let padding = "x".repeat(size - message.len());
+        message.replace(r#""padding":"""#, &format!(r#""padding":"{padding}""#))
+    };
+
+    // A message of exactly the configured size is still accepted
+    client.send_text(message(1024)).await.unwrap();
+    client
+        .set(WORKER_OID, id(1, 6), json!("Accepted"))
+        .await
+        .unwrap();
+    client.wait_for_change(WORKER_OID, id(1, 6)).await.unwrap();
+
+    client.send_text(message(1025)).await.unwrap();
+    let error = client.next_error().await.unwrap();
+    assert!(matches!(error, ClientError::Device { status: 413, .. }));
+    let close = client.closed().await.unwrap().expect("close frame");
+    assert_eq!(close.code, 1009);
+}
+
+#[tokio::test]
+async fn concurrent_upgrades_never_exceed_the_connection_limit() {
+    let url = start_device(|config| config.websocket.max_connections = Some(2)).await;
+    let attempts = (0..8).map(|_| ControlClient::connect(&url));
+    let (mut connected, refused): (Vec<_>, Vec<_>) = futures_util::future::join_all(attempts)
+        .await
+        .into_iter()
+        .partition(Result::is_ok);
+    assert_eq!(connected.len(), 2);
+    assert!(
+        refused
+            .iter()
+            .all(|result| matches!(result, Err(ClientError::Connect(_))))
+    );
+
+    // Closing a connection frees its slot
+    connected.pop().unwrap().unwrap().close().await.unwrap();
+    let mut client = None;
+    for _ in 0..50 {
+        if let Ok(connected) = ControlClient::connect(&url).await {
+            client = Some(connected);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let client = client.expect("a slot should be free again");
+    assert_eq!(
+        client.get(ROOT_OID, id(1, 2)).await.unwrap(),
+        json!(ROOT_OID)
+    );
+}
+
+#[tokio::test]
+async fn device_manager_describes_the_device_and_resets_it() {
+    let url = start_device(|_| {}).await;
+    let client = ControlClient::connect(&url).await.unwrap();
+
+    let manufacturer = client.get(DEVICE_MANAGER_OID, id(3, 2)).await.unwrap();
+    assert_eq!(manufacturer["name"], json!("Your Company"));
+    let serial_number = client.get(DEVICE_MANAGER_OID, id(3, 4)).await.unwrap();
+    assert_eq!(serial_number, json!("SN-123456789"));
+
+    client
+        .set(DEVICE_MANAGER_OID, id(3, 6), json!("Renamed device"))
+        .await
+        .unwrap();
+    client
+        .set(WORKER_OID, id(1, 6), json!("Renamed worker"))
+        .await
+        .unwrap();
+
+    let result = client
+        .command(DEVICE_MANAGER_OID, id(4, 1), json!({}))
+        .await
+        .unwrap();
+    assert!(result.is_success());
+    let close = client.closed().await.unwrap().expect("close frame");
+    assert_eq!(close.code, 1012);
+
+    // The model is rebuilt from scratch
+    let client = ControlClient::connect(&url).await.unwrap();
+    assert_eq!(
+        client.get(WORKER_OID, id(1, 6)).await.unwrap(),
+        json!("My worker 01")
+    );
+}
+
+#[tokio::test]
+async fn reset_reports_initializing_then_normal_operation() {
+    let url = start_device(|_| {}).await;
+    let mut client = ControlClient::connect(&url).await.unwrap();
+    let operational_state = client.get(DEVICE_MANAGER_OID, id(3, 8)).await.unwrap();
+    assert_eq!(operational_state["generic"], json!(1));
+
+    client.subscribe(&[DEVICE_MANAGER_OID]).await.unwrap();
+    let result = client
+        .command(DEVICE_MANAGER_OID, id(4, 1), json!({}))
+        .await
+        .unwrap();
+    assert!(result.is_success());
+    let event = client
+        .wait_for_change(DEVICE_MANAGER_OID, id(3, 8))
+        .await
+        .unwrap();
+    assert_eq!(event.event_data.value["generic"], json!(2));
+    let close = client.closed().await.unwrap().expect("close frame");
+    assert_eq!(close.code, 1012);
+
+    let client = ControlClient::connect(&url).await.unwrap();
+    let operational_state = client.get(DEVICE_MANAGER_OID, id(3, 8)).await.unwrap();
+    assert_eq!(operational_state["generic"], json!(1));
+}
+
+#[tokio::test]
+async fn ident_beacon_switches_itself_off() {
+    let url = start_device(|config| {
+        config.device.identification_timeout = Duration::from_secs(1);
+    })
+    .await;
+    let mut client = ControlClient::connect(&url).await.unwrap();
+    client.subscribe(&[IDENT_BEACON_OID]).await.unwrap();
+
+    assert_eq!(
+        client.get(IDENT_BEACON_OID, id(3, 1)).await.unwrap(),
+        json!(false)
+    );
+    client
+        .set(IDENT_BEACON_OID, id(3, 1), json!(true))
+        .await
+        .unwrap();
+    let event = client
+        .wait_for_change(IDENT_BEACON_OID, id(3, 1))
+        .await
+        .unwrap();
+    assert_eq!(event.event_data.value, json!(true));
+
+    let event = client
+        .wait_for_change(IDENT_BEACON_OID, id(3, 1))
+        .await
+        .unwrap();
+    assert_eq!(event.event_data.value, json!(false));
+}
+
+#[tokio::test]
+async fn bulk_properties_manager_gets_and_sets_by_path() {
+    let client = connect().await;
+
+    let holder = client
+        .command(
+            BULK_PROPERTIES_MANAGER_OID,
+            id(3, 1),
+            json!({ "path": ["root", "my-block-01"], "recurse": true }),
+        )
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    let objects = holder["values"].as_array().unwrap();
+    assert_eq!(objects.len(), 3);
+
+    let mut data_set = client
+        .command(
+            BULK_PROPERTIES_MANAGER_OID,
+            id(3, 1),
+            json!({ "path": ["root", "my-worker-01"], "recurse": false }),
+        )
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    for property in data_set["values"][0]["values"].as_array_mut().unwrap() {
+        if property["id"] == json!(id(1, 6)) {
+            property["value"] = json!("Restored worker");
+        }
+    }
+    let arguments = json!({
+        "dataSet": data_set,
+        "path": ["root", "my-worker-01"],
+        "recurse": false,
+        "restoreMode": 1,
+    });
+
+    let validations = client
+        .command(BULK_PROPERTIES_MANAGER_OID, id(3, 2), arguments.clone())
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert!(!validations.as_array().unwrap().is_empty());
+    assert_eq!(
+        client.get(WORKER_OID, id(1, 6)).await.unwrap(),
+        json!("My worker 01")
+    );
+
+    client
+        .command(BULK_PROPERTIES_MANAGER_OID, id(3, 3), arguments.clone())
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(
+        client.get(WORKER_OID, id(1, 6)).await.unwrap(),
+        json!("Restored worker")
+    );
+
+    // No object of the example device is rebuildable, Rebuild falls back to Modify
+    let mut rebuild = arguments;
+    rebuild["restoreMode"] = json!(2);
+    for property in rebuild["dataSet"]["values"][0]["values"]
+        .as_array_mut()
+        .unwrap()
+    {
+        if property["id"] == json!(id(1, 6)) {
+            property["value"] = json!("Rebuilt worker");
+        }
+    }
+    let validations = client
+        .command(BULK_PROPERTIES_MANAGER_OID, id(3, 3), rebuild)
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(validations[0]["status"], json!(200));
+    assert_eq!(
+        validations[0]["statusMessage"],
+        json!("Object is not rebuildable, values are restored as in Modify")
+    );
+    assert_eq!(
+        client.get(WORKER_OID, id(1, 6)).await.unwrap(),
+        json!("Rebuilt worker")
+    );
+
+    let result = client
+        .command(
+            BULK_PROPERTIES_MANAGER_OID,
+            id(3, 1),
+            json!({ "path": ["root", "missing"], "recurse": false }),
+        )
+        .await
+        .unwrap();
+    assert!(!result.is_success());
+}
+
+#[tokio::test]
+async fn channel_mapping_workers_point_at_their_io() {
+    let client = connect().await;
+
+    let members = client
+        .get_member_descriptors(CHANNEL_MAPPING_BLOCK_OID, false)
+        .await
+        .unwrap();
+    let roles: Vec<&str> = members.iter().map(|member| member.role.as_str()).collect();
+    assert_eq!(
+        roles,
+        vec![
+            "input-receiver-audio",
+            "input-test-tone",
+            "output-sender-audio"
+        ]
+    );
+
+    for member in &members {
+        let touchpoints = client.get(member.oid, id(1, 7)).await.unwrap();
+        let touchpoint = &touchpoints[0];
+        assert_eq!(
+            touchpoint["contextNamespace"],
+            json!("x-nmos/channelmapping")
+        );
+        let io_id = member.role.split_once('-').unwrap().1;
+        assert_eq!(touchpoint["resource"]["ioId"], Value::from(io_id));
+    }
+}
@@ -0,0 +1,120 @@
+//! Persists property values of the example model file across restarts of the device
+
+use nmos_control_rusty_device::{
+    DeviceServer, client::ControlClient, config::Config, data_types::NcElementId,
+    persistence::PersistenceConfig,
+};
+use serde_json::{Value, json};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const DEVICE_MANAGER_OID: u64 = 2;
+const NESTED_WORKER_OID: u64 = 8;
+
+fn id(level: u32, index: u32) -> NcElementId {
+    NcElementId { level, index }
+}
+
+/// Serves the example model file with persistence enabled, returns the URL of its WebSocket
+async fn start_device(file: &Path) -> String {
+    let mut config = Config::default();
+    config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    config.server.port = 0;
+    config.model_file = Some(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("model.example.yaml"));
+    config.persistence = Some(PersistenceConfig {
+        file: file.to_path_buf(),
+        exclude: Vec::new(),
+    });
+    let server = DeviceServer::builder(config).build().await.unwrap();
+    let url = format!("ws://{}/ws", server.local_addr());
+    tokio::spawn(server.serve());
+    url
+}
+
+/// Waits for the persistence file to hold a value for a role path and property
+async fn persisted(file: &Path, role_path: &str, property: &str) -> Value {
+    for _ in 0..50 {
+        if let Ok(text) = std::fs::read_to_string(file) {
+            let journal: Value = serde_json::from_str(&text).unwrap();
+            if journal[role_path].get(property).is_some() {
+                return journal;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("{role_path}/{property} was not persisted");
+}
+
+#[tokio::test]
+async fn initial_values_of_the_model_file_are_not_persisted() {
+    let file = std::env::temp_dir().join(format!("persistence-{}.json", uuid::Uuid::new_v4()));
+    let url = start_device(&file).await;
+    let client = ControlClient::connect(&url).await.unwrap();
+    client
+        .set(NESTED_WORKER_OID, id(1, 6), json!("Persisted label"))
+        .await
+        .unwrap();
+    let journal = persisted(&file, "root.my-block-01.my-worker-02", "1p6").await;
+    // `enabled` of my-worker-01 is set by the model file only
+    assert_eq!(journal.get("root.my-worker-01"), None);
+
+    // Nor are they once a reset rebuilt the model
+    client
+        .command(DEVICE_MANAGER_OID, id(4, 1), json!({}))
+        .await
+        .unwrap();
+    client.closed().await.unwrap();
+    let client = ControlClient::connect(&url).await.unwrap();
+    assert_eq!(
+        client.get(NESTED_WORKER_OID, id(1, 6)).await.unwrap(),
+        json!("Persisted label")
+    );
+    client
+        .set(DEVICE_MANAGER_OID, id(3, 6), json!("Renamed device"))
+        .await
+        .unwrap();
+    let journal = persisted(&file, "root.DeviceManager", "3p6").await;
+    assert_eq!(journal.get("root.my-worker-01"), None);
+
+    let _ = std::fs::remove_file(&file);
+}
+
+#[tokio::test]
+async fn reset_restores_persisted_values_and_reports_the_request() {
+    let file = std::env::temp_dir().join(format!("persistence-{}.json", uuid::Uuid::new_v4()));
+    let url = start_device(&file).await;
+    let client = ControlClient::connect(&url).await.unwrap();
+    assert_eq!(
+        client.get(DEVICE_MANAGER_OID, id(3, 9)).await.unwrap(),
+        json!(1)
+    );
+    client
+        .set(DEVICE_MANAGER_OID, id(3, 6), json!("Renamed device"))
+        .await
+        .unwrap();
+    persisted(&file, "root.DeviceManager", "3p6").await;
+
+    client
+        .command(DEVICE_MANAGER_OID, id(4, 1), json!({}))
+        .await
+        .unwrap();
+    client.closed().await.unwrap();
+
+    let client = ControlClient::connect(&url).await.unwrap();
+    assert_eq!(
+        client.get(DEVICE_MANAGER_OID, id(3, 6)).await.unwrap(),
+        json!("Renamed device")
+    );
+    assert_eq!(
+        client.get(DEVICE_MANAGER_OID, id(3, 9)).await.unwrap(),
+        json!(4)
+    );
+    // The reset cause marker is consumed by the restart
+    let marker = file.with_extension("json.reset-cause");
+    assert!(!marker.exists());
+
+    let _ = std::fs::remove_file(&file);
+}
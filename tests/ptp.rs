@@ -0,0 +1,77 @@
+//! Follows the state of a PTP clock read from a status file into the node resource
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+};
+use nmos_control_rusty_device::{DeviceServer, config::Config, ptp::PtpConfig};
+use serde_json::{Value, json};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::Path,
+    time::Duration,
+};
+use tower::ServiceExt;
+
+async fn node(router: &Router) -> Value {
+    let request = Request::get("/x-nmos/node/v1.3/self")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+fn write_status(file: &Path, gmid: &str, locked: bool) {
+    let status = json!({ "gmid": gmid, "locked": locked, "traceable": false });
+    std::fs::write(file, status.to_string()).unwrap();
+}
+
+#[tokio::test]
+async fn clock_changes_give_the_node_a_new_version() {
+    let file = std::env::temp_dir().join(format!("ptp-{}.json", uuid::Uuid::new_v4()));
+    write_status(&file, "08-00-11-ff-fe-21-e1-b0", true);
+
+    let mut config = Config::default();
+    config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    config.server.port = 0;
+    config.node.ptp = Some(PtpConfig {
+        clock: "clk1".to_string(),
+        pmc_socket: None,
+        file: Some(file.clone()),
+        poll_interval: Duration::from_millis(50),
+    });
+    let mut server = DeviceServer::builder(config).build().await.unwrap();
+    server.start().await.unwrap();
+    let router = server.router();
+
+    let before = node(&router).await;
+    let clock = |node: &Value| {
+        node["clocks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|clock| clock["name"] == "clk1")
+            .cloned()
+            .unwrap()
+    };
+    assert_eq!(clock(&before)["gmid"], "08-00-11-ff-fe-21-e1-b0");
+    assert_eq!(clock(&before)["locked"], true);
+
+    // An unchanged clock leaves the version alone
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(node(&router).await["version"], before["version"]);
+
+    write_status(&file, "08-00-11-ff-fe-21-e1-b1", false);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let after = node(&router).await;
+    assert_eq!(clock(&after)["gmid"], "08-00-11-ff-fe-21-e1-b1");
+    assert_eq!(clock(&after)["locked"], false);
+    assert_ne!(after["version"], before["version"]);
+
+    let _ = std::fs::remove_file(&file);
+}
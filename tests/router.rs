@@ -0,0 +1,79 @@
+//! Serves the routes of a device with an application of its own instead of DeviceServer::serve
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use nmos_control_rusty_device::{
+    DeviceServer, client::ControlClient, config::Config, data_types::NcElementId,
+    websocket::WebSocketConfig,
+};
+use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+const DEVICE_MANAGER_OID: u64 = 2;
+const WORKER_OID: u64 = 5;
+
+fn config() -> Config {
+    let mut config = Config::default();
+    config.server.bind = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    config.server.port = 0;
+    config
+}
+
+#[tokio::test]
+async fn router_served_on_its_own_listener_notifies_subscribers() {
+    let mut server = DeviceServer::builder(config()).build().await.unwrap();
+    server.start().await.unwrap();
+
+    // Plain serving, without the connect info DeviceServer::serve provides
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+    tokio::spawn(axum::serve(listener, server.router()).into_future());
+
+    let mut client = ControlClient::connect(&url).await.unwrap();
+    let operational_state = client
+        .get(DEVICE_MANAGER_OID, NcElementId { level: 3, index: 8 })
+        .await
+        .unwrap();
+    assert_eq!(operational_state["generic"], json!(1));
+
+    client.subscribe(&[WORKER_OID]).await.unwrap();
+    let user_label = NcElementId { level: 1, index: 6 };
+    client
+        .set(WORKER_OID, user_label.clone(), json!("Served elsewhere"))
+        .await
+        .unwrap();
+    let event = client
+        .wait_for_change(WORKER_OID, user_label)
+        .await
+        .unwrap();
+    assert_eq!(event.event_data.value, json!("Served elsewhere"));
+}
+
+#[tokio::test]
+async fn connection_diagnostics_are_opt_in() {
+    let request = || {
+        Request::get("/diagnostics/connections")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let server = DeviceServer::builder(config()).build().await.unwrap();
+    let response = server.router().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let websocket = WebSocketConfig {
+        diagnostics: true,
+        ..WebSocketConfig::default()
+    };
+    let server = DeviceServer::builder(config())
+        .websocket(websocket)
+        .build()
+        .await
+        .unwrap();
+    let response = server.router().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}